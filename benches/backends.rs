@@ -0,0 +1,91 @@
+//! Compares the RawFused backends. Run with `cargo bench --bench backends`.
+
+use safe_once::api::once::Once;
+use safe_once::api::raw::RawFusedConst;
+#[cfg(feature = "dyn-backend")]
+use safe_once::sync::DynRawFused;
+#[cfg(feature = "futex")]
+use safe_once::sync::RawFusedFutex;
+use safe_once::sync::{RawFusedLock, RawFusedStdThread};
+use std::hint::black_box;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 1_000_000;
+const CELLS: usize = 10_000;
+const THREADS: usize = 8;
+
+// Reads of an initialized cell.
+fn initialized<R: RawFusedConst + Sync>() -> Duration {
+    let once = Once::<R, usize>::new();
+    once.get_or_init(|| 1);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(black_box(&once).get_or_init(|| unreachable!()));
+    }
+    start.elapsed() / ITERATIONS as u32
+}
+
+// Initialization of uncontended cells.
+fn uncontended<R: RawFusedConst + Sync>() -> Duration {
+    let onces = (0..CELLS)
+        .map(|_| Once::<R, usize>::new())
+        .collect::<Vec<_>>();
+    let start = Instant::now();
+    for (i, once) in onces.iter().enumerate() {
+        black_box(once.get_or_init(|| i));
+    }
+    start.elapsed() / CELLS as u32
+}
+
+// Initialization of cells that every thread races to initialize, with a slow initializer so
+// that the losers park.
+fn contended<R: RawFusedConst + Send + Sync>() -> Duration {
+    let onces = Arc::new(
+        (0..CELLS / 10)
+            .map(|_| Once::<R, usize>::new())
+            .collect::<Vec<_>>(),
+    );
+    let barrier = Arc::new(Barrier::new(THREADS));
+    let start = Instant::now();
+    let threads = (0..THREADS)
+        .map(|_| {
+            let onces = onces.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                for (i, once) in onces.iter().enumerate() {
+                    barrier.wait();
+                    black_box(once.get_or_init(|| {
+                        thread::sleep(Duration::from_micros(20));
+                        i
+                    }));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    start.elapsed() / onces.len() as u32
+}
+
+fn report<R: RawFusedConst + Send + Sync>(name: &str) {
+    println!(
+        "{:<20} initialized {:>8.2?}  uncontended {:>8.2?}  contended {:>8.2?}",
+        name,
+        initialized::<R>(),
+        uncontended::<R>(),
+        contended::<R>()
+    );
+}
+
+fn main() {
+    report::<RawFusedLock>("RawFusedLock");
+    report::<RawFusedStdThread>("RawFusedStdThread");
+    #[cfg(feature = "futex")]
+    report::<RawFusedFutex>("RawFusedFutex");
+    // Reported last, so that its uncontended cells are measured before any other thread uses it.
+    #[cfg(feature = "dyn-backend")]
+    report::<DynRawFused>("DynRawFused");
+}
@@ -0,0 +1,47 @@
+//! Measures each layer of the fast path separately. Run with
+//! `RUSTFLAGS="--cfg safe_once_bench" cargo bench --bench layers`.
+
+#[cfg(safe_once_bench)]
+fn run() {
+    use safe_once::api::raw::RawFusedConst;
+    use safe_once::sync::bench;
+    use safe_once::sync::{OnceLock, RawFusedLock};
+    use std::hint::black_box;
+    use std::time::Instant;
+
+    const ITERATIONS: u32 = 100_000_000;
+
+    fn measure(name: &str, mut f: impl FnMut()) {
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            f();
+        }
+        let elapsed = start.elapsed().as_secs_f64() * 1e9 / ITERATIONS as f64;
+        println!("{:<16} {:>6.2}ns", name, elapsed);
+    }
+
+    let lock = RawFusedLock::READ;
+    let once = OnceLock::new_init(1usize);
+    measure("state_relaxed", || {
+        black_box(bench::state_relaxed(black_box(&lock)));
+    });
+    measure("state_acquire", || {
+        black_box(bench::state_acquire(black_box(&lock)));
+    });
+    measure("parking_ready", || {
+        black_box(bench::parking_ready(black_box(&lock)));
+    });
+    measure("raw_read", || {
+        black_box(bench::raw_read(black_box(&lock)));
+    });
+    measure("once_get", || {
+        black_box(bench::once_get(black_box(&once)));
+    });
+}
+
+fn main() {
+    #[cfg(safe_once_bench)]
+    run();
+    #[cfg(not(safe_once_bench))]
+    eprintln!("build with RUSTFLAGS=\"--cfg safe_once_bench\" to measure the fast path layers");
+}
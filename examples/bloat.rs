@@ -0,0 +1,42 @@
+//! Instantiates the lazy initialization paths for many types, for comparing code size with
+//! `cargo bloat --release --example bloat --filter safe_once`.
+
+use safe_once::sync::{LazyLock, OnceLock};
+use std::hint::black_box;
+
+macro_rules! instantiate {
+    ($($name:ident: $t:ty = $v:expr;)*) => {
+        $(
+            static $name: OnceLock<$t> = OnceLock::new();
+        )*
+        fn run_once() {
+            $(
+                black_box($name.get_or_init(|| black_box($v)));
+            )*
+        }
+        fn run_lazy() {
+            $(
+                let lazy = LazyLock::<$t, _>::new(|| black_box($v));
+                black_box(lazy.forced());
+            )*
+        }
+    };
+}
+
+instantiate! {
+    A: u8 = 1;
+    B: u16 = 2;
+    C: u32 = 3;
+    D: u64 = 4;
+    E: u128 = 5;
+    F: String = String::from("f");
+    G: Vec<u8> = vec![7];
+    H: (u32, u64) = (8, 8);
+    I: [u64; 4] = [9; 4];
+    J: Option<Box<u32>> = Some(Box::new(10));
+}
+
+fn main() {
+    run_once();
+    run_lazy();
+}
@@ -0,0 +1,132 @@
+//! Alignment control for values stored in cells.
+//!
+//! Aligning a whole cell does not align the value inside it, because the cell's state may be
+//! placed first. Instead, [Aligned] raises the alignment of the value itself, so that a
+//! `OnceLock<Aligned<T, 64>>` stores its value on a 64-byte boundary.
+//! ```
+//! use safe_once::sync::OnceLockAligned;
+//! use safe_once::api::aligned::Aligned;
+//! static TABLE: OnceLockAligned<[f32; 16], 64> = OnceLockAligned::new();
+//! let table = TABLE.get_or_init(|| Aligned::new([1.0; 16]));
+//! assert_eq!(table as *const _ as usize % 64, 0);
+//! ```
+
+use core::fmt::{Debug, Formatter};
+use core::ops::{Deref, DerefMut};
+
+/// Selects a zero-sized marker type with a given alignment.
+pub trait SupportedAlignment {
+    type Marker: Copy;
+}
+
+/// The alignment `N`. [SupportedAlignment] is implemented for powers of two up to 4096.
+pub struct Alignment<const N: usize>;
+
+macro_rules! alignments {
+    ($($n:literal => $marker:ident),*) => {
+        $(
+            #[doc(hidden)]
+            #[repr(align($n))]
+            #[derive(Copy, Clone)]
+            pub struct $marker;
+
+            impl SupportedAlignment for Alignment<$n> {
+                type Marker = $marker;
+            }
+        )*
+    };
+}
+
+alignments!(
+    1 => Align1, 2 => Align2, 4 => Align4, 8 => Align8, 16 => Align16, 32 => Align32,
+    64 => Align64, 128 => Align128, 256 => Align256, 512 => Align512, 1024 => Align1024,
+    2048 => Align2048, 4096 => Align4096
+);
+
+/// A value with an alignment of at least `N`.
+#[repr(C)]
+pub struct Aligned<T, const N: usize>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    _align: [<Alignment<N> as SupportedAlignment>::Marker; 0],
+    value: T,
+}
+
+impl<T, const N: usize> Aligned<T, N>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    pub const fn new(value: T) -> Self {
+        Aligned { _align: [], value }
+    }
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, const N: usize> Deref for Aligned<T, N>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, const N: usize> DerefMut for Aligned<T, N>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T, const N: usize> From<T> for Aligned<T, N>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    fn from(value: T) -> Self {
+        Aligned::new(value)
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for Aligned<T, N>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    fn clone(&self) -> Self {
+        Aligned::new(self.value.clone())
+    }
+}
+
+impl<T: Default, const N: usize> Default for Aligned<T, N>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    fn default() -> Self {
+        Aligned::new(T::default())
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for Aligned<T, N>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for Aligned<T, N>
+where
+    Alignment<N>: SupportedAlignment,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for Aligned<T, N> where Alignment<N>: SupportedAlignment {}
@@ -0,0 +1,93 @@
+//! A value that is borrowed until it is customized, and owned afterwards.
+//!
+//! A [LazyCow] starts as a reference to a shared default. The first call to [LazyCow::modify]
+//! clones the default, applies the modification, and fuses the result, so only the rare users
+//! that customize pay for a copy.
+//! ```
+//! use safe_once::sync::LazyLockCow;
+//! static DEFAULT: Vec<&str> = Vec::new();
+//! let config = LazyLockCow::new(&DEFAULT);
+//! assert!(config.get().is_empty());
+//! config.modify(|config| config.push("verbose"));
+//! assert_eq!(config.get(), &["verbose"]);
+//! assert!(DEFAULT.is_empty());
+//! ```
+
+use crate::api::fused::{Fused, FusedEntry};
+use crate::api::raw::{RawFused, RawFusedConst};
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+
+/// A reference to a default that becomes an owned, fused value when first modified.
+pub struct LazyCow<'a, R: RawFused, T> {
+    borrowed: &'a T,
+    owned: Fused<R, Option<T>>,
+}
+
+impl<'a, R: RawFusedConst, T> LazyCow<'a, R, T> {
+    pub const fn new(borrowed: &'a T) -> Self {
+        LazyCow {
+            borrowed,
+            owned: Fused::new(None),
+        }
+    }
+}
+
+impl<'a, R: RawFused, T> LazyCow<'a, R, T> {
+    /// The owned value if modified, and otherwise the default. While another thread is
+    /// modifying, this returns the default.
+    pub fn get(&self) -> &T {
+        match self.owned.try_read() {
+            Some(Some(owned)) => owned,
+            _ => self.borrowed,
+        }
+    }
+    /// Whether the value has been modified.
+    pub fn is_owned(&self) -> bool {
+        matches!(self.owned.try_read(), Some(Some(_)))
+    }
+    /// The owned value if modified, and otherwise a clone of the default.
+    pub fn into_owned(self) -> T
+    where
+        T: Clone,
+    {
+        match self.owned.into_inner() {
+            (_, Some(owned)) => owned,
+            (_, None) => self.borrowed.clone(),
+        }
+    }
+}
+
+impl<'a, R: RawFused, T: Clone> LazyCow<'a, R, T> {
+    /// If the value has not been modified, clone the default, apply `modify`, and fuse the
+    /// result. Returns the owned value, which a later call cannot modify again.
+    #[track_caller]
+    pub fn modify(&self, modify: impl FnOnce(&mut T)) -> &T {
+        let owned = match self.owned.write() {
+            FusedEntry::Read(owned) => owned,
+            FusedEntry::Write(mut guard) => {
+                let mut value = self.borrowed.clone();
+                modify(&mut value);
+                *guard = Some(value);
+                guard.fuse()
+            }
+        };
+        owned.as_ref().unwrap()
+    }
+}
+
+impl<'a, R: RawFused, T> Deref for LazyCow<'a, R, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<'a, R: RawFused, T: Debug> Debug for LazyCow<'a, R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyCow")
+            .field("value", self.get())
+            .field("owned", &self.is_owned())
+            .finish()
+    }
+}
@@ -0,0 +1,106 @@
+//! The cells that each thread is initializing, used to describe same-thread cycles.
+//!
+//! [Fused::read_or_fuse](crate::api::fused::Fused::read_or_fuse), and so every `get_or_init`
+//! and `Lazy` dereference built on it, pushes the cell onto a thread-local stack while its
+//! initializer runs. When a cell is requested again by its own initializer, the entries from that
+//! cell to the top of the stack form the cycle.
+//!
+//! The stack is intrusive: each frame lives on the stack of the call that pushed it and links to
+//! the frame below, so pushing never allocates. This matters for cells forced from within a
+//! global allocator.
+
+use core::cell::Cell;
+use core::fmt::Write;
+use core::panic::Location;
+use core::ptr::null;
+
+/// A cell whose initializer is running on the current thread.
+pub(crate) struct Frame {
+    addr: *const u8,
+    type_name: &'static str,
+    location: &'static Location<'static>,
+    below: Cell<*const Frame>,
+}
+
+thread_local!(static TOP: Cell<*const Frame> = const { Cell::new(null()) });
+
+impl Frame {
+    /// A frame for the cell at `addr`, holding a `type_name`, that began initializing at
+    /// `location`.
+    pub(crate) fn new(
+        addr: *const u8,
+        type_name: &'static str,
+        location: &'static Location<'static>,
+    ) -> Frame {
+        Frame {
+            addr,
+            type_name,
+            location,
+            below: Cell::new(null()),
+        }
+    }
+
+    /// Push this frame until the returned entry is dropped.
+    pub(crate) fn push(&self) -> Entry<'_> {
+        // Without a thread-local, cycles are still detected but not described.
+        let _ = TOP.try_with(|top| self.below.set(top.replace(self)));
+        Entry(self)
+    }
+}
+
+/// Removes the frame from the stack when dropped.
+pub(crate) struct Entry<'a>(&'a Frame);
+
+impl Drop for Entry<'_> {
+    fn drop(&mut self) {
+        let this: *const Frame = self.0;
+        let _ = TOP.try_with(|top| {
+            // Entries are dropped in order, unless the push above found no thread-local.
+            if top.get() == this {
+                top.set(self.0.below.get());
+            }
+        });
+    }
+}
+
+/// Describe the cycle that begins with the cell at `addr`, which was requested again at
+/// `caller`, if the current thread is initializing it.
+pub(crate) fn describe(addr: *const u8, caller: &Location) -> Option<String> {
+    let mut frames = vec![];
+    let mut frame = TOP.try_with(|top| top.get()).ok()?;
+    // Every linked frame is still on this thread's stack, below the current call.
+    while let Some(current) = unsafe { frame.as_ref() } {
+        frames.push(current);
+        if current.addr == addr {
+            break;
+        }
+        frame = current.below.get();
+    }
+    let start = frames.last().filter(|x| x.addr == addr)?;
+    let mut message = String::from("cycle:");
+    for cell in frames.iter().rev() {
+        write!(
+            message,
+            "\n  {} initializing since {}",
+            name(cell.addr, cell.type_name),
+            cell.location
+        )
+        .unwrap();
+    }
+    write!(
+        message,
+        "\n  {} requested again at {}",
+        name(addr, start.type_name),
+        caller
+    )
+    .unwrap();
+    Some(message)
+}
+
+// The registered name of the static containing `addr`, if any, and the type of its value.
+fn name(addr: *const u8, type_name: &'static str) -> String {
+    match crate::registry::find_containing(addr) {
+        Some(registration) => format!("{}: {}", registration.name(), type_name),
+        None => format!("{:p}: {}", addr, type_name),
+    }
+}
@@ -1,44 +1,257 @@
-use crate::api::raw::{RawFused, RawFusedState};
-use std::cell::UnsafeCell;
-use std::fmt::{Debug, Formatter};
-use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut};
-use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::sync::{PoisonError, TryLockError};
-use std::thread::panicking;
-
-// A mutex that can be made permanently read-only.
-pub struct Fused<R: RawFused, T> {
+#[cfg(feature = "content-hash")]
+use crate::api::hash::FnvHasher;
+#[cfg(feature = "on-fuse")]
+use crate::api::hooks::FuseHooks;
+use crate::api::raw::panicking;
+use crate::api::raw::{check_read, check_write_locked, RawFused, RawFusedConst, RawFusedState};
+use crate::error::{LockError, PoisonError, TryLockError};
+use crate::registry::Registered;
+#[cfg(feature = "on-fuse")]
+use alloc::boxed::Box;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, DebugStruct, Display, Formatter};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::ops::{Deref, DerefMut};
+use core::panic::{Location, RefUnwindSafe, UnwindSafe};
+use core::ptr;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+// A mutex that can be made permanently read-only. `I` is the check added by
+// [Fused::with_invariant], so that other cells do not store one.
+pub struct Fused<R: RawFused, T, I: FusedInvariant<T> = ()> {
     raw: R,
     data: UnsafeCell<T>,
+    #[cfg(feature = "content-hash")]
+    hash: UnsafeCell<Option<u64>>,
+    // Callbacks registered with on_fuse, run by the caller that fuses.
+    #[cfg(feature = "on-fuse")]
+    hooks: FuseHooks<T>,
+    invariant: I,
+}
+
+/// A check applied to the value of a [Fused] when it is fused and when it is read. Implemented
+/// by `()`, which checks nothing, and by [Invariant].
+pub trait FusedInvariant<T> {
+    fn check_fused(&self, value: &T);
+    fn check_read(&self, value: &T);
+}
+
+impl<T> FusedInvariant<T> for () {
+    #[inline]
+    fn check_fused(&self, _: &T) {}
+    #[inline]
+    fn check_read(&self, _: &T) {}
+}
+
+/// The check added by [Fused::with_invariant]. Empty in release builds.
+pub struct Invariant<T> {
+    #[cfg(debug_assertions)]
+    name: &'static str,
+    #[cfg(debug_assertions)]
+    check: fn(&T) -> bool,
+    #[cfg(debug_assertions)]
+    check_reads: bool,
+    marker: PhantomData<fn(&T)>,
+}
+
+impl<T> Invariant<T> {
+    #[allow(unused_variables)]
+    const fn new(name: &'static str, check: fn(&T) -> bool, check_reads: bool) -> Self {
+        Invariant {
+            #[cfg(debug_assertions)]
+            name,
+            #[cfg(debug_assertions)]
+            check,
+            #[cfg(debug_assertions)]
+            check_reads,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> FusedInvariant<T> for Invariant<T> {
+    #[inline]
+    fn check_fused(&self, value: &T) {
+        #[cfg(debug_assertions)]
+        if !(self.check)(value) {
+            panic!("invariant of {} violated", self.name);
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = value;
+    }
+    #[inline]
+    fn check_read(&self, value: &T) {
+        #[cfg(debug_assertions)]
+        if self.check_reads {
+            self.check_fused(value);
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = value;
+    }
 }
 
 // The result of trying to lock a Fused.
-pub enum FusedEntry<'a, R: RawFused, T> {
+pub enum FusedEntry<'a, R: RawFused, T, I: FusedInvariant<T> = ()> {
     // The Fused is read-only and this is a reference to the underlying object.
     Read(&'a T),
     // The Fused is write-locked and this is a guard for mutating the underlying object.
-    Write(FusedGuard<'a, R, T>),
+    Write(FusedGuard<'a, R, T, I>),
 }
 
-// A guard for a write-lock of a Fused.
-pub struct FusedGuard<'a, R: RawFused, T> {
-    fused: Option<&'a Fused<R, T>>,
+/// A guard for a write-lock of a Fused. The guard is [Send] if the backend's
+/// [RawFused::GuardMarker] is, so a [crate::sync::FusedLock] may be finished on another thread:
+/// ```
+/// use safe_once::api::fused::FusedEntry;
+/// use safe_once::sync::FusedLock;
+/// let fused = FusedLock::new(vec![]);
+/// std::thread::scope(|s| {
+///     let FusedEntry::Write(mut guard) = fused.write() else { unreachable!() };
+///     s.spawn(move || {
+///         guard.push(1);
+///         guard.fuse();
+///     });
+/// });
+/// assert_eq!(fused.try_read(), Some(&vec![1]));
+/// ```
+/// Guards of a [crate::cell::FusedCell] cannot leave the thread:
+/// ```compile_fail
+/// use safe_once::api::fused::FusedEntry;
+/// use safe_once::cell::FusedCell;
+/// let fused = FusedCell::new(vec![]);
+/// std::thread::scope(|s| {
+///     let FusedEntry::Write(guard) = fused.write() else { unreachable!() };
+///     s.spawn(move || guard.fuse());
+/// });
+/// ```
+pub struct FusedGuard<'a, R: RawFused, T, I: FusedInvariant<T> = ()> {
+    fused: Option<&'a Fused<R, T, I>>,
     marker: PhantomData<(&'a mut T, R::GuardMarker)>,
 }
 
-impl<'a, R: RawFused, T> FusedGuard<'a, R, T> {
+impl<'a, R: RawFused, T, I: FusedInvariant<T>> FusedGuard<'a, R, T, I> {
     // Make this Fused read-only.
-    pub fn fuse(mut self) -> &'a T {
+    pub fn fuse(self) -> &'a T {
+        #[cfg(feature = "content-hash")]
+        self.set_hash(None);
+        self.fuse_impl()
+    }
+
+    // Make this Fused read-only, and record a content hash computed with `hasher`.
+    #[cfg(feature = "content-hash")]
+    pub fn fuse_hashed_with(self, mut hasher: impl Hasher) -> (&'a T, u64)
+    where
+        T: Hash,
+    {
+        T::hash(&self, &mut hasher);
+        let hash = hasher.finish();
+        self.set_hash(Some(hash));
+        (self.fuse_impl(), hash)
+    }
+
+    // Make this Fused read-only, and record a deterministic content hash. See [FnvHasher].
+    #[cfg(feature = "content-hash")]
+    pub fn fuse_hashed(self) -> (&'a T, u64)
+    where
+        T: Hash,
+    {
+        self.fuse_hashed_with(FnvHasher::new())
+    }
+
+    #[cfg(feature = "content-hash")]
+    fn set_hash(&self, hash: Option<u64>) {
+        // The hash is only written while holding the write lock.
+        unsafe { *self.fused.unwrap().hash.get() = hash };
+    }
+
+    // Read the value and unlock, without poisoning if `read` panics, since it cannot have
+    // modified the value.
+    pub(crate) fn read_and_unlock<X>(mut self, read: impl FnOnce(&T) -> X) -> X {
+        struct Unlock<'a, R: RawFused>(&'a R);
+        impl<R: RawFused> Drop for Unlock<'_, R> {
+            fn drop(&mut self) {
+                check_write_locked(self.0, "dropping a FusedGuard");
+                unsafe { self.0.unlock() }
+            }
+        }
+        let fused = self.fused.take().unwrap();
+        let _unlock = Unlock(&fused.raw);
+        read(unsafe { &*fused.data.get() })
+    }
+
+    fn fuse_impl(mut self) -> &'a T {
         unsafe {
-            let once = self.fused.take().unwrap();
+            let once = self.fused.unwrap();
+            once.invariant.check_fused(&*once.data.get());
+            #[cfg(all(debug_assertions, feature = "std"))]
+            crate::registry::check_initialized_before(&once.raw as *const R as *const u8);
+            self.fused = None;
+            check_write_locked(&once.raw, "FusedGuard::fuse");
             once.raw.unlock_fuse();
-            &*once.data.get()
+            let value = &*once.data.get();
+            #[cfg(feature = "on-fuse")]
+            for hook in once.hooks.take() {
+                hook(value);
+            }
+            value
         }
     }
 }
 
-impl<'a, R: RawFused, T> FusedEntry<'a, R, T> {
+/// The result of [Fused::write_arc].
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub enum ArcFusedEntry<R: RawFused, T, I: FusedInvariant<T> = ()> {
+    Read(Arc<Fused<R, T, I>>),
+    Write(ArcFusedGuard<R, T, I>),
+}
+
+/// A write lock on a Fused that keeps it alive, so that it can be moved to another thread.
+/// Dropping the guard without fusing unlocks the Fused, or poisons it if panicking.
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub struct ArcFusedGuard<R: RawFused, T, I: FusedInvariant<T> = ()> {
+    fused: Arc<Fused<R, T, I>>,
+    marker: PhantomData<R::GuardMarker>,
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<R: RawFused, T, I: FusedInvariant<T>> ArcFusedGuard<R, T, I> {
+    // Make the Fused read-only.
+    pub fn fuse(self) -> Arc<Fused<R, T, I>> {
+        let this = ManuallyDrop::new(self);
+        let fused = unsafe { ptr::read(&this.fused) };
+        unsafe { fused.assume_locked().fuse() };
+        fused
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<R: RawFused, T, I: FusedInvariant<T>> Deref for ArcFusedGuard<R, T, I> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.fused.data.get() }
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<R: RawFused, T, I: FusedInvariant<T>> DerefMut for ArcFusedGuard<R, T, I> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.fused.data.get() }
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<R: RawFused, T, I: FusedInvariant<T>> Drop for ArcFusedGuard<R, T, I> {
+    fn drop(&mut self) {
+        unsafe { drop(self.fused.assume_locked()) }
+    }
+}
+
+impl<'a, R: RawFused, T, I: FusedInvariant<T>> FusedEntry<'a, R, T, I> {
     // Apply a modifier if writeable, and then make read-only
     pub fn or_fuse(self, modify: impl FnOnce(&mut T)) -> &'a T {
         match self {
@@ -50,88 +263,357 @@ impl<'a, R: RawFused, T> FusedEntry<'a, R, T> {
             }
         }
     }
+
+    // Apply a modifier if writeable, and then make read-only if `validate` succeeds. If
+    // `validate` fails, restore the value from before `modify` and unlock without poisoning.
+    pub fn or_fuse_validated<E>(
+        self,
+        modify: impl FnOnce(&mut T),
+        validate: impl FnOnce(&T) -> Result<(), E>,
+    ) -> Result<&'a T, E>
+    where
+        T: Clone,
+    {
+        match self {
+            FusedEntry::Read(x) => Ok(x),
+            FusedEntry::Write(mut x) => {
+                let snapshot = (*x).clone();
+                modify(&mut *x);
+                match validate(&x) {
+                    Ok(()) => Ok(x.fuse()),
+                    Err(e) => {
+                        *x = snapshot;
+                        Err(e)
+                    }
+                }
+            }
+        }
+    }
 }
 
-impl<'a, R: RawFused, T> Deref for FusedGuard<'a, R, T> {
+impl<'a, R: RawFused, T, I: FusedInvariant<T>> Deref for FusedGuard<'a, R, T, I> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
         unsafe { &*self.fused.unwrap().data.get() }
     }
 }
 
-impl<'a, R: RawFused, T> DerefMut for FusedGuard<'a, R, T> {
+impl<'a, R: RawFused, T, I: FusedInvariant<T>> DerefMut for FusedGuard<'a, R, T, I> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe { &mut *self.fused.unwrap().data.get() }
     }
 }
 
-impl<R: RawFused, T> Fused<R, T> {
+impl<R: RawFusedConst, T> Fused<R, T> {
     /// Construct a mutable Fused.
     pub const fn new(x: T) -> Self {
-        Fused {
-            raw: R::UNLOCKED,
-            data: UnsafeCell::new(x),
-        }
+        Fused::from_raw(R::UNLOCKED, x)
     }
 
+    /// Construct an immutable Fused.
     pub const fn new_read(x: T) -> Self {
-        Fused {
-            raw: R::READ,
-            data: UnsafeCell::new(x),
-        }
+        Fused::from_raw(R::READ, x)
     }
 
     /// Construct an immutable Fused that causes and error when locking.
     pub const fn poisoned(x: T) -> Self {
+        Fused::from_raw(R::POISON, x)
+    }
+}
+
+impl<R: RawFused, T> Fused<R, T> {
+    /// Construct a Fused whose state is that of `raw`, for backends that cannot be constructed in
+    /// const contexts.
+    pub const fn from_raw(raw: R, x: T) -> Self {
         Fused {
-            raw: R::POISON,
+            raw,
             data: UnsafeCell::new(x),
+            #[cfg(feature = "content-hash")]
+            hash: UnsafeCell::new(None),
+            #[cfg(feature = "on-fuse")]
+            hooks: FuseHooks::new(),
+            invariant: (),
+        }
+    }
+
+    /// Construct an immutable Fused from `T::default()` after applying `init`.
+    pub fn build(init: impl FnOnce(&mut T)) -> Self
+    where
+        T: Default,
+    {
+        Fused::build_from(T::default(), init)
+    }
+
+    /// Construct an immutable Fused from `seed` after applying `init`.
+    pub fn build_from(mut seed: T, init: impl FnOnce(&mut T)) -> Self {
+        init(&mut seed);
+        Fused::from_raw(R::read(), seed)
+    }
+
+    /// In debug builds, panic with `name` if `check` fails when the value is fused. Does nothing
+    /// in release builds. Only cells with an invariant store it.
+    pub const fn with_invariant(
+        self,
+        name: &'static str,
+        check: fn(&T) -> bool,
+    ) -> Fused<R, T, Invariant<T>> {
+        self.with_invariant_impl(Invariant::new(name, check, false))
+    }
+
+    /// Like [Fused::with_invariant], but additionally checks the invariant on every read of the
+    /// fused value, so it suits only checks that are cheap.
+    pub const fn with_read_invariant(
+        self,
+        name: &'static str,
+        check: fn(&T) -> bool,
+    ) -> Fused<R, T, Invariant<T>> {
+        self.with_invariant_impl(Invariant::new(name, check, true))
+    }
+
+    const fn with_invariant_impl(self, invariant: Invariant<T>) -> Fused<R, T, Invariant<T>> {
+        // Move the fields, which a const fn cannot do by destructuring.
+        let this = ManuallyDrop::new(self);
+        let this = &this as *const ManuallyDrop<Self> as *const Self;
+        unsafe {
+            Fused {
+                raw: ptr::read(&(*this).raw),
+                data: ptr::read(&(*this).data),
+                #[cfg(feature = "content-hash")]
+                hash: ptr::read(&(*this).hash),
+                #[cfg(feature = "on-fuse")]
+                hooks: ptr::read(&(*this).hooks),
+                invariant,
+            }
+        }
+    }
+}
+
+impl<R: RawFused, T, I: FusedInvariant<T>> Fused<R, T, I> {
+    /// Unwrap the result of locking this Fused. If the current thread already holds the write
+    /// lock, the panic names both the call that obtained it and the caller, followed by each cell
+    /// in the cycle that this thread is initializing.
+    #[track_caller]
+    pub(crate) fn unwrap_lock<X>(&self, result: Result<X, LockError>) -> X {
+        match result {
+            Ok(x) => x,
+            Err(e) => self.fail_lock(e, Location::caller()),
+        }
+    }
+
+    /// Panic with the message of [Fused::unwrap_lock] for a request made at `caller`, or act as
+    /// decided by the [cycle handler](crate::deadlock::set_cycle_handler).
+    pub(crate) fn fail_lock(&self, error: LockError, caller: &Location) -> ! {
+        #[cfg(feature = "std")]
+        if let LockError::Cycle {
+            owner: Some(owner), ..
+        } = &error
+        {
+            if !owner.is_current() {
+                // The cycle handler chose to return an error for a cycle across threads.
+                panic!(
+                    "deadlock: threads are waiting for each other's write locks in a cycle, requested at {}",
+                    caller
+                );
+            }
+        }
+        match error {
+            LockError::Cycle { location, .. } => {
+                #[cfg(feature = "std")]
+                let cycle =
+                    &crate::api::cycle::describe(&self.raw as *const R as *const u8, caller)
+                        .map_or(String::new(), |cycle| format!("\n{}", cycle));
+                #[cfg(not(feature = "std"))]
+                let cycle = "";
+                let message = Reentered {
+                    owner: location,
+                    caller,
+                    cycle,
+                };
+                #[cfg(feature = "std")]
+                {
+                    let message = message.to_string();
+                    report_cycle(&message);
+                    panic!("{}", message)
+                }
+                #[cfg(not(feature = "std"))]
+                panic!("{}", message)
+            }
+            LockError::Poisoned { .. } => panic!("{}", error),
+        }
+    }
+
+    // The caller must have observed the read-only state.
+    pub(crate) unsafe fn read_unchecked(&self) -> &T {
+        check_read(&self.raw, "reading a Fused");
+        let value = &*self.data.get();
+        self.invariant.check_read(value);
+        value
+    }
+
+    // Construct a guard for a write lock that the caller holds.
+    pub(crate) unsafe fn assume_locked(&self) -> FusedGuard<'_, R, T, I> {
+        check_write_locked(&self.raw, "constructing a FusedGuard");
+        FusedGuard {
+            fused: Some(self),
+            marker: PhantomData,
         }
     }
-    unsafe fn make_entry(&self, raw: RawFusedState) -> FusedEntry<R, T> {
+
+    // Obtain the write lock of a poisoned Fused, unless another caller already has.
+    #[track_caller]
+    pub(crate) fn try_write_poisoned(&self) -> Option<FusedGuard<'_, R, T, I>> {
+        if !self.raw.try_write_poisoned() {
+            return None;
+        }
+        Some(unsafe { self.assume_locked() })
+    }
+
+    pub(crate) unsafe fn make_entry(&self, raw: RawFusedState) -> FusedEntry<'_, R, T, I> {
         match raw {
-            RawFusedState::Write => FusedEntry::Write(FusedGuard {
-                fused: Some(self),
-                marker: PhantomData,
-            }),
-            RawFusedState::Read => FusedEntry::Read(&*self.data.get()),
+            RawFusedState::Write => FusedEntry::Write(self.assume_locked()),
+            RawFusedState::Read => FusedEntry::Read(self.read_unchecked()),
         }
     }
+    /// Like [Fused::write_checked], but the guard keeps the Fused alive instead of borrowing it.
+    #[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+    #[track_caller]
+    pub fn write_arc_checked(self: &Arc<Self>) -> Result<ArcFusedEntry<R, T, I>, LockError> {
+        Ok(match self.write_checked()? {
+            FusedEntry::Read(_) => ArcFusedEntry::Read(self.clone()),
+            FusedEntry::Write(guard) => {
+                mem::forget(guard);
+                ArcFusedEntry::Write(ArcFusedGuard {
+                    fused: self.clone(),
+                    marker: PhantomData,
+                })
+            }
+        })
+    }
+    #[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+    #[track_caller]
+    pub fn write_arc(self: &Arc<Self>) -> ArcFusedEntry<R, T, I> {
+        self.unwrap_lock(self.write_arc_checked())
+    }
     /// Attempt to obtain a write lock and block if necessary.
-    pub fn write_checked(&self) -> Result<FusedEntry<R, T>, TryLockError<()>> {
-        unsafe { Ok(self.make_entry(self.raw.write_checked()?)) }
+    #[track_caller]
+    pub fn write_checked(&self) -> Result<FusedEntry<'_, R, T, I>, LockError> {
+        let state = self
+            .raw
+            .write_checked()
+            .map_err(|e| lock_error(&self.raw, e))?;
+        unsafe { Ok(self.make_entry(state)) }
     }
     /// Attempt to obtain a write lock and block if necessary. Panics if poisoned or deadlocked.
-    pub fn write(&self) -> FusedEntry<R, T> {
-        self.write_checked().unwrap()
+    #[track_caller]
+    pub fn write(&self) -> FusedEntry<'_, R, T, I> {
+        self.unwrap_lock(self.write_checked())
     }
     /// Attempt to obtain a write lock without blocking.
-    pub fn try_write_checked(&self) -> Result<Option<FusedEntry<R, T>>, TryLockError<()>> {
-        unsafe { Ok(self.raw.try_write_checked()?.map(|e| self.make_entry(e))) }
+    #[track_caller]
+    pub fn try_write_checked(&self) -> Result<Option<FusedEntry<'_, R, T, I>>, LockError> {
+        let state = self
+            .raw
+            .try_write_checked()
+            .map_err(|e| lock_error(&self.raw, e))?;
+        unsafe { Ok(state.map(|e| self.make_entry(e))) }
     }
     /// Attempt to obtain a write lock without blocking. Panics if poisoned or deadlocked.
-    pub fn try_write(&self) -> Option<FusedEntry<R, T>> {
+    #[track_caller]
+    pub fn try_write(&self) -> Option<FusedEntry<'_, R, T, I>> {
         self.try_write_checked().unwrap()
     }
+    /// Attempt to obtain a write lock, blocking until `deadline` at the latest.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn try_write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<FusedEntry<'_, R, T, I>>, LockError> {
+        let state = self
+            .raw
+            .write_until_checked(deadline)
+            .map_err(|e| lock_error(&self.raw, e))?;
+        unsafe { Ok(state.map(|e| self.make_entry(e))) }
+    }
+    /// Attempt to obtain a write lock, blocking until `deadline` at the latest. Panics if
+    /// poisoned or deadlocked.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn try_write_until(&self, deadline: Instant) -> Option<FusedEntry<'_, R, T, I>> {
+        self.unwrap_lock(self.try_write_until_checked(deadline))
+    }
+    /// Attempt to obtain a write lock, blocking for at most `timeout`. A timeout too long to
+    /// represent as an [Instant] blocks without a deadline.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn try_write_for_checked(
+        &self,
+        timeout: Duration,
+    ) -> Result<Option<FusedEntry<'_, R, T, I>>, LockError> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.try_write_until_checked(deadline),
+            None => self.write_checked().map(Some),
+        }
+    }
+    /// Attempt to obtain a write lock, blocking for at most `timeout`. Panics if poisoned or
+    /// deadlocked.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn try_write_for(&self, timeout: Duration) -> Option<FusedEntry<'_, R, T, I>> {
+        self.unwrap_lock(self.try_write_for_checked(timeout))
+    }
     /// If this is writeable, obtain a write lock, apply the modifier, make readable, and then
     /// return a reference. Otherwise just return the reference.
-    pub fn read_or_fuse_checked(
+    #[track_caller]
+    pub fn read_or_fuse_checked(&self, modify: impl FnOnce(&mut T)) -> Result<&T, LockError> {
+        self.read_or_fuse_as_checked(core::any::type_name::<T>(), modify)
+    }
+    /// Like [Fused::read_or_fuse_checked], but names the value `type_name` in the panic for a
+    /// cycle, for wrappers whose `T` is an implementation detail.
+    #[track_caller]
+    pub(crate) fn read_or_fuse_as_checked(
         &self,
+        type_name: &'static str,
         modify: impl FnOnce(&mut T),
-    ) -> Result<&T, TryLockError<()>> {
-        Ok(self.write_checked()?.or_fuse(modify))
+    ) -> Result<&T, LockError> {
+        if let Ok(Some(value)) = self.try_read_checked() {
+            return Ok(value);
+        }
+        let mut modify = Some(modify);
+        fuse_erased(&self.raw, type_name, &mut || unsafe {
+            if let Some(modify) = modify.take() {
+                let value = &mut *self.data.get();
+                modify(value);
+                self.invariant.check_fused(value);
+            }
+        })
+        .map_err(|e| lock_error(&self.raw, e))?;
+        let value = unsafe { self.read_unchecked() };
+        // Only the caller that ran `modify` fused.
+        #[cfg(feature = "on-fuse")]
+        if modify.is_none() {
+            for hook in self.hooks.take() {
+                hook(value);
+            }
+        }
+        Ok(value)
     }
     /// If this is writeable, obtain a write lock, apply the modifier, make readable, and then
     /// return a reference. Otherwise just return the reference. Panics if poisoned or deadlocked.
+    #[track_caller]
     pub fn read_or_fuse(&self, modify: impl FnOnce(&mut T)) -> &T {
-        self.read_or_fuse_checked(modify).unwrap()
+        self.unwrap_lock(self.read_or_fuse_checked(modify))
     }
     /// If this is read-only, return a reference to the underlying object. Does not block.
-    pub fn try_read_checked(&self) -> Result<Option<&T>, PoisonError<()>> {
+    pub fn try_read_checked(&self) -> Result<Option<&T>, LockError> {
+        let state = self
+            .raw
+            .try_read_checked()
+            .map_err(|e| lock_error(&self.raw, e))?;
         unsafe {
-            Ok(match self.raw.try_read_checked()? {
+            Ok(match state {
                 RawFusedState::Write => None,
-                RawFusedState::Read => Some(&*self.data.get()),
+                RawFusedState::Read => Some(self.read_unchecked()),
             })
         }
     }
@@ -140,57 +622,444 @@ impl<R: RawFused, T> Fused<R, T> {
     pub fn try_read(&self) -> Option<&T> {
         self.try_read_checked().unwrap()
     }
-    // pub fn read_checked(&self) -> Result<Option<&T>, TryLockError<()>> {
-    //     unsafe {
-    //         Ok(match self.raw.read_checked()? {
-    //             RawFusedState::Write => None,
-    //             RawFusedState::Read => Some(&*self.data.get())
-    //         })
-    //     }
-    // }
-    // pub fn read(&self) -> Option<&T> {
-    //     self.read_checked().unwrap()
-    // }
-    pub fn get_mut(&mut self) -> (Result<RawFusedState, PoisonError<()>>, &mut T) {
-        (self.raw.try_get_mut(), self.data.get_mut())
-    }
-    pub fn into_inner(mut self) -> (Result<RawFusedState, PoisonError<()>>, T) {
-        let state = self.raw.try_get_mut();
+    /// If this was made read-only with [FusedGuard::fuse_hashed] or
+    /// [FusedGuard::fuse_hashed_with], return the recorded content hash.
+    #[cfg(feature = "content-hash")]
+    pub fn content_hash(&self) -> Option<u64> {
+        unsafe {
+            match self.raw.try_read_checked() {
+                Ok(RawFusedState::Read) => *self.hash.get(),
+                _ => None,
+            }
+        }
+    }
+    /// If this is read-only, return a reference to the underlying object. If write-locked, block
+    /// until unlocked.
+    pub fn read_blocking_checked(&self) -> Result<Option<&T>, LockError> {
+        let state = self
+            .raw
+            .read_checked()
+            .map_err(|e| lock_error(&self.raw, e))?;
+        unsafe {
+            Ok(match state {
+                RawFusedState::Write => None,
+                RawFusedState::Read => Some(self.read_unchecked()),
+            })
+        }
+    }
+    /// If this is read-only, return a reference to the underlying object. If write-locked, block
+    /// until unlocked. Panics if poisoned or deadlocked.
+    #[track_caller]
+    pub fn read_blocking(&self) -> Option<&T> {
+        self.unwrap_lock(self.read_blocking_checked())
+    }
+    /// Run `callback` exactly once when this Fused becomes read-only, or immediately if it
+    /// already is. The callback runs on the thread that fuses, after the value is published. It
+    /// never runs if this Fused is poisoned. If another thread holds the write lock, blocks until
+    /// it is released. Returns an error on deadlock, unless the current thread holds the write
+    /// lock, in which case the callback runs when that lock is fused.
+    #[cfg(feature = "on-fuse")]
+    pub fn on_fuse_checked(
+        &self,
+        callback: impl FnOnce(&T) + Send + 'static,
+    ) -> Result<(), LockError> {
+        if self.raw.holds_write_lock() == Some(true) {
+            self.hooks.push(Box::new(callback));
+            // The guard may have been sent to a thread that fused before the push.
+            if let Ok(Some(value)) = self.try_read_checked() {
+                for hook in self.hooks.take() {
+                    hook(value);
+                }
+            }
+            return Ok(());
+        }
+        match self.raw.write_checked() {
+            Ok(RawFusedState::Read) => callback(unsafe { self.read_unchecked() }),
+            Ok(RawFusedState::Write) => unsafe {
+                self.hooks.push(Box::new(callback));
+                check_write_locked(&self.raw, "Fused::on_fuse");
+                self.raw.unlock();
+            },
+            Err(TryLockError::WouldBlock) => {
+                return Err(lock_error(&self.raw, TryLockError::WouldBlock))
+            }
+            Err(TryLockError::Poisoned(_)) => {}
+        }
+        Ok(())
+    }
+    /// Like [Fused::on_fuse_checked], but panics on deadlock.
+    #[cfg(feature = "on-fuse")]
+    #[track_caller]
+    pub fn on_fuse(&self, callback: impl FnOnce(&T) + Send + 'static) {
+        self.unwrap_lock(self.on_fuse_checked(callback))
+    }
+    /// Block until this is read-only, and then return a reference to the underlying object.
+    pub fn wait_fused_checked(&self) -> Result<&T, LockError> {
+        self.raw
+            .wait_read_checked()
+            .map_err(|e| lock_error(&self.raw, e))?;
+        unsafe { Ok(self.read_unchecked()) }
+    }
+    /// Block until this is read-only, and then return a reference to the underlying object.
+    /// Panics if poisoned or deadlocked.
+    #[track_caller]
+    pub fn wait_fused(&self) -> &T {
+        self.unwrap_lock(self.wait_fused_checked())
+    }
+    pub(crate) fn raw(&self) -> &R {
+        &self.raw
+    }
+    /// Return a clone of the current value, briefly taking the write lock if this is still
+    /// writeable. Returns None if poisoned, or if the current thread holds the write lock. A
+    /// panic in `T::clone` leaves this unlocked rather than poisoned.
+    pub fn clone_current(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        match self.write_checked().ok()? {
+            FusedEntry::Read(x) => Some(x.clone()),
+            FusedEntry::Write(guard) => Some(guard.read_and_unlock(T::clone)),
+        }
+    }
+    /// The thread initializing this Fused, if it is write-locked and the backend records its
+    /// owner. For diagnosing a thread that blocks here.
+    #[cfg(feature = "std")]
+    pub fn owner_thread(&self) -> Option<crate::sync::OwnerThread> {
+        self.raw.owner_thread()
+    }
+    /// The counters of contention and initialization for this Fused, if the backend keeps them.
+    /// See [stats](crate::stats).
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Option<crate::stats::Stats> {
+        self.raw.counters().map(|x| x.get())
+    }
+    /// A raw pointer to the value. Reading through it is only sound in the read-only state, and
+    /// writing through it only while holding the write lock.
+    pub const fn as_mut_ptr(&self) -> *mut T {
+        self.data.get()
+    }
+
+    /// Return the value without checking the state.
+    ///
+    /// # Safety
+    /// No thread may write to the value for the lifetime of the returned reference, and any
+    /// write must happen-before this call.
+    pub unsafe fn force_read_unchecked(&self) -> &T {
+        unsafe { &*self.data.get() }
+    }
+
+    /// Make the value read-only as if a write guard had been fused, running [Fused::on_fuse]
+    /// callbacks and debug invariants.
+    ///
+    /// # Safety
+    /// The current thread must hold the write lock without a live guard, for example after
+    /// calling [std::mem::forget] on a [FusedGuard].
+    pub unsafe fn assume_init_read_state(&self) -> &T {
+        unsafe { self.assume_locked().fuse() }
+    }
+
+    pub fn get_mut(&mut self) -> (Result<RawFusedState, LockError>, &mut T) {
+        let state = self.raw.try_get_mut().map_err(|e| lock_error(&self.raw, e));
+        (state, self.data.get_mut())
+    }
+    pub fn into_inner(mut self) -> (Result<RawFusedState, LockError>, T) {
+        let state = self.raw.try_get_mut().map_err(|e| lock_error(&self.raw, e));
         (state, self.data.into_inner())
     }
+    /// Return the value for writing using exclusive access, without changing the state. A
+    /// read-only Fused stays read-only, and its content hash is cleared.
+    pub fn write_mut(&mut self) -> &mut T {
+        #[cfg(feature = "content-hash")]
+        {
+            *self.hash.get_mut() = None;
+        }
+        self.data.get_mut()
+    }
+    // Make a writeable Fused read-only using exclusive access, as if a guard had been fused.
+    pub(crate) fn fuse_mut(&mut self) {
+        self.invariant.check_fused(unsafe { &*self.data.get() });
+        self.raw = R::read();
+        let value = self.data.get_mut();
+        #[cfg(feature = "on-fuse")]
+        for hook in self.hooks.take() {
+            hook(value);
+        }
+    }
+    /// Make a read-only Fused writeable again. Exclusive access guarantees that no references to
+    /// the read-only value remain. Returns an error and leaves the state unchanged if poisoned.
+    pub fn unfuse(&mut self) -> Result<(), LockError> {
+        self.raw
+            .try_get_mut()
+            .map_err(|e| lock_error(&self.raw, e))?;
+        self.raw = R::unlocked();
+        #[cfg(feature = "content-hash")]
+        {
+            *self.hash.get_mut() = None;
+        }
+        Ok(())
+    }
+    /// Replace the value using exclusive access, regardless of whether this is read-only. A
+    /// poisoned Fused becomes writeable because the inconsistent value is discarded.
+    pub fn replace(&mut self, value: T) -> T {
+        if self.raw.try_get_mut().is_err() {
+            self.raw = R::unlocked();
+        }
+        #[cfg(feature = "content-hash")]
+        {
+            *self.hash.get_mut() = None;
+        }
+        mem::replace(self.data.get_mut(), value)
+    }
+    /// Set the value using exclusive access. See [Fused::replace].
+    pub fn set(&mut self, value: T) {
+        self.replace(value);
+    }
+    /// Make a poisoned Fused writeable again using exclusive access, keeping the value left by
+    /// the panicking writer. Does nothing unless poisoned.
+    pub fn clear_poison(&mut self) {
+        if self.raw.try_get_mut().is_err() {
+            self.raw = R::unlocked();
+        }
+    }
+    /// If poisoned, let one caller repair the value left by the panicking writer, and then make
+    /// it read-only. Returns None without calling `repair` if not poisoned, or if another caller
+    /// is recovering it. If `repair` panics, the Fused is poisoned again.
+    #[track_caller]
+    pub fn try_recover(&self, repair: impl FnOnce(&mut T)) -> Option<&T> {
+        let mut guard = self.try_write_poisoned()?;
+        repair(&mut guard);
+        Some(guard.fuse())
+    }
+}
+
+// Describe an error from `raw`, while the state that caused it is likely still current.
+pub(crate) fn lock_error<R: RawFused>(raw: &R, error: impl Into<TryLockError<()>>) -> LockError {
+    match error.into() {
+        TryLockError::WouldBlock => LockError::Cycle {
+            #[cfg(feature = "std")]
+            owner: raw.owner_thread(),
+            location: raw.owner_location(),
+        },
+        TryLockError::Poisoned(_) => LockError::Poisoned {
+            cause: raw.poison_cause(),
+        },
+    }
+}
+
+// Lock, run `init`, and fuse, or poison if `init` panics, recording its message with `std`. Only generic over `R`, so the slow
+// path is instantiated once per backend rather than once per value and initializer type.
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn fuse_erased<R: RawFused>(
+    raw: &R,
+    type_name: &'static str,
+    init: &mut dyn FnMut(),
+) -> Result<(), TryLockError<()>> {
+    struct Unlock<'a, R: RawFused>(&'a R);
+    impl<'a, R: RawFused> Drop for Unlock<'a, R> {
+        fn drop(&mut self) {
+            check_write_locked(self.0, "poisoning after a panicking initializer");
+            unsafe { self.0.unlock_poison() }
+        }
+    }
+    #[cfg(not(feature = "std"))]
+    let _ = type_name;
+    #[cfg(feature = "std")]
+    let waiting = crate::watchdog::Waiting::enter(type_name);
+    let state = raw.write_checked()?;
+    #[cfg(feature = "std")]
+    drop(waiting);
+    if let RawFusedState::Write = state {
+        let unlock = Unlock(raw);
+        #[cfg(feature = "stats")]
+        let timing = crate::stats::Timing::start(raw.counters());
+        #[cfg(feature = "std")]
+        let instrument = crate::instrument::Init::start(raw as *const R as *const u8, type_name);
+        #[cfg(feature = "std")]
+        let frame = crate::api::cycle::Frame::new(
+            raw as *const R as *const u8,
+            type_name,
+            Location::caller(),
+        );
+        #[cfg(feature = "std")]
+        let initializing = frame.push();
+        #[cfg(feature = "std")]
+        if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(init)) {
+            // Record the message for later callers, and propagate the original panic.
+            mem::forget(unlock);
+            check_write_locked(raw, "poisoning after a panicking initializer");
+            unsafe { raw.unlock_panic(crate::api::raw::payload_message(&*payload)) };
+            std::panic::resume_unwind(payload);
+        }
+        #[cfg(not(feature = "std"))]
+        init();
+        #[cfg(feature = "std")]
+        drop(initializing);
+        #[cfg(all(debug_assertions, feature = "std"))]
+        crate::registry::check_initialized_before(raw as *const R as *const u8);
+        mem::forget(unlock);
+        check_write_locked(raw, "Fused::read_or_fuse");
+        unsafe { raw.unlock_fuse() };
+        #[cfg(feature = "std")]
+        instrument.finish();
+        #[cfg(feature = "stats")]
+        drop(timing);
+    }
+    Ok(())
+}
+
+// The panic message for a thread that requested a write lock it already holds.
+struct Reentered<'a> {
+    owner: Option<&'static Location<'static>>,
+    caller: &'a Location<'a>,
+    cycle: &'a str,
+}
+
+impl Display for Reentered<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.owner {
+            Some(owner) => write!(
+                f,
+                "deadlock: write lock obtained at {} was requested again at {} on the same thread{}",
+                owner, self.caller, self.cycle
+            ),
+            None => write!(
+                f,
+                "deadlock: write lock was requested again at {} on the same thread{}",
+                self.caller, self.cycle
+            ),
+        }
+    }
+}
+
+// Consult the cycle handler about a thread that requested a write lock it already holds.
+#[cfg(feature = "std")]
+fn report_cycle(description: &str) {
+    crate::deadlock::detected(&crate::deadlock::CycleInfo {
+        description,
+        threads: 1,
+    });
+}
+
+unsafe impl<R: RawFused + Send, T: Send, I: FusedInvariant<T> + Send> Send for Fused<R, T, I> {}
+
+unsafe impl<R: RawFused + Send + Sync, T: Send + Sync, I: FusedInvariant<T> + Sync> Sync
+    for Fused<R, T, I>
+{
+}
+
+impl<
+        R: RawFused + RefUnwindSafe + UnwindSafe,
+        T: RefUnwindSafe + UnwindSafe,
+        I: FusedInvariant<T> + RefUnwindSafe,
+    > RefUnwindSafe for Fused<R, T, I>
+{
+}
+
+impl<R: RawFused + UnwindSafe, T: UnwindSafe, I: FusedInvariant<T> + UnwindSafe> UnwindSafe
+    for Fused<R, T, I>
+{
 }
 
-unsafe impl<R: RawFused + Send, T: Send> Send for Fused<R, T> {}
+// The state of a Fused as shown by Debug.
+#[derive(Debug)]
+enum DebugState {
+    Uninit,
+    Initializing,
+    Init,
+    Poisoned,
+}
+
+impl<R: RawFused, T, I: FusedInvariant<T>> Fused<R, T, I> {
+    /// Add the state to `d` without blocking, with the thread holding the write lock and whether
+    /// others wait for it if the backend records them. Returns the value if read-only.
+    pub(crate) fn debug_state(&self, d: &mut DebugStruct) -> Option<&T> {
+        let state = match self.raw.try_read_checked() {
+            Ok(RawFusedState::Read) => DebugState::Init,
+            Err(_) => DebugState::Poisoned,
+            Ok(RawFusedState::Write) if self.raw.is_write_locked() == Some(true) => {
+                DebugState::Initializing
+            }
+            Ok(RawFusedState::Write) => DebugState::Uninit,
+        };
+        d.field("state", &state);
+        match state {
+            DebugState::Init => return Some(unsafe { self.read_unchecked() }),
+            DebugState::Initializing => {
+                #[cfg(feature = "std")]
+                if let Some(owner) = self.raw.owner_thread() {
+                    d.field("owner", &format_args!("{}", owner));
+                }
+                if let Some(waiters) = self.raw.has_waiters() {
+                    d.field("waiters", &waiters);
+                }
+            }
+            DebugState::Uninit | DebugState::Poisoned => {}
+        }
+        None
+    }
+}
 
-unsafe impl<R: RawFused + Send + Sync, T: Send + Sync> Sync for Fused<R, T> {}
+impl<R: RawFused, T: Debug, I: FusedInvariant<T>> Debug for Fused<R, T, I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_struct("Fused");
+        if let Some(value) = self.debug_state(&mut d) {
+            d.field("value", value);
+        }
+        d.finish()
+    }
+}
 
-impl<R: RawFused + RefUnwindSafe + UnwindSafe, T: RefUnwindSafe + UnwindSafe> RefUnwindSafe
-    for Fused<R, T>
+impl<R: RawFused, T, I: FusedInvariant<T>> Registered for Fused<R, T, I>
+where
+    Self: Sync,
 {
+    fn force(&self) {}
+    fn is_initialized(&self) -> bool {
+        matches!(self.try_read_checked(), Ok(Some(_)))
+    }
 }
 
-impl<R: RawFused + UnwindSafe, T: UnwindSafe> UnwindSafe for Fused<R, T> {}
+// Comparisons only consider read-only values, and treat writeable or poisoned cells as empty.
+impl<R: RawFused, T: PartialEq, I: FusedInvariant<T>> PartialEq for Fused<R, T, I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.try_read_checked().ok().flatten() == other.try_read_checked().ok().flatten()
+    }
+}
+
+impl<R: RawFused, T: Eq, I: FusedInvariant<T>> Eq for Fused<R, T, I> {}
+
+impl<R: RawFused, T: PartialOrd, I: FusedInvariant<T>> PartialOrd for Fused<R, T, I> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let other = other.try_read_checked().ok().flatten();
+        self.try_read_checked().ok().flatten().partial_cmp(&other)
+    }
+}
 
-impl<R: RawFused + Debug, T: Debug> Debug for Fused<R, T> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Fused")
-            .field("raw", &self.raw)
-            .field("value", &self.try_read())
-            .finish()
+impl<R: RawFused, T: Hash, I: FusedInvariant<T>> Hash for Fused<R, T, I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.try_read_checked().ok().flatten().hash(state)
     }
 }
 
 impl<R: RawFused, T: Default> Default for Fused<R, T> {
     fn default() -> Self {
-        Fused::new(T::default())
+        Fused::from_raw(R::unlocked(), T::default())
     }
 }
 
-impl<'a, R: RawFused, T> Drop for FusedGuard<'a, R, T> {
+impl<'a, R: RawFused, T, I: FusedInvariant<T>> Drop for FusedGuard<'a, R, T, I> {
     fn drop(&mut self) {
         unsafe {
             if let Some(fused) = self.fused {
+                check_write_locked(&fused.raw, "dropping a FusedGuard");
                 if panicking() {
+                    #[cfg(feature = "std")]
+                    crate::instrument::poisoned(
+                        &fused.raw as *const R as *const u8,
+                        core::any::type_name::<T>(),
+                    );
                     fused.raw.unlock_poison();
                 } else {
                     fused.raw.unlock();
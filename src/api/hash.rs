@@ -0,0 +1,33 @@
+//! A deterministic hasher for content digests.
+
+use core::hash::Hasher;
+
+/// The 64-bit FNV-1a hash. Unlike [DefaultHasher](std::collections::hash_map::DefaultHasher), the
+/// output depends only on the bytes written, so it is not randomized per process. Those bytes come
+/// from [Hash] implementations, which may differ between targets (in endianness or the width of
+/// `usize`) and between versions of Rust or of the hashed types.
+#[derive(Copy, Clone, Debug)]
+pub struct FnvHasher(u64);
+
+impl FnvHasher {
+    pub const fn new() -> Self {
+        FnvHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher::new()
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! The callbacks registered with [Fused::on_fuse](crate::api::fused::Fused::on_fuse).
+//!
+//! They are kept in a list linked through heap nodes, so that an empty list costs a single word.
+//! Callbacks are pushed with a compare-and-swap and taken by swapping out the whole list, so each
+//! one runs at most once even if a guard was sent to another thread and the thread that obtained
+//! it registers a callback concurrently.
+
+use crate::atomic::AtomicPtr;
+use crate::atomic::Ordering::{AcqRel, Relaxed};
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+use core::ptr::null_mut;
+
+type Callback<T> = Box<dyn FnOnce(&T) + Send>;
+
+struct Node<T> {
+    callback: Callback<T>,
+    next: *mut Node<T>,
+}
+
+/// A stack of callbacks shared between threads.
+pub(crate) struct FuseHooks<T> {
+    head: AtomicPtr<Node<T>>,
+    marker: PhantomData<Box<Node<T>>>,
+}
+
+impl<T> FuseHooks<T> {
+    pub(crate) const fn new() -> Self {
+        FuseHooks {
+            head: AtomicPtr::new(null_mut()),
+            marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn push(&self, callback: Callback<T>) {
+        let node = Box::into_raw(Box::new(Node {
+            callback,
+            next: self.head.load(Relaxed),
+        }));
+        unsafe {
+            while let Err(head) =
+                self.head
+                    .compare_exchange_weak((*node).next, node, AcqRel, Relaxed)
+            {
+                (*node).next = head;
+            }
+        }
+    }
+
+    /// Remove every callback pushed so far, in the order they were pushed. A caller that takes
+    /// the list after publishing the value synchronizes with every later push, so the pusher
+    /// observes the value.
+    pub(crate) fn take(&self) -> Hooks<T> {
+        let mut node = self.head.swap(null_mut(), AcqRel);
+        let mut reversed = null_mut();
+        while !node.is_null() {
+            unsafe {
+                let next = (*node).next;
+                (*node).next = reversed;
+                reversed = node;
+                node = next;
+            }
+        }
+        Hooks(reversed)
+    }
+}
+
+// Callbacks are Send, and are only moved out by the caller that takes them from the list.
+unsafe impl<T> Send for FuseHooks<T> {}
+unsafe impl<T> Sync for FuseHooks<T> {}
+
+impl<T> Drop for FuseHooks<T> {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+/// Callbacks taken from [FuseHooks]. Those not yet yielded are dropped with it.
+pub(crate) struct Hooks<T>(*mut Node<T>);
+
+impl<T> Iterator for Hooks<T> {
+    type Item = Callback<T>;
+    fn next(&mut self) -> Option<Callback<T>> {
+        if self.0.is_null() {
+            return None;
+        }
+        let node = unsafe { Box::from_raw(self.0) };
+        self.0 = node.next;
+        Some(node.callback)
+    }
+}
+
+impl<T> Drop for Hooks<T> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
@@ -0,0 +1,111 @@
+//! Out-of-line storage for large values.
+//!
+//! A [OnceIndirect] stores only its state and a pointer inline, and allocates the value on the
+//! heap when initialized. This keeps large values out of static BSS and keeps structs with many
+//! cells compact.
+//! ```
+//! use safe_once::sync::OnceLockIndirect;
+//! static TABLE: OnceLockIndirect<[u64; 4096]> = OnceLockIndirect::new();
+//! assert_eq!(TABLE.get_or_init(|| [7; 4096])[4095], 7);
+//! assert!(size_of_val(&TABLE) < 4096);
+//! ```
+//! Choosing between inline and indirect storage from `size_of::<T>()` would need a type alias
+//! computed from a generic constant, which stable Rust does not support. Use [prefer_indirect]
+//! to make the choice explicit, e.g. in a `const` assertion next to the `static`.
+
+use crate::api::once::Once;
+use crate::api::raw::{RawFused, RawFusedConst};
+use crate::error::LockError;
+use crate::registry::Registered;
+use std::fmt::{Debug, Formatter};
+use std::mem::size_of;
+
+/// The size in bytes above which a value is better stored out of line.
+pub const INDIRECT_THRESHOLD: usize = 256;
+
+/// Whether values of type `T` are larger than [INDIRECT_THRESHOLD].
+pub const fn prefer_indirect<T>() -> bool {
+    size_of::<T>() > INDIRECT_THRESHOLD
+}
+
+/// A [Once] that stores its value on the heap.
+pub struct OnceIndirect<R: RawFused, T> {
+    once: Once<R, Box<T>>,
+}
+
+impl<R: RawFusedConst, T> OnceIndirect<R, T> {
+    pub const fn new() -> Self {
+        OnceIndirect { once: Once::new() }
+    }
+}
+
+impl<R: RawFused, T> OnceIndirect<R, T> {
+    #[track_caller]
+    pub fn get_or_init_checked(&self, init: impl FnOnce() -> T) -> Result<&T, LockError> {
+        Ok(self.once.get_or_init_checked(|| Box::new(init()))?)
+    }
+    #[track_caller]
+    pub fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+        self.once.get_or_init(|| Box::new(init()))
+    }
+    pub fn try_get_checked(&self) -> Result<Option<&T>, LockError> {
+        Ok(self.once.try_get_checked()?.map(|x| &**x))
+    }
+    pub fn try_get(&self) -> Option<&T> {
+        self.try_get_checked().unwrap()
+    }
+    /// Return the value if initialized. If another thread is initializing, block until it
+    /// finishes or abandons initialization.
+    pub fn get_blocking_checked(&self) -> Result<Option<&T>, LockError> {
+        Ok(self.once.get_blocking_checked()?.map(|x| &**x))
+    }
+    /// Like [OnceIndirect::get_blocking_checked], but panics if poisoned or deadlocked.
+    pub fn get_blocking(&self) -> Option<&T> {
+        self.get_blocking_checked().unwrap()
+    }
+    pub fn into_inner(self) -> Option<T> {
+        self.once.into_inner().map(|x| *x)
+    }
+}
+
+impl<R: RawFused, T> Registered for OnceIndirect<R, T>
+where
+    Self: Sync,
+{
+    fn force(&self) {}
+    fn is_initialized(&self) -> bool {
+        matches!(self.try_get_checked(), Ok(Some(_)))
+    }
+}
+
+impl<R: RawFused, T> From<T> for OnceIndirect<R, T> {
+    fn from(value: T) -> Self {
+        OnceIndirect {
+            once: Once::from(Box::new(value)),
+        }
+    }
+}
+
+impl<R: RawFused, T> Default for OnceIndirect<R, T> {
+    fn default() -> Self {
+        OnceIndirect {
+            once: Once::default(),
+        }
+    }
+}
+
+impl<R: RawFused, T: Clone> Clone for OnceIndirect<R, T> {
+    fn clone(&self) -> Self {
+        OnceIndirect {
+            once: self.once.clone(),
+        }
+    }
+}
+
+impl<R: RawFused, T: Debug> Debug for OnceIndirect<R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnceIndirect")
+            .field("value", &self.try_get_checked().ok().flatten())
+            .finish()
+    }
+}
@@ -1,11 +1,21 @@
 //! A lazy initialization pattern where the initializer is supplied at construction.
 
-use crate::api::fused::Fused;
-use crate::api::raw::RawFused;
-use std::cell::Cell;
-use std::fmt::{Debug, Formatter};
-use std::mem;
-use std::ops::Deref;
+use crate::api::fused::{Fused, FusedEntry};
+use crate::api::raw::{RawFused, RawFusedConst};
+use crate::api::try_deref::TryDeref;
+use crate::error::LockError;
+use crate::registry::Registered;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::mem;
+use core::ops::Deref;
+#[cfg(feature = "std")]
+use std::any::Any;
+#[cfg(feature = "std")]
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+#[cfg(feature = "std")]
+use std::thread;
 
 enum State<T, F> {
     Callback(F),
@@ -17,39 +27,294 @@ pub struct Lazy<R: RawFused, T, F = fn() -> T> {
     once: Fused<R, State<T, F>>,
 }
 
-impl<R: RawFused, T, F> Lazy<R, T, F> {
+/// The payload of a caught panic.
+#[cfg(feature = "std")]
+pub type PanicPayload = Box<dyn Any + Send>;
+/// A caught panic, which cannot occur without std.
+#[cfg(not(feature = "std"))]
+pub type PanicPayload = core::convert::Infallible;
+
+fn resume(payload: PanicPayload) -> ! {
+    #[cfg(feature = "std")]
+    {
+        resume_unwind(payload)
+    }
+    #[cfg(not(feature = "std"))]
+    match payload {}
+}
+
+/// The initializer of a [Lazy]. Implemented for every `FnOnce() -> T`.
+pub trait LazyInit<T> {
+    /// Produce the value, and a panic to resume after the value is stored.
+    fn init(self) -> (T, Option<PanicPayload>);
+}
+
+impl<T, F: FnOnce() -> T> LazyInit<T> for F {
+    fn init(self) -> (T, Option<PanicPayload>) {
+        (self(), None)
+    }
+}
+
+/// An initializer that stores the result of `fallback` if `init` panics. See
+/// [Lazy::new_with_fallback].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct Fallback<F, G> {
+    init: F,
+    fallback: G,
+}
+
+#[cfg(feature = "std")]
+impl<T, F: FnOnce() -> T, G: FnOnce() -> T> LazyInit<T> for Fallback<F, G> {
+    fn init(self) -> (T, Option<PanicPayload>) {
+        let init = self.init;
+        match catch_unwind(AssertUnwindSafe(init)) {
+            Ok(value) => (value, None),
+            Err(payload) => ((self.fallback)(), Some(payload)),
+        }
+    }
+}
+
+impl<R: RawFusedConst, T, F> Lazy<R, T, F> {
     pub const fn new(init: F) -> Self {
         Lazy {
             once: Fused::new(State::Callback(init)),
         }
     }
+    /// Construct an already initialized Lazy that never runs an initializer.
+    pub const fn new_value(value: T) -> Self {
+        Lazy {
+            once: Fused::new_read(State::Value(value)),
+        }
+    }
 }
 
-impl<R: RawFused, T, F: FnOnce() -> T> Deref for Lazy<R, T, F> {
-    type Target = T;
-    fn deref(&self) -> &Self::Target {
-        match self
+impl<R: RawFused, T, F> Lazy<R, T, F> {
+    /// Like [Lazy::new], for backends that cannot be constructed in const contexts.
+    pub fn new_runtime(init: F) -> Self {
+        Lazy {
+            once: Fused::from_raw(R::unlocked(), State::Callback(init)),
+        }
+    }
+    /// Return the value if already initialized, without forcing.
+    pub fn try_get_checked(&self) -> Result<Option<&T>, LockError> {
+        Ok(match self.once.try_read_checked()? {
+            Some(State::Value(x)) => Some(x),
+            _ => None,
+        })
+    }
+    /// Return the value if already initialized, without forcing. Panics if poisoned.
+    pub fn try_get(&self) -> Option<&T> {
+        self.try_get_checked().unwrap()
+    }
+    /// Make a poisoned Lazy unforced using exclusive access, with `init` replacing the
+    /// initializer that panicked. Does nothing unless poisoned.
+    pub fn clear_poison(&mut self, init: F) {
+        if self.once.get_mut().0.is_err() {
+            self.once.clear_poison();
+            *self.once.write_mut() = State::Callback(init);
+        }
+    }
+    /// If poisoned, let one caller run `init` in place of the initializer that panicked, and
+    /// store its value. Returns None without calling `init` if not poisoned, or if another caller
+    /// is recovering it. If `init` panics, the Lazy is poisoned again.
+    #[track_caller]
+    pub fn try_recover(&self, init: impl FnOnce() -> T) -> Option<&T> {
+        let mut guard = self.once.try_write_poisoned()?;
+        *guard = State::Value(init());
+        match guard.fuse() {
+            State::Value(x) => Some(x),
+            _ => unreachable!(),
+        }
+    }
+    /// The thread initializing this Lazy. See [Fused::owner_thread].
+    #[cfg(feature = "std")]
+    pub fn owner_thread(&self) -> Option<crate::sync::OwnerThread> {
+        self.once.owner_thread()
+    }
+    /// The counters of contention and initialization for this Lazy. See [Fused::stats].
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Option<crate::stats::Stats> {
+        self.once.stats()
+    }
+    /// In debug builds, declare that `self` must be initialized before `other`. See
+    /// [Once::assert_initialized_before](crate::api::once::Once::assert_initialized_before).
+    pub fn assert_initialized_before(&'static self, other: &'static impl Registered)
+    where
+        Self: Sync,
+        F: LazyInit<T>,
+    {
+        #[cfg(all(debug_assertions, feature = "std"))]
+        crate::registry::declare_initialized_before(self, other);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: RawFusedConst, T, F, G> Lazy<R, T, Fallback<F, G>> {
+    /// Construct a Lazy that stores the result of `fallback` if `init` panics. The panic still
+    /// propagates to the caller that ran `init`, but later accesses see the fallback value
+    /// instead of a poisoned cell.
+    pub const fn new_with_fallback(init: F, fallback: G) -> Self {
+        Lazy::new(Fallback { init, fallback })
+    }
+}
+
+impl<R: RawFused, T, F: LazyInit<T>> Lazy<R, T, F> {
+    /// Force initialization and return a reference to the value.
+    #[track_caller]
+    pub fn try_forced(&self) -> Result<&T, LockError> {
+        let mut payload = None;
+        let value = match self
             .once
-            .read_or_fuse(|x| match mem::replace(x, State::Poisoned) {
-                State::Callback(f) => *x = State::Value(f()),
-                State::Value(_) => unreachable!(),
-                State::Poisoned => unreachable!(),
-            }) {
+            .read_or_fuse_as_checked(core::any::type_name::<T>(), |x| {
+                match mem::replace(x, State::Poisoned) {
+                    State::Callback(f) => {
+                        let (value, panic) = f.init();
+                        *x = State::Value(value);
+                        payload = panic;
+                    }
+                    State::Value(_) => unreachable!(),
+                    State::Poisoned => unreachable!(),
+                }
+            })? {
             State::Callback(_) => unreachable!(),
             State::Value(x) => x,
             State::Poisoned => unreachable!(),
+        };
+        if let Some(payload) = payload {
+            resume(payload);
+        }
+        Ok(value)
+    }
+    /// Force initialization and return a reference to the value. Panics if poisoned or
+    /// deadlocked. Equivalent to dereferencing, but easier to find or forbid by name.
+    #[track_caller]
+    pub fn forced(&self) -> &T {
+        self.once.unwrap_lock(self.try_forced())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F: LazyInit<T>> Lazy<crate::sync::RawFusedLock, T, F>
+where
+    Self: Sync,
+{
+    /// Start running the initializer on a new background thread. Later accesses return the value
+    /// or block until the background thread finishes. If the initializer panics, the Lazy is
+    /// poisoned. An access that happens before the background thread starts runs the
+    /// initializer itself, and the background thread then does nothing.
+    /// ```
+    /// use safe_once::sync::LazyLock;
+    /// static TABLE: LazyLock<Vec<u64>> = LazyLock::new(|| (0..1000).map(|x| x * x).collect());
+    /// TABLE.spawn_init();
+    /// assert_eq!(TABLE[10], 100);
+    /// ```
+    pub fn spawn_init(&'static self) {
+        self.spawn_init_with(|init| {
+            thread::Builder::new()
+                .name("safe-once-init".to_string())
+                .spawn(init)
+                .expect("failed to spawn initializer thread");
+        })
+    }
+    /// Like [Lazy::spawn_init], but run the initializer with `spawn`, such as a thread pool.
+    pub fn spawn_init_with(&'static self, spawn: impl FnOnce(Box<dyn FnOnce() + Send>)) {
+        spawn(Box::new(move || {
+            self.forced();
+        }))
+    }
+}
+
+impl<R: RawFused, T, F: LazyInit<T>> Deref for Lazy<R, T, F> {
+    type Target = T;
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        self.forced()
+    }
+}
+
+impl<R: RawFused, T, F: LazyInit<T>> TryDeref for Lazy<R, T, F> {
+    type Target = T;
+    #[track_caller]
+    fn try_deref(&self) -> Result<&Self::Target, LockError> {
+        self.try_forced()
+    }
+}
+
+impl<R: RawFused, T, F: LazyInit<T>> Registered for Lazy<R, T, F>
+where
+    Self: Sync,
+{
+    #[track_caller]
+    fn force(&self) {
+        self.forced();
+    }
+    fn is_initialized(&self) -> bool {
+        matches!(self.once.try_read_checked(), Ok(Some(_)))
+    }
+}
+
+// Comparisons do not force initialization, and treat poisoned cells as uninitialized.
+impl<R: RawFused, T: PartialEq, F> PartialEq for Lazy<R, T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.try_get_checked().ok().flatten() == other.try_get_checked().ok().flatten()
+    }
+}
+
+impl<R: RawFused, T: Eq, F> Eq for Lazy<R, T, F> {}
+
+impl<R: RawFused, T: PartialOrd, F> PartialOrd for Lazy<R, T, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let other = other.try_get_checked().ok().flatten();
+        self.try_get_checked().ok().flatten().partial_cmp(&other)
+    }
+}
+
+impl<R: RawFused, T: Hash, F> Hash for Lazy<R, T, F> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.try_get_checked().ok().flatten().hash(state)
+    }
+}
+
+impl<R: RawFused, T: Clone, F: Clone> Clone for Lazy<R, T, F> {
+    /// Clone the value if initialized, or otherwise the initializer. Blocks if another thread is
+    /// initializing.
+    fn clone(&self) -> Self {
+        let once = match self.once.write_checked() {
+            Ok(FusedEntry::Read(State::Value(x))) => {
+                Fused::from_raw(R::read(), State::Value(x.clone()))
+            }
+            // A panic in `F::clone` leaves this Lazy unlocked rather than poisoned.
+            Ok(FusedEntry::Write(guard)) => guard.read_and_unlock(|state| match state {
+                State::Callback(f) => Fused::from_raw(R::unlocked(), State::Callback(f.clone())),
+                _ => unreachable!(),
+            }),
+            Ok(FusedEntry::Read(_)) => unreachable!(),
+            Err(LockError::Poisoned { .. }) => Fused::from_raw(R::poisoned(), State::Poisoned),
+            Err(LockError::Cycle { .. }) => {
+                panic!("cannot clone a Lazy during its initialization")
+            }
+        };
+        Lazy { once }
+    }
+}
+
+impl<R: RawFused, T, F> From<T> for Lazy<R, T, F> {
+    fn from(value: T) -> Self {
+        Lazy {
+            once: Fused::from_raw(R::read(), State::Value(value)),
         }
     }
 }
 
 impl<R: RawFused, T: Default> Default for Lazy<R, T> {
     fn default() -> Self {
-        Lazy::new(Default::default)
+        Lazy::new_runtime(Default::default)
     }
 }
 
 impl<R: RawFused, T: Debug> Debug for Lazy<R, T> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         (**self).fmt(f)
     }
 }
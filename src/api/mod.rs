@@ -1,4 +1,20 @@
+pub mod aligned;
+#[cfg(feature = "std")]
+pub mod cow;
+#[cfg(feature = "std")]
+mod cycle;
 pub mod fused;
+pub mod hash;
+#[cfg(feature = "on-fuse")]
+mod hooks;
+#[cfg(feature = "std")]
+pub mod indirect;
 pub mod lazy;
 pub mod once;
+pub mod pin;
 pub mod raw;
+#[cfg(feature = "std")]
+pub mod resettable_lazy;
+#[cfg(feature = "std")]
+pub mod retry_lazy;
+pub mod try_deref;
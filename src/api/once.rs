@@ -1,17 +1,21 @@
 //! A lazy initialization pattern where the initializer is supplied at access time.
 
 use crate::api::fused::{Fused, FusedEntry, FusedGuard};
-use crate::api::raw::{RawFused, RawFusedState};
-use std::cell::UnsafeCell;
-use std::fmt::{Debug, Formatter};
-use std::marker::PhantomData;
-use std::mem;
-use std::mem::MaybeUninit;
-use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::sync::{PoisonError, TryLockError};
-use std::thread::panicking;
-
-#[derive(Debug)]
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState, SpinWait};
+use crate::error::{LockError, TryLockError};
+use crate::registry::Registered;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::panic::{Location, RefUnwindSafe, UnwindSafe};
+use core::ptr;
+
 pub struct Once<R: RawFused, T> {
     fused: Fused<R, MaybeUninit<T>>,
 }
@@ -21,6 +25,17 @@ pub enum OnceEntry<'a, R: RawFused, T> {
     Vacant(OnceGuard<'a, R, T>),
 }
 
+/// A guard for initializing a Once. Like [FusedGuard], it is [Send] for a
+/// [crate::sync::OnceLock] but not for a [crate::cell::OnceCell]:
+/// ```compile_fail
+/// use safe_once::api::once::OnceEntry;
+/// use safe_once::cell::OnceCell;
+/// let once = OnceCell::<usize>::new();
+/// std::thread::scope(|s| {
+///     let OnceEntry::Vacant(guard) = once.lock() else { unreachable!() };
+///     s.spawn(move || guard.init(1));
+/// });
+/// ```
 pub struct OnceGuard<'a, R: RawFused, T>(FusedGuard<'a, R, MaybeUninit<T>>);
 
 impl<'a, R: RawFused, T> OnceGuard<'a, R, T> {
@@ -30,6 +45,63 @@ impl<'a, R: RawFused, T> OnceGuard<'a, R, T> {
             self.0.fuse().assume_init_ref()
         }
     }
+    /// Initialize the value directly in the cell's storage, avoiding a move of a large value.
+    ///
+    /// # Safety
+    /// `init` must fully initialize the value before returning. If `init` panics, the cell is
+    /// poisoned and the partially initialized value is leaked.
+    pub unsafe fn init_in_place(mut self, init: impl FnOnce(&mut MaybeUninit<T>)) -> &'a T {
+        unsafe {
+            init(&mut self.0);
+            self.0.fuse().assume_init_ref()
+        }
+    }
+}
+
+/// The result of [Once::lock_arc].
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub enum OwnedOnceEntry<R: RawFused, T> {
+    Occupied(Arc<Once<R, T>>),
+    Vacant(OwnedOnceGuard<R, T>),
+}
+
+/// A write lock on a Once that keeps it alive, so that it can be initialized without borrowing
+/// it. Dropping the guard without initializing unlocks the Once, or poisons it if panicking.
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub struct OwnedOnceGuard<R: RawFused, T> {
+    once: Arc<Once<R, T>>,
+    marker: PhantomData<R::GuardMarker>,
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<R: RawFused, T> OwnedOnceGuard<R, T> {
+    pub fn init(self, value: T) -> Arc<Once<R, T>> {
+        let this = ManuallyDrop::new(self);
+        let once = unsafe { ptr::read(&this.once) };
+        unsafe {
+            let mut guard = once.fused.assume_locked();
+            guard.write(value);
+            guard.fuse();
+        }
+        once
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<R: RawFused, T> Drop for OwnedOnceGuard<R, T> {
+    fn drop(&mut self) {
+        unsafe { drop(self.once.fused.assume_locked()) }
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<R: RawFused, T> OwnedOnceEntry<R, T> {
+    pub fn or_init(self, value: impl FnOnce() -> T) -> Arc<Once<R, T>> {
+        match self {
+            OwnedOnceEntry::Occupied(x) => x,
+            OwnedOnceEntry::Vacant(x) => x.init(value()),
+        }
+    }
 }
 
 impl<'a, R: RawFused, T> OnceEntry<'a, R, T> {
@@ -39,9 +111,17 @@ impl<'a, R: RawFused, T> OnceEntry<'a, R, T> {
             OnceEntry::Vacant(x) => x.init(value()),
         }
     }
+    /// Like [OnceEntry::or_init], but if `value` returns an error the cell is unlocked without
+    /// being poisoned, and another caller may attempt initialization.
+    pub fn or_try_init<E>(self, value: impl FnOnce() -> Result<T, E>) -> Result<&'a T, E> {
+        match self {
+            OnceEntry::Occupied(x) => Ok(x),
+            OnceEntry::Vacant(x) => Ok(x.init(value()?)),
+        }
+    }
 }
 
-impl<R: RawFused, T> Once<R, T> {
+impl<R: RawFusedConst, T> Once<R, T> {
     pub const fn new() -> Self {
         Once {
             fused: Fused::new(MaybeUninit::uninit()),
@@ -52,6 +132,32 @@ impl<R: RawFused, T> Once<R, T> {
             fused: Fused::poisoned(MaybeUninit::uninit()),
         }
     }
+    /// Construct an already initialized Once. Usable in `static` initializers, unlike
+    /// [From::from].
+    pub const fn new_init(value: T) -> Self {
+        Once {
+            fused: Fused::new_read(MaybeUninit::new(value)),
+        }
+    }
+}
+
+impl<R: RawFused, T> Once<R, T> {
+    /// Like [Once::new], for backends that cannot be constructed in const contexts.
+    pub fn new_runtime() -> Self {
+        Once {
+            fused: Fused::from_raw(R::unlocked(), MaybeUninit::uninit()),
+        }
+    }
+    /// The thread initializing this Once. See [Fused::owner_thread].
+    #[cfg(feature = "std")]
+    pub fn owner_thread(&self) -> Option<crate::sync::OwnerThread> {
+        self.fused.owner_thread()
+    }
+    /// The counters of contention and initialization for this Once. See [Fused::stats].
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Option<crate::stats::Stats> {
+        self.fused.stats()
+    }
     unsafe fn make_entry<'a>(
         &'a self,
         raw: FusedEntry<'a, R, MaybeUninit<T>>,
@@ -63,38 +169,258 @@ impl<R: RawFused, T> Once<R, T> {
             }
         }
     }
-    pub fn lock_checked(&self) -> Result<OnceEntry<R, T>, TryLockError<()>> {
+    #[track_caller]
+    pub fn lock_checked(&self) -> Result<OnceEntry<'_, R, T>, LockError> {
         unsafe { Ok(self.make_entry(self.fused.write_checked()?)) }
     }
-    pub fn lock(&self) -> OnceEntry<R, T> {
-        self.lock_checked().unwrap()
+    #[track_caller]
+    pub fn lock(&self) -> OnceEntry<'_, R, T> {
+        self.fused.unwrap_lock(self.lock_checked())
+    }
+    /// Like [Once::lock_checked], but the guard keeps the Once alive instead of borrowing it.
+    #[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+    #[track_caller]
+    pub fn lock_arc_checked(self: &Arc<Self>) -> Result<OwnedOnceEntry<R, T>, LockError> {
+        Ok(match self.fused.write_checked()? {
+            FusedEntry::Read(_) => OwnedOnceEntry::Occupied(self.clone()),
+            FusedEntry::Write(guard) => {
+                mem::forget(guard);
+                OwnedOnceEntry::Vacant(OwnedOnceGuard {
+                    once: self.clone(),
+                    marker: PhantomData,
+                })
+            }
+        })
+    }
+    #[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+    #[track_caller]
+    pub fn lock_arc(self: &Arc<Self>) -> OwnedOnceEntry<R, T> {
+        self.fused.unwrap_lock(self.lock_arc_checked())
     }
-    pub fn try_lock_checked(&self) -> Result<Option<OnceEntry<R, T>>, TryLockError<()>> {
+    #[track_caller]
+    pub fn try_lock_checked(&self) -> Result<Option<OnceEntry<'_, R, T>>, LockError> {
         unsafe { Ok(self.fused.try_write_checked()?.map(|e| self.make_entry(e))) }
     }
-    pub fn try_lock(&self) -> Option<OnceEntry<R, T>> {
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<OnceEntry<'_, R, T>> {
         self.try_lock_checked().unwrap()
     }
+    #[track_caller]
     pub fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
-        self.get_or_init_checked(init).unwrap()
+        self.fused.unwrap_lock(self.get_or_init_checked(init))
     }
-    pub fn get_or_init_checked(&self, init: impl FnOnce() -> T) -> Result<&T, TryLockError<()>> {
-        Ok(self.lock_checked()?.or_init(init))
+    #[track_caller]
+    pub fn get_or_init_checked(&self, init: impl FnOnce() -> T) -> Result<&T, LockError> {
+        unsafe {
+            Ok(self
+                .fused
+                .read_or_fuse_as_checked(core::any::type_name::<T>(), |x| {
+                    x.write(init());
+                })?
+                .assume_init_ref())
+        }
+    }
+    /// Like [Once::get_or_init], but `init` runs without holding the lock, so it may run
+    /// concurrently on several threads. The first value to be stored wins and the others are
+    /// dropped. Suited to cheap, pure initializers. Never parks: while another caller holds the
+    /// lock, spins and then yields until it is released. Returns an error if the current thread
+    /// holds the lock.
+    #[track_caller]
+    pub fn get_or_init_racy_checked(&self, init: impl FnOnce() -> T) -> Result<&T, LockError> {
+        if let Some(value) = self.try_get_checked()? {
+            return Ok(value);
+        }
+        let value = init();
+        let mut spin = SpinWait::new();
+        loop {
+            if let Some(entry) = self.try_lock_checked()? {
+                return Ok(entry.or_init(|| value));
+            }
+            // The lock is only held briefly to store a value, unless another caller is running a
+            // non-racy initializer.
+            if self.fused.raw().holds_write_lock() == Some(true) {
+                return Err(crate::api::fused::lock_error(
+                    self.fused.raw(),
+                    TryLockError::WouldBlock,
+                ));
+            }
+            if !spin.spin() {
+                #[cfg(feature = "std")]
+                std::thread::yield_now();
+                #[cfg(not(feature = "std"))]
+                core::hint::spin_loop();
+            }
+        }
+    }
+    #[track_caller]
+    pub fn get_or_init_racy(&self, init: impl FnOnce() -> T) -> &T {
+        self.fused.unwrap_lock(self.get_or_init_racy_checked(init))
+    }
+    /// If poisoned, let one caller run `init` again and store its value. Returns None without
+    /// calling `init` if not poisoned, or if another caller is recovering it. If `init` panics,
+    /// the Once is poisoned again.
+    #[track_caller]
+    pub fn try_recover(&self, init: impl FnOnce() -> T) -> Option<&T> {
+        let guard = OnceGuard(self.fused.try_write_poisoned()?);
+        Some(guard.init(init()))
+    }
+    /// Like [Once::get_or_init], but if poisoned, run `init` again instead of panicking. Panics
+    /// on deadlock, if `init` panics, or if poisoned and the backend cannot recover.
+    #[track_caller]
+    pub fn get_or_reinit(&self, init: impl FnOnce() -> T) -> &T {
+        let mut init = Some(init);
+        loop {
+            match self.lock_checked() {
+                Ok(entry) => return entry.or_init(|| init.take().unwrap()()),
+                Err(LockError::Poisoned { .. }) => {
+                    if let Some(guard) = self.fused.try_write_poisoned() {
+                        return OnceGuard(guard).init(init.take().unwrap()());
+                    }
+                    // Another caller may have recovered it first, in which case lock again.
+                    // Otherwise the backend never recovers.
+                    if let Err(e @ LockError::Poisoned { .. }) = self.fused.try_read_checked() {
+                        self.fused.fail_lock(e, Location::caller());
+                    }
+                }
+                Err(e) => self.fused.fail_lock(e, Location::caller()),
+            }
+        }
     }
-    pub fn try_get_checked(&self) -> Result<Option<&T>, PoisonError<()>> {
+    pub fn try_get_checked(&self) -> Result<Option<&T>, LockError> {
         unsafe { Ok(self.fused.try_read_checked()?.map(|x| x.assume_init_ref())) }
     }
-    // pub fn get_checked(&self) -> Result<Option<&T>, TryLockError<()>> {
-    //     unsafe {
-    //         Ok(self.fused.read_checked()?.map(|x| x.assume_init_ref()))
-    //     }
-    // }
+    /// Return the value if initialized. If another thread is initializing, block until it
+    /// finishes or abandons initialization.
+    pub fn get_blocking_checked(&self) -> Result<Option<&T>, LockError> {
+        unsafe {
+            Ok(self
+                .fused
+                .read_blocking_checked()?
+                .map(|x| x.assume_init_ref()))
+        }
+    }
+    /// Like [Once::get_blocking_checked], but panics if poisoned or deadlocked.
+    #[track_caller]
+    pub fn get_blocking(&self) -> Option<&T> {
+        self.fused.unwrap_lock(self.get_blocking_checked())
+    }
     pub fn try_get(&self) -> Option<&T> {
         self.try_get_checked().unwrap()
     }
-    // pub fn get(&self) -> Option<&T> {
-    //     self.get_checked().unwrap()
-    // }
+    /// Block until the value is initialized, by this or another thread.
+    pub fn wait_checked(&self) -> Result<&T, LockError> {
+        unsafe { Ok(self.fused.wait_fused_checked()?.assume_init_ref()) }
+    }
+    /// Like [Once::wait_checked], but panics if poisoned or deadlocked.
+    #[track_caller]
+    pub fn wait(&self) -> &T {
+        self.fused.unwrap_lock(self.wait_checked())
+    }
+    /// See [Fused::unwrap_lock].
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub(crate) fn unwrap_lock<X>(&self, result: Result<X, LockError>) -> X {
+        self.fused.unwrap_lock(result)
+    }
+    /// Initialize the value if uninitialized, blocking while another thread initializes it.
+    /// Returns `value` if already initialized.
+    #[track_caller]
+    pub fn set(&self, value: T) -> Result<(), T> {
+        match self.lock() {
+            OnceEntry::Occupied(_) => Err(value),
+            OnceEntry::Vacant(guard) => {
+                guard.init(value);
+                Ok(())
+            }
+        }
+    }
+    /// Return the value without checking the state. Debug builds assert that it is initialized.
+    ///
+    /// # Safety
+    /// The caller must ensure the value is initialized, and that the initialization
+    /// happened-before this call (for example by a previous call to [Once::try_get] on this
+    /// thread, or by joining the initializing thread).
+    pub unsafe fn get_unchecked(&self) -> &T {
+        debug_assert!(
+            matches!(self.try_get_checked(), Ok(Some(_))),
+            "get_unchecked called on an uninitialized Once"
+        );
+        unsafe { self.fused.read_unchecked().assume_init_ref() }
+    }
+    /// A raw pointer to the storage for the value, which may be uninitialized. Writing through
+    /// it is only sound while holding the write lock.
+    pub const fn as_mut_ptr(&self) -> *mut T {
+        self.fused.as_mut_ptr() as *mut T
+    }
+    /// Return the value without checking the state, even in debug builds.
+    ///
+    /// # Safety
+    /// The value must be initialized, no thread may write to it for the lifetime of the
+    /// returned reference, and the initialization must happen-before this call.
+    pub unsafe fn force_read_unchecked(&self) -> &T {
+        unsafe { self.fused.force_read_unchecked().assume_init_ref() }
+    }
+    /// Mark the Once as initialized after writing the value through [Once::as_mut_ptr].
+    /// ```
+    /// use safe_once::sync::OnceLock;
+    /// use safe_once::api::once::OnceEntry;
+    /// let once = OnceLock::<u32>::new();
+    /// let OnceEntry::Vacant(guard) = once.lock() else { unreachable!() };
+    /// std::mem::forget(guard);
+    /// unsafe {
+    ///     once.as_mut_ptr().write(5);
+    ///     once.assume_init_read_state();
+    /// }
+    /// assert_eq!(once.try_get(), Some(&5));
+    /// ```
+    ///
+    /// # Safety
+    /// The current thread must hold the write lock without a live guard, for example after
+    /// calling [std::mem::forget] on a [OnceGuard], and the value must be initialized.
+    pub unsafe fn assume_init_read_state(&self) -> &T {
+        unsafe { self.fused.assume_init_read_state().assume_init_ref() }
+    }
+    /// In debug builds, declare that `self` must be initialized before `other`, and panic with
+    /// both names (see [crate::register!]) if `other` is initialized first. Does nothing in
+    /// release builds or without std.
+    pub fn assert_initialized_before(&'static self, other: &'static impl Registered)
+    where
+        Self: Sync,
+    {
+        #[cfg(all(debug_assertions, feature = "std"))]
+        crate::registry::declare_initialized_before(self, other);
+    }
+    /// Return the value using exclusive access, if initialized.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        match self.fused.get_mut() {
+            (Ok(RawFusedState::Read), value) => Some(unsafe { value.assume_init_mut() }),
+            _ => None,
+        }
+    }
+    /// Remove the value, leaving the Once uninitialized.
+    pub fn take(&mut self) -> Option<T> {
+        mem::take(self).into_inner()
+    }
+    /// Make a poisoned Once uninitialized using exclusive access, so that the next caller runs
+    /// its initializer again. Does nothing unless poisoned.
+    pub fn clear_poison(&mut self) {
+        self.fused.clear_poison();
+    }
+    /// Return the value, initializing it with `init` if necessary. Exclusive access means no
+    /// synchronization is needed. Panics if poisoned.
+    #[track_caller]
+    pub fn get_mut_or_init(&mut self, init: impl FnOnce() -> T) -> &mut T {
+        let (state, _) = self.fused.get_mut();
+        match state {
+            Ok(RawFusedState::Read) => {}
+            Ok(RawFusedState::Write) => {
+                self.fused.write_mut().write(init());
+                self.fused.fuse_mut();
+            }
+            Err(e) => self.fused.fail_lock(e, Location::caller()),
+        }
+        unsafe { self.fused.write_mut().assume_init_mut() }
+    }
     fn into_inner_raw(self) -> Fused<R, MaybeUninit<T>> {
         unsafe {
             let result = ((&self.fused) as *const Fused<_, _>).read();
@@ -107,8 +433,8 @@ impl<R: RawFused, T> Once<R, T> {
             let (state, value) = self.into_inner_raw().into_inner();
             // self.fused = Fused::poisoned(MaybeUninit::uninit());
             match state {
-                Ok(RawFusedState::Read) | Err(_) => Some(value.assume_init_read()),
-                Ok(RawFusedState::Write) => None,
+                Ok(RawFusedState::Read) => Some(value.assume_init_read()),
+                Ok(RawFusedState::Write) | Err(_) => None,
             }
         }
     }
@@ -119,8 +445,8 @@ impl<R: RawFused, T> Drop for Once<R, T> {
         unsafe {
             let (state, value) = self.fused.get_mut();
             match state {
-                Ok(RawFusedState::Read) | Err(_) => value.assume_init_drop(),
-                Ok(RawFusedState::Write) => {}
+                Ok(RawFusedState::Read) => value.assume_init_drop(),
+                Ok(RawFusedState::Write) | Err(_) => {}
             }
         }
     }
@@ -129,7 +455,7 @@ impl<R: RawFused, T> Drop for Once<R, T> {
 impl<R: RawFused, T> From<T> for Once<R, T> {
     fn from(value: T) -> Self {
         Once {
-            fused: Fused::new_read(MaybeUninit::new(value)),
+            fused: Fused::from_raw(R::read(), MaybeUninit::new(value)),
         }
     }
 }
@@ -145,18 +471,51 @@ impl<R: RawFused + RefUnwindSafe + UnwindSafe, T: RefUnwindSafe + UnwindSafe> Re
 
 impl<R: RawFused + UnwindSafe, T: UnwindSafe> UnwindSafe for Once<R, T> {}
 
-// impl<R: RawFused + Debug, T: Debug> Debug for Once<R, T> {
-//     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-//         f.debug_struct("Once")
-//             .field("raw", &self.fu)
-//             .field("value", &self.try_get())
-//             .finish()
-//     }
-// }
+impl<R: RawFused, T: Debug> Debug for Once<R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_struct("Once");
+        if let Some(value) = self.fused.debug_state(&mut d) {
+            d.field("value", unsafe { value.assume_init_ref() });
+        }
+        d.finish()
+    }
+}
+
+impl<R: RawFused, T> Registered for Once<R, T>
+where
+    Self: Sync,
+{
+    fn force(&self) {}
+    fn is_initialized(&self) -> bool {
+        matches!(self.try_get_checked(), Ok(Some(_)))
+    }
+}
+
+// Comparisons treat poisoned cells as uninitialized.
+impl<R: RawFused, T: PartialEq> PartialEq for Once<R, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.try_get_checked().ok().flatten() == other.try_get_checked().ok().flatten()
+    }
+}
+
+impl<R: RawFused, T: Eq> Eq for Once<R, T> {}
+
+impl<R: RawFused, T: PartialOrd> PartialOrd for Once<R, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let other = other.try_get_checked().ok().flatten();
+        self.try_get_checked().ok().flatten().partial_cmp(&other)
+    }
+}
+
+impl<R: RawFused, T: Hash> Hash for Once<R, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.try_get_checked().ok().flatten().hash(state)
+    }
+}
 
 impl<R: RawFused, T> Default for Once<R, T> {
     fn default() -> Self {
-        Once::new()
+        Once::new_runtime()
     }
 }
 
@@ -164,8 +523,76 @@ impl<R: RawFused, T: Clone> Clone for Once<R, T> {
     fn clone(&self) -> Self {
         match self.try_get_checked() {
             Ok(Some(x)) => Once::from(x.clone()),
-            Ok(None) => Once::new(),
-            Err(_) => Once::poisoned(),
+            Ok(None) => Once::new_runtime(),
+            Err(_) => Once {
+                fused: Fused::from_raw(R::poisoned(), MaybeUninit::uninit()),
+            },
         }
     }
 }
+
+#[cfg(feature = "async")]
+impl<T> Once<crate::sync::RawFusedLock, T> {
+    /// Wait until the value is initialized without blocking the thread, so that an async task can
+    /// await a value initialized by synchronous code. Since the write lock may be held by another
+    /// task on the same thread, this fails if poisoned but does not detect cycles.
+    pub fn wait_async_checked(&self) -> crate::future::WaitAsync<'_, T> {
+        crate::future::WaitAsync::new(self)
+    }
+    /// Like [Once::wait_async_checked], but panics if poisoned or deadlocked.
+    #[track_caller]
+    pub fn wait_async(&self) -> impl std::future::Future<Output = &T> {
+        let caller = std::panic::Location::caller();
+        let future = self.wait_async_checked();
+        async move {
+            match future.await {
+                Ok(value) => value,
+                Err(e) => self.fail_lock(e, caller),
+            }
+        }
+    }
+    /// Obtain the write lock without blocking the thread. Like [Once::wait_async_checked], this
+    /// fails if poisoned but does not detect cycles. The lock is held by the returned guard rather
+    /// than the current thread, so the guard may be held across an `await`: other tasks and
+    /// threads, including this one, wait for it instead of reporting a cycle.
+    pub fn lock_async_checked(&self) -> crate::future::LockAsync<'_, T> {
+        crate::future::LockAsync::new(self)
+    }
+    pub(crate) fn poll_wait(
+        &self,
+        cx: &mut std::task::Context,
+    ) -> std::task::Poll<Result<&T, LockError>> {
+        let raw = self.fused.raw();
+        raw.poll_read(cx)
+            .map_ok(|()| unsafe { self.fused.read_unchecked().assume_init_ref() })
+            .map_err(|e| crate::api::fused::lock_error(raw, e))
+    }
+    pub(crate) fn poll_lock(
+        &self,
+        cx: &mut std::task::Context,
+    ) -> std::task::Poll<Result<OnceEntry<'_, crate::sync::RawFusedLock, T>, LockError>> {
+        use std::task::Poll;
+        let raw = self.fused.raw();
+        let mut registered = false;
+        loop {
+            let state = raw
+                .try_write_detached()
+                .map_err(|e| crate::api::fused::lock_error(raw, e))?;
+            if let Some(state) = state {
+                return Poll::Ready(Ok(unsafe { self.make_entry(self.fused.make_entry(state)) }));
+            }
+            if registered {
+                return Poll::Pending;
+            }
+            crate::future::register(self.fused_addr(), cx.waker());
+            std::sync::atomic::fence(std::sync::atomic::Ordering::SeqCst);
+            registered = true;
+        }
+    }
+    pub(crate) fn fail_lock(&self, error: LockError, caller: &std::panic::Location) -> ! {
+        self.fused.fail_lock(error, caller)
+    }
+    pub(crate) fn fused_addr(&self) -> usize {
+        self.fused.raw().fuse_waiters_addr()
+    }
+}
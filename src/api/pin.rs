@@ -0,0 +1,97 @@
+//! Once initialization of values that are never moved after initialization.
+//!
+//! A [OncePin] is accessed through `Pin<&OncePin>`, so it cannot move once any accessor has been
+//! called, and its accessors return `Pin<&T>`. A value initialized in place sees its final
+//! address, so it may refer to itself.
+//! ```
+//! use safe_once::sync::OnceLockPin;
+//! use std::pin::Pin;
+//! static LIST: OnceLockPin<String> = OnceLockPin::new();
+//! let list = Pin::static_ref(&LIST);
+//! assert_eq!(*list.get_or_init(|| "head".to_string()), "head");
+//! ```
+
+use crate::api::once::{Once, OnceEntry};
+use crate::api::raw::{RawFused, RawFusedConst};
+use crate::error::LockError;
+use core::fmt::{Debug, Formatter};
+use core::mem::MaybeUninit;
+use core::pin::Pin;
+
+/// A [Once] whose value is pinned once initialized.
+pub struct OncePin<R: RawFused, T> {
+    once: Once<R, T>,
+}
+
+impl<R: RawFusedConst, T> OncePin<R, T> {
+    pub const fn new() -> Self {
+        OncePin { once: Once::new() }
+    }
+}
+
+impl<R: RawFused, T> OncePin<R, T> {
+    fn once(self: Pin<&Self>) -> &Once<R, T> {
+        &self.get_ref().once
+    }
+    fn pin(value: &T) -> Pin<&T> {
+        // The value is only reachable through a pinned OncePin, and is dropped in place.
+        unsafe { Pin::new_unchecked(value) }
+    }
+    #[track_caller]
+    pub fn get_or_init_checked(
+        self: Pin<&Self>,
+        init: impl FnOnce() -> T,
+    ) -> Result<Pin<&T>, LockError> {
+        Ok(Self::pin(self.once().get_or_init_checked(init)?))
+    }
+    #[track_caller]
+    pub fn get_or_init(self: Pin<&Self>, init: impl FnOnce() -> T) -> Pin<&T> {
+        Self::pin(self.once().get_or_init(init))
+    }
+    /// Initialize the value at its final address if necessary.
+    ///
+    /// # Safety
+    /// `init` must fully initialize the value before returning. If `init` panics, the cell is
+    /// poisoned and the partially initialized value is leaked.
+    #[track_caller]
+    pub unsafe fn get_or_init_in_place(
+        self: Pin<&Self>,
+        init: impl FnOnce(Pin<&mut MaybeUninit<T>>),
+    ) -> Pin<&T> {
+        let value = match self.once().lock() {
+            OnceEntry::Occupied(value) => value,
+            OnceEntry::Vacant(guard) => unsafe {
+                guard.init_in_place(|slot| init(Pin::new_unchecked(slot)))
+            },
+        };
+        Self::pin(value)
+    }
+    pub fn try_get_checked(self: Pin<&Self>) -> Result<Option<Pin<&T>>, LockError> {
+        Ok(self.once().try_get_checked()?.map(Self::pin))
+    }
+    pub fn try_get(self: Pin<&Self>) -> Option<Pin<&T>> {
+        self.once().try_get().map(Self::pin)
+    }
+}
+
+impl<R: RawFused, T: Unpin> OncePin<R, T> {
+    pub fn into_inner(self) -> Option<T> {
+        self.once.into_inner()
+    }
+}
+
+impl<R: RawFused, T> Default for OncePin<R, T> {
+    fn default() -> Self {
+        OncePin {
+            once: Once::default(),
+        }
+    }
+}
+
+impl<R: RawFused, T: Debug> Debug for OncePin<R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OncePin")
+            .field("value", &self.once.try_get_checked())
+            .finish()
+    }
+}
@@ -1,6 +1,9 @@
 //! The core synchronization primitive that is shared by both Once* structs and Lazy* structs.
 
-use std::sync::{PoisonError, TryLockError};
+use crate::error::{PoisonError, TryLockError};
+use core::panic::Location;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
 /// The state of a RawFused at the beginning of a call.
 pub enum RawFusedState {
@@ -19,27 +22,49 @@ pub enum RawFusedState {
 /// * WRITE     - Exactly one caller may mutate the object.
 /// * READ      - All callers may read the object.
 /// * POISON    - All callers may read the object. The object may be in
-///               an inconsistent state due to a panic.
+///   an inconsistent state due to a panic.
 ///
-pub unsafe trait RawFused: 'static {
+/// # Safety
+/// Implementations must provide the memory ordering of a mutex: a transition out of WRITE must
+/// happen-before any caller that subsequently observes the new state.
+pub unsafe trait RawFused: 'static + Sized {
     /// The annotation that defines whether a guard is Send.
     type GuardMarker;
-    const UNLOCKED: Self;
-    const READ: Self;
-    const POISON: Self;
+
+    /// Construct a RawFused in the UNLOCKED state.
+    fn unlocked() -> Self;
+    /// Construct a RawFused in the READ state.
+    fn read() -> Self;
+    /// Construct a RawFused in the POISON state.
+    fn poisoned() -> Self;
 
     /// Attempt to obtain a write lock, blocking if necessary.
     /// * On UNLOCKED, transition to WRITE and return Write.
     /// * On WRITE, block or return WouldBlock if a deadlock is detected.
     /// * On READ, return Read.
     /// * On POISON, return Poisoned.
+    #[track_caller]
     fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>>;
 
+    /// Attempt to obtain a write lock, blocking until the deadline if necessary.
+    /// * On UNLOCKED, transition to WRITE and return Write.
+    /// * On WRITE, block until the deadline and then return None, or return WouldBlock if a
+    ///   deadlock is detected.
+    /// * On READ, return Read.
+    /// * On POISON, return Poisoned.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>>;
+
     /// Attempt to obtain a write lock, but do not block.
     /// * On UNLOCKED, transition to WRITE and return Write.
     /// * On WRITE, return WouldBlock.
     /// * On READ, return Read.
     /// * On POISON, return Poisoned.
+    #[track_caller]
     fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>>;
 
     /// Attempt to use an existing read lock, blocking if there is a write lock
@@ -47,7 +72,25 @@ pub unsafe trait RawFused: 'static {
     /// * On WRITE, block or return WouldBlock if a deadlock is detected.
     /// * On READ, return Read.
     /// * On POISON, return Poisoned.
-    // fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>>;
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>>;
+
+    /// Block until READ or POISON.
+    /// * On UNLOCKED or WRITE, block, or return WouldBlock if a deadlock is detected.
+    /// * On READ, return Ok.
+    /// * On POISON, return Poisoned.
+    ///
+    /// The default implementation yields to the scheduler while waiting, or spins without std.
+    fn wait_read_checked(&self) -> Result<(), TryLockError<()>> {
+        loop {
+            match self.read_checked()? {
+                RawFusedState::Read => return Ok(()),
+                #[cfg(feature = "std")]
+                RawFusedState::Write => std::thread::yield_now(),
+                #[cfg(not(feature = "std"))]
+                RawFusedState::Write => core::hint::spin_loop(),
+            }
+        }
+    }
 
     /// Attempt to use an existing read lock, but do not block
     /// * On UNLOCKED, return Write.
@@ -56,15 +99,191 @@ pub unsafe trait RawFused: 'static {
     /// * On POISON, return Poisoned.
     fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>>;
 
-    /// Transition from WRITE to UNLOCKED. Other states cause undefined behavior.
+    /// Attempt to obtain the write lock of a poisoned lock, so that the caller can repair the
+    /// object. At most one caller succeeds for each poisoning.
+    /// * On POISON, transition to WRITE and return true.
+    /// * Otherwise, return false.
+    ///
+    /// The default implementation never recovers.
+    #[track_caller]
+    fn try_write_poisoned(&self) -> bool {
+        false
+    }
+
+    /// If the write lock is held, the location of the call that obtained it, if recorded.
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        None
+    }
+
+    /// If the write lock is held, the thread that obtained it, if recorded.
+    #[cfg(feature = "std")]
+    fn owner_thread(&self) -> Option<crate::sync::OwnerThread> {
+        None
+    }
+
+    /// Whether any caller is waiting for the write lock to be released, or None if the backend
+    /// does not track it. Shown by the [Debug] output of the [api](crate::api) types.
+    fn has_waiters(&self) -> Option<bool> {
+        None
+    }
+
+    /// If poisoned, what poisoned it, if recorded.
+    fn poison_cause(&self) -> Option<crate::error::PoisonCause> {
+        None
+    }
+
+    /// The backend's counters of contention and initialization, if it keeps them. See
+    /// [stats](crate::stats).
+    #[cfg(feature = "stats")]
+    fn counters(&self) -> Option<&crate::stats::Counters> {
+        None
+    }
+
+    /// Whether the write lock is held by any caller, or None if the backend does not track it.
+    /// Used by the `debug-invariants` feature to check the preconditions of the unsafe methods.
+    fn is_write_locked(&self) -> Option<bool> {
+        None
+    }
+
+    /// Whether the current thread holds the write lock, or None if the backend does not track
+    /// which thread holds it.
+    fn holds_write_lock(&self) -> Option<bool> {
+        None
+    }
+
+    /// Transition from WRITE to UNLOCKED.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock. Other states cause undefined behavior.
     unsafe fn unlock(&self);
 
-    /// Transition from WRITE to POISON. Other states cause undefined behavior.
+    /// Transition from WRITE to POISON.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock. Other states cause undefined behavior.
     unsafe fn unlock_poison(&self);
 
-    /// Transition from WRITE TO READ. Other states cause undefined behavior.
+    /// Transition from WRITE to POISON like [RawFused::unlock_poison], recording the message of
+    /// the panic that poisoned the lock for [RawFused::poison_cause]. The default implementation
+    /// discards it.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock. Other states cause undefined behavior.
+    #[cfg(feature = "std")]
+    unsafe fn unlock_panic(&self, message: String) {
+        let _ = message;
+        unsafe { self.unlock_poison() }
+    }
+
+    /// Transition from WRITE TO READ.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock. Other states cause undefined behavior.
     unsafe fn unlock_fuse(&self);
 
     /// Return the current state.
     fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>>;
 }
+
+/// A [RawFused] that can be constructed in const contexts, which the `const fn` constructors of
+/// the [api](crate::api) types require so that they can be used in statics. Backends whose state
+/// needs runtime construction implement only [RawFused], and are used through
+/// [Fused::from_raw](crate::api::fused::Fused::from_raw), the `new_runtime` constructors, and
+/// [Default].
+///
+/// # Safety
+/// Each constant must be in the same state as the corresponding [RawFused] constructor.
+pub unsafe trait RawFusedConst: RawFused {
+    const UNLOCKED: Self;
+    const READ: Self;
+    const POISON: Self;
+}
+
+/// With the `debug-invariants` feature in debug builds, panic unless the write lock is held.
+#[inline]
+#[track_caller]
+pub(crate) fn check_write_locked<R: RawFused>(raw: &R, operation: &str) {
+    #[cfg(all(feature = "debug-invariants", debug_assertions))]
+    if raw.is_write_locked() == Some(false) {
+        panic!(
+            "{} requires the write lock of the {} at {:p}, but it is not held",
+            operation,
+            core::any::type_name::<R>(),
+            raw
+        );
+    }
+}
+
+/// With the `debug-invariants` feature in debug builds, panic unless the state is READ.
+#[inline]
+#[track_caller]
+pub(crate) fn check_read<R: RawFused>(raw: &R, operation: &str) {
+    #[cfg(all(feature = "debug-invariants", debug_assertions))]
+    if !matches!(raw.try_read_checked(), Ok(RawFusedState::Read)) {
+        panic!(
+            "{} requires the {} at {:p} to be read-only, but it is not",
+            operation,
+            core::any::type_name::<R>(),
+            raw
+        );
+    }
+}
+
+/// Whether the current thread is unwinding, so that guards poison instead of unlocking. Without
+/// std a panic cannot be detected, and guards dropped during unwinding unlock.
+#[inline]
+pub(crate) fn panicking() -> bool {
+    #[cfg(feature = "std")]
+    {
+        std::thread::panicking()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        false
+    }
+}
+
+#[cfg(feature = "parking-lot")]
+pub(crate) use parking_lot_core::SpinWait;
+
+/// A bounded exponential backoff with the interface of parking_lot_core's SpinWait.
+#[cfg(not(feature = "parking-lot"))]
+pub(crate) struct SpinWait {
+    counter: u32,
+}
+
+#[cfg(not(feature = "parking-lot"))]
+impl SpinWait {
+    pub(crate) fn new() -> Self {
+        SpinWait { counter: 0 }
+    }
+
+    /// Spin for a while and return true, or return false once the caller should block instead.
+    pub(crate) fn spin(&mut self) -> bool {
+        if self.counter >= 10 {
+            return false;
+        }
+        self.counter += 1;
+        for _ in 0..1 << self.counter {
+            core::hint::spin_loop();
+        }
+        true
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn reset(&mut self) {
+        self.counter = 0;
+    }
+}
+
+#[cfg(feature = "std")]
+/// The message of a panic, if its payload is a string.
+pub(crate) fn payload_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "Box<dyn Any>".to_string(),
+        },
+    }
+}
@@ -0,0 +1,107 @@
+//! A [Lazy](crate::api::lazy::Lazy) that can be returned to the unforced state.
+
+use crate::api::once::{Once, OnceEntry};
+use crate::api::raw::{RawFused, RawFusedConst};
+use crate::api::try_deref::TryDeref;
+use crate::error::LockError;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+
+/// A lazily initialized value whose initializer is kept after forcing, so that [reset] or [take]
+/// can discard the value and the next access runs the initializer again.
+///
+/// [reset]: ResettableLazy::reset
+/// [take]: ResettableLazy::take
+/// ```
+/// use safe_once::sync::ResettableLazyLock;
+/// let mut lazy = ResettableLazyLock::new(|| vec![1, 2, 3]);
+/// lazy.forced();
+/// assert_eq!(lazy.take(), Some(vec![1, 2, 3]));
+/// assert_eq!(lazy.try_get(), None);
+/// assert_eq!(*lazy, [1, 2, 3]);
+/// ```
+pub struct ResettableLazy<R: RawFused, T, F = fn() -> T> {
+    once: Once<R, T>,
+    init: F,
+}
+
+impl<R: RawFusedConst, T, F> ResettableLazy<R, T, F> {
+    pub const fn new(init: F) -> Self {
+        ResettableLazy {
+            once: Once::new(),
+            init,
+        }
+    }
+}
+
+impl<R: RawFused, T, F> ResettableLazy<R, T, F> {
+    /// Return the value if already initialized, without forcing.
+    pub fn try_get_checked(&self) -> Result<Option<&T>, LockError> {
+        self.once.try_get_checked()
+    }
+    /// Return the value if already initialized, without forcing.
+    pub fn try_get(&self) -> Option<&T> {
+        self.try_get_checked().unwrap()
+    }
+    /// Remove the value, so that the next access runs the initializer again. Also clears poison.
+    pub fn take(&mut self) -> Option<T> {
+        self.once.take()
+    }
+    /// Drop the value, so that the next access runs the initializer again. Also clears poison.
+    pub fn reset(&mut self) {
+        self.take();
+    }
+    pub fn into_inner(self) -> Option<T> {
+        self.once.into_inner()
+    }
+}
+
+impl<R: RawFused, T, F: Fn() -> T> ResettableLazy<R, T, F> {
+    /// Force initialization and return a reference to the value.
+    #[track_caller]
+    pub fn try_forced(&self) -> Result<&T, LockError> {
+        match self.once.lock_checked()? {
+            OnceEntry::Occupied(x) => Ok(x),
+            OnceEntry::Vacant(guard) => Ok(guard.init((self.init)())),
+        }
+    }
+    /// Force initialization and return a reference to the value. Panics if poisoned or on
+    /// deadlock.
+    #[track_caller]
+    pub fn forced(&self) -> &T {
+        self.once.unwrap_lock(self.try_forced())
+    }
+}
+
+impl<R: RawFused, T, F: Fn() -> T> Deref for ResettableLazy<R, T, F> {
+    type Target = T;
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        self.forced()
+    }
+}
+
+impl<R: RawFused, T, F: Fn() -> T> TryDeref for ResettableLazy<R, T, F> {
+    type Target = T;
+    #[track_caller]
+    fn try_deref(&self) -> Result<&Self::Target, LockError> {
+        self.try_forced()
+    }
+}
+
+impl<R: RawFused, T: Default> Default for ResettableLazy<R, T> {
+    fn default() -> Self {
+        ResettableLazy {
+            once: Once::default(),
+            init: T::default,
+        }
+    }
+}
+
+impl<R: RawFused, T: Debug, F> Debug for ResettableLazy<R, T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResettableLazy")
+            .field("value", &self.try_get_checked().ok().flatten())
+            .finish()
+    }
+}
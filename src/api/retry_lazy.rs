@@ -0,0 +1,97 @@
+//! A [Lazy](crate::api::lazy::Lazy) whose initializer is retried after a panic.
+
+use crate::api::once::{Once, OnceEntry};
+use crate::api::raw::{RawFused, RawFusedConst};
+use crate::api::try_deref::TryDeref;
+use crate::error::LockError;
+use crate::registry::Registered;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+/// A lazily initialized value that is never poisoned. If the initializer panics, the panic
+/// propagates and the cell stays uninitialized, so the next access runs the initializer again.
+pub struct RetryLazy<R: RawFused, T, F = fn() -> T> {
+    once: Once<R, T>,
+    init: F,
+}
+
+impl<R: RawFusedConst, T, F> RetryLazy<R, T, F> {
+    pub const fn new(init: F) -> Self {
+        RetryLazy {
+            once: Once::new(),
+            init,
+        }
+    }
+}
+
+impl<R: RawFused, T, F> RetryLazy<R, T, F> {
+    /// Return the value if already initialized, without forcing.
+    pub fn try_get_checked(&self) -> Result<Option<&T>, LockError> {
+        self.once.try_get_checked()
+    }
+    /// Return the value if already initialized, without forcing.
+    pub fn try_get(&self) -> Option<&T> {
+        self.try_get_checked().unwrap()
+    }
+}
+
+impl<R: RawFused, T, F: Fn() -> T> RetryLazy<R, T, F> {
+    /// Force initialization and return a reference to the value.
+    #[track_caller]
+    pub fn try_forced(&self) -> Result<&T, LockError> {
+        match self.once.lock_checked()? {
+            OnceEntry::Occupied(x) => Ok(x),
+            OnceEntry::Vacant(guard) => match catch_unwind(AssertUnwindSafe(&self.init)) {
+                Ok(value) => Ok(guard.init(value)),
+                Err(payload) => {
+                    // Not panicking, so the guard unlocks instead of poisoning.
+                    drop(guard);
+                    resume_unwind(payload)
+                }
+            },
+        }
+    }
+    /// Force initialization and return a reference to the value. Panics if the initializer
+    /// panics or on deadlock.
+    #[track_caller]
+    pub fn forced(&self) -> &T {
+        self.once.unwrap_lock(self.try_forced())
+    }
+}
+
+impl<R: RawFused, T, F: Fn() -> T> Deref for RetryLazy<R, T, F> {
+    type Target = T;
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        self.forced()
+    }
+}
+
+impl<R: RawFused, T, F: Fn() -> T> TryDeref for RetryLazy<R, T, F> {
+    type Target = T;
+    #[track_caller]
+    fn try_deref(&self) -> Result<&Self::Target, LockError> {
+        self.try_forced()
+    }
+}
+
+impl<R: RawFused, T, F: Fn() -> T> Registered for RetryLazy<R, T, F>
+where
+    Self: Sync,
+{
+    fn force(&self) {
+        self.forced();
+    }
+    fn is_initialized(&self) -> bool {
+        matches!(self.try_get_checked(), Ok(Some(_)))
+    }
+}
+
+impl<R: RawFused, T: Debug, F> Debug for RetryLazy<R, T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryLazy")
+            .field("value", &self.try_get_checked().ok().flatten())
+            .finish()
+    }
+}
@@ -0,0 +1,10 @@
+//! A checked alternative to [Deref](std::ops::Deref) for types whose dereference may block, panic,
+//! or run arbitrary initialization code.
+
+use crate::error::LockError;
+
+pub trait TryDeref {
+    type Target: ?Sized;
+    /// Dereference, returning an error instead of panicking if poisoned or deadlocked.
+    fn try_deref(&self) -> Result<&Self::Target, LockError>;
+}
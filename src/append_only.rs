@@ -0,0 +1,217 @@
+//! Append-only collections that can grow through a shared reference.
+//!
+//! Elements are boxed and never removed or replaced while the collection is shared, so references
+//! returned by [FrozenVec::push] or [FrozenMap::insert] stay valid as more elements are added.
+//! The collections are built on a never-fused [Fused], whose write lock every operation through
+//! `&self` holds briefly, so readers wait for each other as well as for writers. [FrozenVec] runs
+//! no caller code under the lock. [FrozenMap] hashes and compares keys, drops a value that was not
+//! inserted, and formats its entries for [Debug] under the lock, so those implementations must
+//! not access the same map, which panics.
+//! ```
+//! use safe_once::sync::FrozenVecLock;
+//! let names = FrozenVecLock::new();
+//! let first: &str = names.push("first".to_string());
+//! names.push("second".to_string());
+//! assert_eq!(first, "first");
+//! assert_eq!(names.get(1).map(String::as_str), Some("second"));
+//! ```
+
+use crate::api::fused::{Fused, FusedEntry, FusedGuard};
+use crate::api::raw::{RawFused, RawFusedConst};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+
+#[track_caller]
+pub(crate) fn lock<R: RawFused, T>(fused: &Fused<R, T>) -> FusedGuard<'_, R, T> {
+    match fused.write() {
+        FusedEntry::Write(guard) => guard,
+        FusedEntry::Read(_) => unreachable!(),
+    }
+}
+
+/// A vector that can be appended to through `&self`.
+pub struct FrozenVec<R: RawFused, T> {
+    vec: Fused<R, Vec<Box<T>>>,
+}
+
+impl<R: RawFusedConst, T> FrozenVec<R, T> {
+    pub const fn new() -> Self {
+        FrozenVec {
+            vec: Fused::new(Vec::new()),
+        }
+    }
+}
+
+impl<R: RawFused, T> FrozenVec<R, T> {
+    /// Append `value`, returning a reference to it that lives as long as the vector.
+    #[track_caller]
+    pub fn push(&self, value: T) -> &T {
+        let mut vec = lock(&self.vec);
+        vec.push(Box::new(value));
+        let ptr: *const T = &**vec.last().unwrap();
+        // The box is never dropped or mutated through a shared reference.
+        unsafe { &*ptr }
+    }
+
+    /// Return the element at `index`.
+    #[track_caller]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let ptr: *const T = &**lock(&self.vec).get(index)?;
+        Some(unsafe { &*ptr })
+    }
+
+    #[track_caller]
+    pub fn len(&self) -> usize {
+        lock(&self.vec).len()
+    }
+
+    #[track_caller]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the elements, including any pushed during iteration.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..).map_while(|index| self.get(index))
+    }
+
+    /// Access the elements mutably. Exclusive access means no locking is needed.
+    pub fn get_mut(&mut self) -> &mut Vec<Box<T>> {
+        self.vec.get_mut().1
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.vec.into_inner().1.into_iter().map(|x| *x).collect()
+    }
+}
+
+impl<R: RawFused, T> Default for FrozenVec<R, T> {
+    fn default() -> Self {
+        FrozenVec {
+            vec: Fused::default(),
+        }
+    }
+}
+
+impl<R: RawFused, T> From<Vec<T>> for FrozenVec<R, T> {
+    fn from(vec: Vec<T>) -> Self {
+        FrozenVec {
+            vec: Fused::from_raw(R::unlocked(), vec.into_iter().map(Box::new).collect()),
+        }
+    }
+}
+
+impl<R: RawFused, T> FromIterator<T> for FrozenVec<R, T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        FrozenVec {
+            vec: Fused::from_raw(R::unlocked(), iter.into_iter().map(Box::new).collect()),
+        }
+    }
+}
+
+impl<R: RawFused, T: Debug> Debug for FrozenVec<R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A hash map that can be inserted into through `&self`. Values are never replaced once inserted.
+pub struct FrozenMap<R: RawFused, K, V> {
+    map: Fused<R, HashMap<K, Box<V>>>,
+}
+
+impl<R: RawFused, K: Eq + Hash, V> FrozenMap<R, K, V> {
+    pub fn new() -> Self {
+        FrozenMap {
+            map: Fused::from_raw(R::unlocked(), HashMap::new()),
+        }
+    }
+
+    fn extend(&self, value: &V) -> &V {
+        // Values are boxed and never removed through a shared reference.
+        unsafe { &*(value as *const V) }
+    }
+
+    /// Insert `value` unless `key` is already present, and return the value for `key`. If `key`
+    /// is already present, `value` is dropped.
+    #[track_caller]
+    pub fn insert(&self, key: K, value: V) -> &V {
+        self.extend(
+            lock(&self.map)
+                .entry(key)
+                .or_insert_with(|| Box::new(value)),
+        )
+    }
+
+    /// Return the value for `key`.
+    #[track_caller]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        Some(self.extend(lock(&self.map).get(key)?))
+    }
+
+    /// Return the value for `key`, inserting the result of `init` if it is absent. `init` runs
+    /// without the lock held, so it may access the map, and concurrent callers may each run it;
+    /// the first value inserted wins. See [OnceMap](crate::map::OnceMap) to run it at most once.
+    #[track_caller]
+    pub fn get_or_insert_with(&self, key: K, init: impl FnOnce(&K) -> V) -> &V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = init(&key);
+        self.insert(key, value)
+    }
+
+    #[track_caller]
+    pub fn len(&self) -> usize {
+        lock(&self.map).len()
+    }
+
+    #[track_caller]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Access the entries mutably. Exclusive access means no locking is needed.
+    pub fn get_mut(&mut self) -> &mut HashMap<K, Box<V>> {
+        self.map.get_mut().1
+    }
+
+    pub fn into_map(self) -> HashMap<K, V> {
+        self.map
+            .into_inner()
+            .1
+            .into_iter()
+            .map(|(k, v)| (k, *v))
+            .collect()
+    }
+}
+
+impl<R: RawFused, K: Eq + Hash, V> Default for FrozenMap<R, K, V> {
+    fn default() -> Self {
+        FrozenMap::new()
+    }
+}
+
+impl<R: RawFused, K: Eq + Hash, V> FromIterator<(K, V)> for FrozenMap<R, K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        FrozenMap {
+            map: Fused::from_raw(
+                R::unlocked(),
+                iter.into_iter().map(|(k, v)| (k, Box::new(v))).collect(),
+            ),
+        }
+    }
+}
+
+impl<R: RawFused, K: Debug, V: Debug> Debug for FrozenMap<R, K, V> {
+    #[track_caller]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(lock(&self.map).iter()).finish()
+    }
+}
@@ -0,0 +1,8 @@
+//! The atomic types used by the `no_std` backends: those of [core], or those of
+//! [portable-atomic](https://docs.rs/portable-atomic) with the `portable-atomic` feature, which
+//! emulates the operations that the target lacks.
+
+#[cfg(not(feature = "portable-atomic"))]
+pub(crate) use core::sync::atomic::*;
+#[cfg(feature = "portable-atomic")]
+pub(crate) use portable_atomic::*;
@@ -0,0 +1,154 @@
+//! A concurrent cache of lazily built values, such as compiled regexes or parsers.
+//!
+//! Each key's value is built at most once, even under concurrent access, using a [OnceLock] per
+//! key. Built values are permanent, so references to them live as long as the cache. Entries whose
+//! build failed (or has not started) hold no value; when a capacity is configured, the least
+//! recently used of those entries are evicted. Entries being built are never evicted.
+//! ```
+//! use safe_once::cache::KeyedLazyCache;
+//! let cache = KeyedLazyCache::new();
+//! let len = cache.get_or_init("hello", |key| key.len());
+//! assert_eq!(*len, 5);
+//! assert_eq!(cache.get_or_init("hello", |_| unreachable!()), &5);
+//! ```
+
+use crate::sync::OnceLock;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+struct Slot<V> {
+    cell: Arc<OnceLock<V>>,
+    last_used: u64,
+}
+
+struct Entries<K, V> {
+    map: HashMap<K, Slot<V>>,
+    clock: u64,
+}
+
+pub struct KeyedLazyCache<K, V> {
+    capacity: Option<usize>,
+    entries: Mutex<Entries<K, V>>,
+}
+
+impl<K: Eq + Hash + Clone, V> KeyedLazyCache<K, V> {
+    /// Construct a cache that never evicts.
+    pub fn new() -> Self {
+        KeyedLazyCache {
+            capacity: None,
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    /// Construct a cache that evicts unbuilt entries beyond `capacity`. Built entries are never
+    /// evicted, so the cache may exceed its capacity if it contains more built values.
+    pub fn with_capacity(capacity: usize) -> Self {
+        KeyedLazyCache {
+            capacity: Some(capacity),
+            ..KeyedLazyCache::new()
+        }
+    }
+
+    fn cell(&self, key: K) -> Arc<OnceLock<V>> {
+        let mut entries = self.entries.lock().unwrap();
+        let entries = &mut *entries;
+        entries.clock += 1;
+        let clock = entries.clock;
+        if let Some(slot) = entries.map.get_mut(&key) {
+            slot.last_used = clock;
+            return slot.cell.clone();
+        }
+        let cell = Arc::new(OnceLock::new());
+        entries.map.insert(
+            key,
+            Slot {
+                cell: cell.clone(),
+                last_used: clock,
+            },
+        );
+        if let Some(capacity) = self.capacity {
+            while entries.map.len() > capacity {
+                let victim = entries
+                    .map
+                    .iter()
+                    .filter(|(_, slot)| Self::evictable(slot))
+                    .min_by_key(|(_, slot)| slot.last_used)
+                    .map(|(key, _)| key.clone());
+                match victim {
+                    Some(victim) => entries.map.remove(&victim),
+                    None => break,
+                };
+            }
+        }
+        cell
+    }
+
+    // An entry is evictable if it has no value and nobody outside the map holds it, so that no
+    // build is in progress.
+    fn evictable(slot: &Slot<V>) -> bool {
+        Arc::strong_count(&slot.cell) == 1 && matches!(slot.cell.try_get_checked(), Ok(None))
+    }
+
+    fn extend(&self, value: &V) -> &V {
+        // Built entries are never removed, so the value lives as long as the cache.
+        unsafe { &*(value as *const V) }
+    }
+
+    /// Return the value for `key`, building it with `build` if necessary.
+    pub fn get_or_init(&self, key: K, build: impl FnOnce(&K) -> V) -> &V {
+        let cell = self.cell(key.clone());
+        self.extend(cell.get_or_init(|| build(&key)))
+    }
+
+    /// Return the value for `key`, building it with `build` if necessary. If `build` fails, the
+    /// entry remains unbuilt and a later call may retry.
+    pub fn get_or_try_init<E>(
+        &self,
+        key: K,
+        build: impl FnOnce(&K) -> Result<V, E>,
+    ) -> Result<&V, E> {
+        let cell = self.cell(key.clone());
+        let value = cell.lock().or_try_init(|| build(&key))?;
+        Ok(self.extend(value))
+    }
+
+    /// Return the value for `key` if it has been built.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let entries = self.entries.lock().unwrap();
+        let value = entries.map.get(key)?.cell.try_get()?;
+        Some(self.extend(value))
+    }
+
+    /// The number of entries, including unbuilt entries.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Eq + Hash + Clone, V> Default for KeyedLazyCache<K, V> {
+    fn default() -> Self {
+        KeyedLazyCache::new()
+    }
+}
+
+impl<K, V> Debug for KeyedLazyCache<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyedLazyCache")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,33 @@
+use crate::api::fused::{Fused, FusedInvariant};
+use crate::api::raw::RawFusedState;
+use crate::cell::RawFusedCell;
+use std::ops::Deref;
+
+/// The value of a fused [FusedCell](crate::cell::FusedCell), which is [Sync] because no interior
+/// mutability remains.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frozen<T>(T);
+
+impl<T> Frozen<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Frozen<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, I: FusedInvariant<T>> Fused<RawFusedCell, T, I> {
+    /// Convert a read-only FusedCell into a [Frozen] that can be shared across threads. Returns
+    /// the cell unchanged if it is still writeable or poisoned.
+    pub fn freeze(mut self) -> Result<Frozen<T>, Self> {
+        match self.get_mut().0 {
+            Ok(RawFusedState::Read) => Ok(Frozen(self.into_inner().1)),
+            _ => Err(self),
+        }
+    }
+}
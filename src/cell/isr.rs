@@ -0,0 +1,30 @@
+//! Implementations for data shared with interrupt handlers on a single core.
+//!
+//! Initialize these from thread context only. A handler may read them with `try_get`, which is
+//! wait-free. A handler that interrupts the initializer cannot wait for it to finish, so if it
+//! requests the value anyway, it panics like a reentrant initialization in
+//! [sync](crate::sync) instead of observing a partially written value. Unlike `cs`, interrupts
+//! stay enabled during initialization.
+//!
+//! Share these only between one thread and the handlers that interrupt it on the same core. A
+//! thread on another core that finds the value being initialized cannot tell that it could wait,
+//! so it panics as if it were a cycle.
+//! ```
+//! use safe_once::cell::isr::OnceCell;
+//! static CONFIG: OnceCell<u32> = OnceCell::new();
+//! fn interrupt_handler() -> u32 {
+//!     CONFIG.try_get().copied().unwrap_or(0)
+//! }
+//! assert_eq!(interrupt_handler(), 0);
+//! CONFIG.get_or_init(|| 42);
+//! assert_eq!(interrupt_handler(), 42);
+//! ```
+
+use crate::api::fused::Fused;
+use crate::api::lazy::Lazy;
+use crate::api::once::Once;
+use crate::cell::RawFusedIsr;
+
+pub type OnceCell<T> = Once<RawFusedIsr, T>;
+pub type LazyCell<T, F = fn() -> T> = Lazy<RawFusedIsr, T, F>;
+pub type FusedCell<T> = Fused<RawFusedIsr, T>;
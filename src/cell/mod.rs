@@ -1,12 +1,65 @@
-//! Implementations that are not [Sync](::std::marker::Sync).
+//! Implementations that are not [Sync](::core::marker::Sync), and [isr] for data shared with
+//! interrupt handlers.
 
+#[cfg(feature = "std")]
+mod frozen;
+pub mod isr;
+#[cfg(feature = "std")]
 mod raw_fused_cell;
+mod raw_fused_isr;
 
+use crate::api::aligned::Aligned;
+#[cfg(feature = "std")]
+use crate::api::cow::LazyCow;
 use crate::api::fused::Fused;
+#[cfg(feature = "std")]
+use crate::api::indirect::OnceIndirect;
 use crate::api::lazy::Lazy;
 use crate::api::once::Once;
+use crate::api::pin::OncePin;
+#[cfg(feature = "std")]
+use crate::api::resettable_lazy::ResettableLazy;
+#[cfg(feature = "std")]
+use crate::api::retry_lazy::RetryLazy;
+#[cfg(feature = "std")]
+use crate::append_only::{FrozenMap, FrozenVec};
+#[cfg(feature = "std")]
+pub use frozen::*;
+#[cfg(feature = "std")]
 pub use raw_fused_cell::*;
+pub use raw_fused_isr::*;
 
+#[cfg(feature = "std")]
 pub type OnceCell<T> = Once<RawFusedCell, T>;
+#[cfg(feature = "std")]
 pub type LazyCell<T, F = fn() -> T> = Lazy<RawFusedCell, T, F>;
+/// A [LazyCell] that retries its initializer after a panic instead of poisoning.
+#[cfg(feature = "std")]
+pub type RetryLazyCell<T, F = fn() -> T> = RetryLazy<RawFusedCell, T, F>;
+/// A [LazyCell] that can be reset to rerun its initializer. See [crate::api::resettable_lazy].
+#[cfg(feature = "std")]
+pub type ResettableLazyCell<T, F = fn() -> T> = ResettableLazy<RawFusedCell, T, F>;
+#[cfg(feature = "std")]
 pub type FusedCell<T> = Fused<RawFusedCell, T>;
+
+/// A [OnceCell] whose value is aligned to at least `ALIGN` bytes.
+#[cfg(feature = "std")]
+pub type OnceCellAligned<T, const ALIGN: usize> = Once<RawFusedCell, Aligned<T, ALIGN>>;
+/// A [FusedCell] whose value is aligned to at least `ALIGN` bytes.
+#[cfg(feature = "std")]
+pub type FusedCellAligned<T, const ALIGN: usize> = Fused<RawFusedCell, Aligned<T, ALIGN>>;
+/// A [OnceCell] that stores its value on the heap. See [crate::api::indirect].
+#[cfg(feature = "std")]
+pub type OnceCellIndirect<T> = OnceIndirect<RawFusedCell, T>;
+/// A [OnceCell] whose value is pinned once initialized. See [crate::api::pin].
+#[cfg(feature = "std")]
+pub type OnceCellPin<T> = OncePin<RawFusedCell, T>;
+/// A reference to a default that is cloned when first modified. See [crate::api::cow].
+#[cfg(feature = "std")]
+pub type LazyCellCow<'a, T> = LazyCow<'a, RawFusedCell, T>;
+/// A vector that can be appended to through a shared reference. See [crate::append_only].
+#[cfg(feature = "std")]
+pub type FrozenVecCell<T> = FrozenVec<RawFusedCell, T>;
+/// A map that can be inserted into through a shared reference. See [crate::append_only].
+#[cfg(feature = "std")]
+pub type FrozenMapCell<K, V> = FrozenMap<RawFusedCell, K, V>;
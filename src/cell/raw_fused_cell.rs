@@ -1,11 +1,11 @@
-use crate::api::raw::{RawFused, RawFusedState};
-use parking_lot::lock_api::GuardNoSend;
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState};
 use std::cell::{Cell, UnsafeCell};
 use std::fmt::{Debug, Formatter};
 use std::mem::MaybeUninit;
+use std::panic::Location;
 use std::sync::{PoisonError, TryLockError};
 use std::thread::panicking;
-// use crate::error::{LockError, PoisonError};
+use std::time::Instant;
 
 #[derive(Copy, Clone, Debug)]
 enum State {
@@ -16,22 +16,70 @@ enum State {
 }
 
 #[derive(Debug)]
-pub struct RawFusedCell(Cell<State>);
+pub struct RawFusedCell {
+    state: Cell<State>,
+    // Where the write lock was obtained. Only meaningful while initializing.
+    #[cfg(feature = "owner-location")]
+    owner: Cell<Option<&'static Location<'static>>>,
+}
+
+impl RawFusedCell {
+    const fn new(state: State) -> Self {
+        RawFusedCell {
+            state: Cell::new(state),
+            #[cfg(feature = "owner-location")]
+            owner: Cell::new(None),
+        }
+    }
+
+    #[track_caller]
+    fn lock(&self) {
+        self.state.set(State::Initializing);
+        #[cfg(feature = "owner-location")]
+        self.owner.set(Some(Location::caller()));
+    }
+}
+
+unsafe impl RawFusedConst for RawFusedCell {
+    const UNLOCKED: Self = RawFusedCell::new(State::Uninit);
+    const READ: Self = RawFusedCell::new(State::Initialized);
+    const POISON: Self = RawFusedCell::new(State::Poison);
+}
 
 unsafe impl RawFused for RawFusedCell {
-    type GuardMarker = GuardNoSend;
-    const UNLOCKED: Self = RawFusedCell(Cell::new(State::Uninit));
-    const READ: Self = RawFusedCell(Cell::new(State::Initialized));
-    const POISON: Self = RawFusedCell(Cell::new(State::Poison));
+    // Not Send, so that guards stay on the thread that owns the cell.
+    type GuardMarker = *mut ();
+
+    fn unlocked() -> Self {
+        Self::UNLOCKED
+    }
+
+    fn read() -> Self {
+        Self::READ
+    }
+
+    fn poisoned() -> Self {
+        Self::POISON
+    }
 
+    #[track_caller]
     fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
         self.try_write_checked()?.ok_or(TryLockError::WouldBlock)
     }
 
+    #[track_caller]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        Ok(Some(self.write_checked()?))
+    }
+
+    #[track_caller]
     fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
-        match self.0.get() {
+        match self.state.get() {
             State::Uninit => {
-                self.0.set(State::Initializing);
+                self.lock();
                 Ok(Some(RawFusedState::Write))
             }
             State::Initializing => Ok(None),
@@ -39,40 +87,77 @@ unsafe impl RawFused for RawFusedCell {
             State::Poison => Err(PoisonError::new(())),
         }
     }
-    // fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
-    //     Ok(self.try_read_checked()?)
-    // }
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        match self.state.get() {
+            State::Initializing => Err(TryLockError::WouldBlock),
+            _ => Ok(self.try_read_checked()?),
+        }
+    }
+
+    fn wait_read_checked(&self) -> Result<(), TryLockError<()>> {
+        match self.try_read_checked()? {
+            RawFusedState::Read => Ok(()),
+            // No other thread can fuse this.
+            RawFusedState::Write => Err(TryLockError::WouldBlock),
+        }
+    }
 
     fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
-        match self.0.get() {
+        match self.state.get() {
             State::Uninit => Ok(RawFusedState::Write),
             State::Initializing => Ok(RawFusedState::Write),
             State::Initialized => Ok(RawFusedState::Read),
             State::Poison => Err(PoisonError::new(())),
         }
     }
+    #[track_caller]
+    fn try_write_poisoned(&self) -> bool {
+        if !matches!(self.state.get(), State::Poison) {
+            return false;
+        }
+        self.lock();
+        true
+    }
+
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        match self.state.get() {
+            #[cfg(feature = "owner-location")]
+            State::Initializing => self.owner.get(),
+            _ => None,
+        }
+    }
+
+    fn is_write_locked(&self) -> Option<bool> {
+        Some(matches!(self.state.get(), State::Initializing))
+    }
+
+    fn holds_write_lock(&self) -> Option<bool> {
+        // Not Sync, so only the current thread can have locked it.
+        self.is_write_locked()
+    }
+
     unsafe fn unlock(&self) {
-        match self.0.get() {
-            State::Initializing => self.0.set(State::Uninit),
+        match self.state.get() {
+            State::Initializing => self.state.set(State::Uninit),
             _ => panic!("Not already initializing"),
         }
     }
     unsafe fn unlock_fuse(&self) {
-        match self.0.get() {
-            State::Initializing => self.0.set(State::Initialized),
+        match self.state.get() {
+            State::Initializing => self.state.set(State::Initialized),
             _ => panic!("Not already initializing"),
         }
     }
 
     unsafe fn unlock_poison(&self) {
-        match self.0.get() {
-            State::Initializing => self.0.set(State::Poison),
+        match self.state.get() {
+            State::Initializing => self.state.set(State::Poison),
             _ => panic!("Not already initializing"),
         }
     }
 
     fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
-        match *self.0.get_mut() {
+        match *self.state.get_mut() {
             State::Uninit => Ok(RawFusedState::Write),
             State::Initializing => Ok(RawFusedState::Write),
             State::Initialized => Ok(RawFusedState::Read),
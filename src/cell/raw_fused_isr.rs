@@ -0,0 +1,151 @@
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState};
+#[cfg(feature = "owner-location")]
+use crate::atomic::{AtomicPtr, Ordering::Relaxed};
+use crate::error::{PoisonError, TryLockError};
+use crate::spin::atomic_state::{AtomicState, POISON, READ, UNLOCKED, WRITE};
+use core::fmt::{Debug, Formatter};
+use core::panic::Location;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// A [RawFused] shared between a thread and the interrupt handlers that preempt it on the same
+/// core. Like [RawFusedCell](super::RawFusedCell), it never waits: a request that finds the write
+/// lock held must come from a handler that interrupted the initializer (or from the initializer
+/// itself), so it fails with [TryLockError::WouldBlock]. Reads are a single atomic load, so they
+/// are wait-free.
+///
+/// It is [Sync] so that it can be placed in a `static`, but it is only correct on a single core:
+/// a thread on another core, or another thread preempting the initializer, that requests the
+/// value while it is being initialized panics as if it were a cycle instead of waiting. Use
+/// [spin](crate::spin) or [sync](crate::sync) for data shared between threads.
+pub struct RawFusedIsr {
+    state: AtomicState,
+    // Where the write lock was obtained, reported when a handler finds it held. Only meaningful
+    // while locked.
+    #[cfg(feature = "owner-location")]
+    owner: AtomicPtr<Location<'static>>,
+}
+
+impl RawFusedIsr {
+    const fn with_state(state: u8) -> Self {
+        RawFusedIsr {
+            state: AtomicState::new(state),
+            #[cfg(feature = "owner-location")]
+            owner: AtomicPtr::new(core::ptr::null_mut()),
+        }
+    }
+
+    #[track_caller]
+    fn set_owner(&self) {
+        #[cfg(feature = "owner-location")]
+        self.owner
+            .store(Location::caller() as *const _ as *mut _, Relaxed);
+    }
+}
+
+unsafe impl RawFusedConst for RawFusedIsr {
+    const UNLOCKED: Self = RawFusedIsr::with_state(UNLOCKED);
+    const READ: Self = RawFusedIsr::with_state(READ);
+    const POISON: Self = RawFusedIsr::with_state(POISON);
+}
+
+unsafe impl RawFused for RawFusedIsr {
+    type GuardMarker = ();
+
+    fn unlocked() -> Self {
+        Self::UNLOCKED
+    }
+
+    fn read() -> Self {
+        Self::READ
+    }
+
+    fn poisoned() -> Self {
+        Self::POISON
+    }
+
+    #[track_caller]
+    fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        self.try_write_checked()?.ok_or(TryLockError::WouldBlock)
+    }
+
+    #[cfg(feature = "std")]
+    #[track_caller]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        Ok(Some(self.write_checked()?))
+    }
+
+    #[track_caller]
+    fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        let state = self.state.try_write()?;
+        if let Some(RawFusedState::Write) = state {
+            self.set_owner();
+        }
+        Ok(state)
+    }
+
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        match self.state.load() {
+            WRITE => Err(TryLockError::WouldBlock),
+            state => Ok(AtomicState::state(state)?),
+        }
+    }
+
+    fn wait_read_checked(&self) -> Result<(), TryLockError<()>> {
+        match self.read_checked()? {
+            RawFusedState::Read => Ok(()),
+            // Spinning in a handler would prevent the interrupted initializer from finishing.
+            RawFusedState::Write => Err(TryLockError::WouldBlock),
+        }
+    }
+
+    fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+        AtomicState::state(self.state.load())
+    }
+
+    #[track_caller]
+    fn try_write_poisoned(&self) -> bool {
+        if !self.state.try_write_poisoned() {
+            return false;
+        }
+        self.set_owner();
+        true
+    }
+
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        #[cfg(feature = "owner-location")]
+        if self.state.is_write_locked() {
+            return unsafe { self.owner.load(Relaxed).as_ref() };
+        }
+        None
+    }
+
+    fn is_write_locked(&self) -> Option<bool> {
+        Some(self.state.is_write_locked())
+    }
+
+    unsafe fn unlock(&self) {
+        self.state.unlock(UNLOCKED);
+    }
+
+    unsafe fn unlock_poison(&self) {
+        self.state.unlock(POISON);
+    }
+
+    unsafe fn unlock_fuse(&self) {
+        self.state.unlock(READ);
+    }
+
+    fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+        self.state.get_mut()
+    }
+}
+
+impl Debug for RawFusedIsr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.state.debug("RawFusedIsr", f)
+    }
+}
@@ -0,0 +1,29 @@
+//! Implementations for single-core microcontrollers, behind the `critical-section` feature.
+//!
+//! State transitions run inside a [critical section](critical_section::with), which masks
+//! interrupts instead of relying on atomic compare-and-swap, so these types work on targets
+//! without it. On a single core, a context that finds a cell being initialized has interrupted
+//! the initializer (or is the initializer itself), so it cannot wait for it: like a reentrant
+//! initialization in [sync](crate::sync), this panics instead of hanging. These types are not
+//! suitable for multi-core critical-section implementations, where another core may be
+//! initializing concurrently.
+//! ```
+//! # #[cfg(feature = "critical-section")] {
+//! use safe_once::cs::{LazyLock, OnceLock};
+//! static LAZY: LazyLock<u32> = LazyLock::new(|| 42);
+//! static ONCE: OnceLock<&str> = OnceLock::new();
+//! assert_eq!(*LAZY, 42);
+//! assert_eq!(*ONCE.get_or_init(|| "hello"), "hello");
+//! # }
+//! ```
+
+mod raw_fused_critical_section;
+
+use crate::api::fused::Fused;
+use crate::api::lazy::Lazy;
+use crate::api::once::Once;
+pub use raw_fused_critical_section::*;
+
+pub type OnceLock<T> = Once<RawFusedCriticalSection, T>;
+pub type LazyLock<T, F = fn() -> T> = Lazy<RawFusedCriticalSection, T, F>;
+pub type FusedLock<T> = Fused<RawFusedCriticalSection, T>;
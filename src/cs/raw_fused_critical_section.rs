@@ -0,0 +1,175 @@
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState};
+use crate::error::{PoisonError, TryLockError};
+use core::cell::Cell;
+use core::fmt::{Debug, Formatter};
+use core::panic::Location;
+use critical_section::Mutex;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum State {
+    Unlocked,
+    Write(&'static Location<'static>),
+    Read,
+    #[cfg(not(feature = "no-poison"))]
+    Poison,
+}
+
+#[cfg(not(feature = "no-poison"))]
+const POISON: State = State::Poison;
+// Without poisoning, a panicking initializer unlocks so that the next caller retries.
+#[cfg(feature = "no-poison")]
+const POISON: State = State::Unlocked;
+
+/// A [RawFused] whose state is only accessed within a critical section. Waiting is never
+/// possible, so every request that finds the write lock held fails with
+/// [TryLockError::WouldBlock].
+pub struct RawFusedCriticalSection {
+    state: Mutex<Cell<State>>,
+}
+
+impl RawFusedCriticalSection {
+    const fn with_state(state: State) -> Self {
+        RawFusedCriticalSection {
+            state: Mutex::new(Cell::new(state)),
+        }
+    }
+
+    fn get(&self) -> State {
+        critical_section::with(|cs| self.state.borrow(cs).get())
+    }
+
+    fn set(&self, state: State) {
+        critical_section::with(|cs| self.state.borrow(cs).set(state))
+    }
+
+    fn read_state(state: State) -> Result<RawFusedState, PoisonError<()>> {
+        match state {
+            State::Unlocked | State::Write(_) => Ok(RawFusedState::Write),
+            State::Read => Ok(RawFusedState::Read),
+            #[cfg(not(feature = "no-poison"))]
+            State::Poison => Err(PoisonError::new(())),
+        }
+    }
+}
+
+unsafe impl RawFusedConst for RawFusedCriticalSection {
+    const UNLOCKED: Self = RawFusedCriticalSection::with_state(State::Unlocked);
+    const READ: Self = RawFusedCriticalSection::with_state(State::Read);
+    const POISON: Self = RawFusedCriticalSection::with_state(POISON);
+}
+
+unsafe impl RawFused for RawFusedCriticalSection {
+    type GuardMarker = ();
+
+    fn unlocked() -> Self {
+        Self::UNLOCKED
+    }
+
+    fn read() -> Self {
+        Self::READ
+    }
+
+    fn poisoned() -> Self {
+        Self::POISON
+    }
+
+    #[track_caller]
+    fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        self.try_write_checked()?.ok_or(TryLockError::WouldBlock)
+    }
+
+    #[cfg(feature = "std")]
+    #[track_caller]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        Ok(Some(self.write_checked()?))
+    }
+
+    #[track_caller]
+    fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        let caller = Location::caller();
+        critical_section::with(|cs| {
+            let state = self.state.borrow(cs);
+            match state.get() {
+                State::Unlocked => {
+                    state.set(State::Write(caller));
+                    Ok(Some(RawFusedState::Write))
+                }
+                State::Write(_) => Ok(None),
+                state => Ok(Some(Self::read_state(state)?)),
+            }
+        })
+    }
+
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        match self.get() {
+            State::Write(_) => Err(TryLockError::WouldBlock),
+            state => Ok(Self::read_state(state)?),
+        }
+    }
+
+    fn wait_read_checked(&self) -> Result<(), TryLockError<()>> {
+        match self.read_checked()? {
+            RawFusedState::Read => Ok(()),
+            // Spinning would prevent the context that could fuse this from running.
+            RawFusedState::Write => Err(TryLockError::WouldBlock),
+        }
+    }
+
+    fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+        Self::read_state(self.get())
+    }
+
+    #[cfg(not(feature = "no-poison"))]
+    #[track_caller]
+    fn try_write_poisoned(&self) -> bool {
+        let caller = Location::caller();
+        critical_section::with(|cs| {
+            let state = self.state.borrow(cs);
+            let poisoned = state.get() == State::Poison;
+            if poisoned {
+                state.set(State::Write(caller));
+            }
+            poisoned
+        })
+    }
+
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        match self.get() {
+            State::Write(owner) => Some(owner),
+            _ => None,
+        }
+    }
+
+    fn is_write_locked(&self) -> Option<bool> {
+        Some(matches!(self.get(), State::Write(_)))
+    }
+
+    unsafe fn unlock(&self) {
+        self.set(State::Unlocked)
+    }
+
+    unsafe fn unlock_poison(&self) {
+        self.set(POISON)
+    }
+
+    unsafe fn unlock_fuse(&self) {
+        self.set(State::Read)
+    }
+
+    fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+        Self::read_state(self.state.get_mut().get())
+    }
+}
+
+impl Debug for RawFusedCriticalSection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("RawFusedCriticalSection")
+            .field(&self.get())
+            .finish()
+    }
+}
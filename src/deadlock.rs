@@ -0,0 +1,68 @@
+//! A process-wide hook that decides what happens when the crate detects a deadlock cycle.
+//!
+//! By default, the thread that detects a cycle panics. Programs that cannot unwind safely, such
+//! as libraries called from a foreign host, can use [set_cycle_handler] to abort instead, or to
+//! return an error from the methods that report errors. The handler runs first, so it can also log
+//! the cycle.
+//! ```
+//! use safe_once::deadlock::{set_cycle_handler, CycleAction, CycleInfo};
+//! fn abort(cycle: &CycleInfo) -> CycleAction {
+//!     eprintln!("{}", cycle.description);
+//!     CycleAction::Abort
+//! }
+//! set_cycle_handler(abort);
+//! ```
+
+use std::ptr::null_mut;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::Ordering::{Acquire, Release};
+
+/// A cycle passed to the handler set by [set_cycle_handler].
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct CycleInfo<'a> {
+    /// The message that the crate panics with by default.
+    pub description: &'a str,
+    /// The number of threads in the cycle: one when a thread requests a cell that it is
+    /// initializing, and more with the `deadlock-detection` feature.
+    pub threads: usize,
+}
+
+/// What to do about a cycle, as decided by the handler set by [set_cycle_handler].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleAction {
+    /// Panic with the description of the cycle.
+    Panic,
+    /// Abort the process.
+    Abort,
+    /// Return [WouldBlock](std::sync::TryLockError::WouldBlock) from `_checked` methods that
+    /// wait, and panic in methods that cannot return an error. A thread that requests a cell it
+    /// is initializing already receives this error from `_checked` methods without consulting
+    /// the handler.
+    Error,
+}
+
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(null_mut());
+
+/// Call `handler` for every subsequent cycle, and act on its decision, replacing any previous
+/// handler.
+pub fn set_cycle_handler(handler: fn(&CycleInfo) -> CycleAction) {
+    HANDLER.store(handler as *mut (), Release);
+}
+
+/// Consult the handler about a detected cycle. Aborts if it decides to, and otherwise returns its
+/// decision, which is [CycleAction::Panic] without a handler.
+pub(crate) fn detected(info: &CycleInfo) -> CycleAction {
+    crate::instrument::deadlock(info.description);
+    let handler = HANDLER.load(Acquire);
+    if handler.is_null() {
+        return CycleAction::Panic;
+    }
+    let handler: fn(&CycleInfo) -> CycleAction = unsafe { std::mem::transmute(handler) };
+    let action = handler(info);
+    if action == CycleAction::Abort {
+        std::process::abort();
+    }
+    action
+}
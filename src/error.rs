@@ -0,0 +1,212 @@
+//! The errors returned by the `_checked` methods.
+//!
+//! The [api](crate::api) types return [LockError], which describes the cycle or the poisoning
+//! that caused it. Backends and the remaining types return the lock errors of the standard
+//! library with the `std` feature, and equivalents with the same names, variants and methods
+//! without it. [From] conversions between the two ease migrating code that matched on
+//! [TryLockError].
+
+use core::fmt::{self, Display, Formatter};
+use core::panic::Location;
+
+#[cfg(feature = "std")]
+pub use std::sync::{PoisonError, TryLockError};
+
+/// The error returned by the `_checked` methods of [Fused](crate::api::fused::Fused),
+/// [Once](crate::api::once::Once) and [Lazy](crate::api::lazy::Lazy).
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub enum LockError {
+    /// Waiting for the write lock would deadlock, because the current thread holds it, or with
+    /// the `deadlock-detection` feature, because a cycle of waiting threads that includes the
+    /// current thread does.
+    Cycle {
+        /// The thread holding the write lock, if the backend records it.
+        #[cfg(feature = "std")]
+        owner: Option<crate::sync::OwnerThread>,
+        /// Where the write lock was obtained, if the backend records it.
+        location: Option<&'static Location<'static>>,
+    },
+    /// A panic while the write lock was held poisoned the cell.
+    Poisoned {
+        /// What poisoned the cell, if the backend records it.
+        cause: Option<PoisonCause>,
+    },
+}
+
+/// What poisoned a cell.
+#[derive(Debug, Clone)]
+pub struct PoisonCause {
+    location: &'static Location<'static>,
+    #[cfg(feature = "std")]
+    message: Option<String>,
+}
+
+impl PoisonCause {
+    #[cfg(feature = "std")]
+    pub(crate) fn new(location: &'static Location<'static>, message: Option<String>) -> Self {
+        PoisonCause { location, message }
+    }
+
+    /// Where the write lock held during the panic was obtained.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// The message of the panic, if the cell was poisoned by a panicking initializer rather than
+    /// by dropping a write guard. A payload that is not a string is described as `Box<dyn Any>`.
+    #[cfg(feature = "std")]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+impl Display for PoisonCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the holder of the write lock obtained at {} panicked",
+            self.location
+        )?;
+        #[cfg(feature = "std")]
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for LockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Cycle { location, .. } => {
+                f.write_str("deadlock: waiting for the write lock would never finish")?;
+                #[cfg(feature = "std")]
+                if let LockError::Cycle {
+                    owner: Some(owner), ..
+                } = self
+                {
+                    write!(f, ", held by {}", owner)?;
+                }
+                if let Some(location) = location {
+                    write!(f, " since {}", location)?;
+                }
+                Ok(())
+            }
+            LockError::Poisoned { cause } => {
+                f.write_str("poisoned lock: another task failed inside")?;
+                if let Some(cause) = cause {
+                    write!(f, " ({})", cause)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl core::error::Error for LockError {}
+
+impl From<TryLockError<()>> for LockError {
+    fn from(error: TryLockError<()>) -> Self {
+        match error {
+            TryLockError::WouldBlock => LockError::Cycle {
+                #[cfg(feature = "std")]
+                owner: None,
+                location: None,
+            },
+            TryLockError::Poisoned(_) => LockError::Poisoned { cause: None },
+        }
+    }
+}
+
+impl From<PoisonError<()>> for LockError {
+    fn from(_: PoisonError<()>) -> Self {
+        LockError::Poisoned { cause: None }
+    }
+}
+
+impl From<LockError> for TryLockError<()> {
+    fn from(error: LockError) -> Self {
+        match error {
+            LockError::Cycle { .. } => TryLockError::WouldBlock,
+            LockError::Poisoned { .. } => TryLockError::Poisoned(PoisonError::new(())),
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub use no_std::*;
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use core::error::Error;
+    use core::fmt::{Debug, Display, Formatter};
+
+    /// A lock was poisoned by a panic while it was held.
+    pub struct PoisonError<T> {
+        data: T,
+    }
+
+    /// A lock could not be acquired.
+    pub enum TryLockError<T> {
+        Poisoned(PoisonError<T>),
+        WouldBlock,
+    }
+
+    impl<T> PoisonError<T> {
+        pub fn new(data: T) -> Self {
+            PoisonError { data }
+        }
+        pub fn into_inner(self) -> T {
+            self.data
+        }
+        pub fn get_ref(&self) -> &T {
+            &self.data
+        }
+        pub fn get_mut(&mut self) -> &mut T {
+            &mut self.data
+        }
+    }
+
+    impl<T> From<PoisonError<T>> for TryLockError<T> {
+        fn from(error: PoisonError<T>) -> Self {
+            TryLockError::Poisoned(error)
+        }
+    }
+
+    impl<T> Debug for PoisonError<T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("PoisonError").finish_non_exhaustive()
+        }
+    }
+
+    impl<T> Display for PoisonError<T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            f.write_str("poisoned lock: another task failed inside")
+        }
+    }
+
+    impl<T> Error for PoisonError<T> {}
+
+    impl<T> Debug for TryLockError<T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            match self {
+                TryLockError::Poisoned(e) => f.debug_tuple("Poisoned").field(e).finish(),
+                TryLockError::WouldBlock => f.write_str("WouldBlock"),
+            }
+        }
+    }
+
+    impl<T> Display for TryLockError<T> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            match self {
+                TryLockError::Poisoned(e) => Display::fmt(e, f),
+                TryLockError::WouldBlock => {
+                    f.write_str("try_lock failed because the operation would block")
+                }
+            }
+        }
+    }
+
+    impl<T> Error for TryLockError<T> {}
+}
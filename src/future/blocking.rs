@@ -0,0 +1,50 @@
+use crate::api::once::{Once, OnceEntry};
+use crate::sync::RawFusedLock;
+use std::future::Future;
+use std::panic::{resume_unwind, Location};
+
+impl<T: Send + 'static> Once<RawFusedLock, T> {
+    /// Return the value, running `init` with [spawn_blocking](::tokio::task::spawn_blocking) if
+    /// necessary, so that a slow synchronous initializer does not stall the runtime's worker
+    /// threads. Other tasks wait on wakers instead of blocking their threads. The write lock is held
+    /// by the awaiting future rather than its thread, so synchronous callers on any thread wait
+    /// for `init` instead of reporting a cycle. If `init` panics, the
+    /// cell is poisoned and the panic resumes in the awaiting task. Must be called within a tokio
+    /// runtime.
+    /// ```
+    /// # #[cfg(feature = "tokio")] {
+    /// use safe_once::sync::OnceLock;
+    /// static TABLE: OnceLock<Vec<u64>> = OnceLock::new();
+    /// async fn table() -> &'static [u64] {
+    ///     TABLE
+    ///         .get_or_init_blocking(|| (0..1000).map(|x| x * x).collect())
+    ///         .await
+    /// }
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn get_or_init_blocking(
+        &self,
+        init: impl FnOnce() -> T + Send + 'static,
+    ) -> impl Future<Output = &T> {
+        let caller = Location::caller();
+        async move {
+            let guard = match self.lock_async_checked().await {
+                Ok(OnceEntry::Occupied(value)) => return value,
+                Ok(OnceEntry::Vacant(guard)) => guard,
+                Err(e) => self.fail_lock(e, caller),
+            };
+            match ::tokio::task::spawn_blocking(init).await {
+                Ok(value) => guard.init(value),
+                Err(e) => match e.try_into_panic() {
+                    // The guard is dropped while panicking, which poisons the cell.
+                    Ok(payload) => resume_unwind(payload),
+                    Err(e) => {
+                        drop(guard);
+                        panic!("initializer was cancelled: {}", e)
+                    }
+                },
+            }
+        }
+    }
+}
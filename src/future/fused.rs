@@ -0,0 +1,249 @@
+use crate::api::raw::RawFusedState;
+use crate::error::LockError;
+use crate::future::raw::{fail, lock_error};
+use crate::future::{timeout, RawFusedAsync};
+use std::cell::UnsafeCell;
+use std::fmt::{Debug, Formatter};
+use std::future::{poll_fn, Future};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::panic::Location;
+use std::sync::PoisonError;
+use std::thread;
+use std::time::Duration;
+
+/// An async [Fused](crate::api::fused::Fused): a mutex whose guard may be held across `.await`
+/// points, and which can be made permanently read-only with [AsyncFusedGuard::fuse]. Readers
+/// wait until it is fused.
+/// ```
+/// # #[cfg(feature = "async")] {
+/// use safe_once::future::{AsyncFusedEntry, AsyncFusedLock};
+/// static STARTUP: AsyncFusedLock<Vec<&str>> = AsyncFusedLock::new(Vec::new());
+/// async fn start() {
+///     if let AsyncFusedEntry::Write(mut steps) = STARTUP.write().await {
+///         steps.push("config");
+///         steps.push("pools");
+///         steps.fuse();
+///     }
+/// }
+/// async fn steps() -> &'static [&'static str] {
+///     STARTUP.read().await
+/// }
+/// # }
+/// ```
+pub struct AsyncFused<R: RawFusedAsync, T> {
+    raw: R,
+    data: UnsafeCell<T>,
+}
+
+pub enum AsyncFusedEntry<'a, R: RawFusedAsync, T> {
+    Read(&'a T),
+    Write(AsyncFusedGuard<'a, R, T>),
+}
+
+/// The write lock of an [AsyncFused]. Dropping the guard unlocks, or poisons if the thread is
+/// panicking.
+pub struct AsyncFusedGuard<'a, R: RawFusedAsync, T> {
+    lock: &'a AsyncFused<R, T>,
+}
+
+impl<R: RawFusedAsync, T> AsyncFused<R, T> {
+    pub const fn new(value: T) -> Self {
+        AsyncFused {
+            raw: R::UNLOCKED,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    pub const fn new_fused(value: T) -> Self {
+        AsyncFused {
+            raw: R::READ,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    unsafe fn read_unchecked(&self) -> &T {
+        unsafe { &*self.data.get() }
+    }
+
+    /// Obtain the write lock, or a reference to the value if fused.
+    #[track_caller]
+    pub fn write_checked(
+        &self,
+    ) -> impl Future<Output = Result<AsyncFusedEntry<'_, R, T>, LockError>> {
+        let caller = Location::caller();
+        async move {
+            Ok(
+                match poll_fn(|cx| self.raw.poll_write_checked(cx, caller))
+                    .await
+                    .map_err(|e| lock_error(&self.raw, e))?
+                {
+                    RawFusedState::Read => AsyncFusedEntry::Read(unsafe { self.read_unchecked() }),
+                    RawFusedState::Write => AsyncFusedEntry::Write(AsyncFusedGuard { lock: self }),
+                },
+            )
+        }
+    }
+
+    /// Like [AsyncFused::write_checked], but panics if poisoned or on a cycle.
+    #[track_caller]
+    pub fn write(&self) -> impl Future<Output = AsyncFusedEntry<'_, R, T>> {
+        let caller = Location::caller();
+        let future = self.write_checked();
+        async move {
+            match future.await {
+                Ok(entry) => entry,
+                Err(e) => fail(&self.raw, e, caller),
+            }
+        }
+    }
+
+    /// Return the value if fused, without waiting.
+    pub fn try_read_checked(&self) -> Result<Option<&T>, PoisonError<()>> {
+        match self.raw.try_read_checked()? {
+            RawFusedState::Read => Ok(Some(unsafe { self.read_unchecked() })),
+            RawFusedState::Write => Ok(None),
+        }
+    }
+
+    /// Return the value if fused, without waiting. Panics if poisoned.
+    pub fn try_read(&self) -> Option<&T> {
+        self.try_read_checked().unwrap()
+    }
+
+    /// Wait until the value is fused.
+    pub async fn read_checked(&self) -> Result<&T, LockError> {
+        poll_fn(|cx| self.raw.poll_read_checked(cx))
+            .await
+            .map_err(|e| lock_error(&self.raw, e))?;
+        Ok(unsafe { self.read_unchecked() })
+    }
+
+    /// Wait until the value is fused. Panics if poisoned or on a cycle.
+    #[track_caller]
+    pub fn read(&self) -> impl Future<Output = &T> {
+        let caller = Location::caller();
+        async move {
+            match self.read_checked().await {
+                Ok(value) => value,
+                Err(e) => fail(&self.raw, e, caller),
+            }
+        }
+    }
+
+    /// Like [AsyncFused::write], but returns None if the write lock is not available within
+    /// `duration`.
+    #[track_caller]
+    pub fn write_timeout(
+        &self,
+        duration: Duration,
+    ) -> impl Future<Output = Option<AsyncFusedEntry<'_, R, T>>> {
+        timeout(duration, self.write())
+    }
+
+    /// Like [AsyncFused::read], but returns None if the value is not fused within
+    /// `duration`.
+    #[track_caller]
+    pub fn read_timeout(&self, duration: Duration) -> impl Future<Output = Option<&T>> {
+        timeout(duration, self.read())
+    }
+
+    pub fn get_mut(&mut self) -> (Result<RawFusedState, PoisonError<()>>, &mut T) {
+        (self.raw.try_get_mut(), self.data.get_mut())
+    }
+
+    /// Make a poisoned AsyncFused writeable again using exclusive access, keeping the value left
+    /// by the panicking writer. Does nothing unless poisoned.
+    pub fn clear_poison(&mut self) {
+        if self.raw.try_get_mut().is_err() {
+            self.raw = R::UNLOCKED;
+        }
+    }
+
+    pub fn into_inner(mut self) -> (Result<RawFusedState, PoisonError<()>>, T) {
+        (self.raw.try_get_mut(), self.data.into_inner())
+    }
+}
+
+impl<'a, R: RawFusedAsync, T> AsyncFusedGuard<'a, R, T> {
+    /// Make the value permanently read-only, waking all readers.
+    pub fn fuse(self) -> &'a T {
+        let lock = self.lock;
+        mem::forget(self);
+        unsafe {
+            lock.raw.unlock_fuse();
+            lock.read_unchecked()
+        }
+    }
+}
+
+impl<'a, R: RawFusedAsync, T> Deref for AsyncFusedGuard<'a, R, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, R: RawFusedAsync, T> DerefMut for AsyncFusedGuard<'a, R, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<'a, R: RawFusedAsync, T> Drop for AsyncFusedGuard<'a, R, T> {
+    fn drop(&mut self) {
+        unsafe {
+            if thread::panicking() {
+                self.lock.raw.unlock_poison()
+            } else {
+                self.lock.raw.unlock()
+            }
+        }
+    }
+}
+
+impl<'a, R: RawFusedAsync, T> AsyncFusedEntry<'a, R, T> {
+    /// Apply `update` and fuse if still writeable, and return the fused value.
+    pub fn or_fuse(self, update: impl FnOnce(&mut T)) -> &'a T {
+        match self {
+            AsyncFusedEntry::Read(value) => value,
+            AsyncFusedEntry::Write(mut guard) => {
+                update(&mut guard);
+                guard.fuse()
+            }
+        }
+    }
+}
+
+impl<R: RawFusedAsync, T: Default> Default for AsyncFused<R, T> {
+    fn default() -> Self {
+        AsyncFused::new(T::default())
+    }
+}
+
+impl<R: RawFusedAsync, T: Debug> Debug for AsyncFused<R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut f = f.debug_tuple("AsyncFused");
+        match self.try_read_checked() {
+            Ok(Some(value)) => f.field(value),
+            Ok(None) => f.field(&format_args!("<unfused>")),
+            Err(_) => f.field(&format_args!("<poisoned>")),
+        };
+        f.finish()
+    }
+}
+
+impl<'a, R: RawFusedAsync, T: Debug> Debug for AsyncFusedGuard<'a, R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+// The guard gives exclusive access to the value on any thread, and readers share it.
+unsafe impl<R: RawFusedAsync + Send, T: Send> Send for AsyncFused<R, T> {}
+
+unsafe impl<R: RawFusedAsync + Send + Sync, T: Send + Sync> Sync for AsyncFused<R, T> {}
+
+unsafe impl<'a, R: RawFusedAsync + Sync, T: Send + Sync> Send for AsyncFusedGuard<'a, R, T> {}
+
+unsafe impl<'a, R: RawFusedAsync + Sync, T: Sync> Sync for AsyncFusedGuard<'a, R, T> {}
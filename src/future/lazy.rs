@@ -0,0 +1,100 @@
+use crate::error::LockError;
+use crate::future::raw::fail;
+use crate::future::{timeout, AsyncOnce, RawFusedAsync};
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::panic::Location;
+use std::pin::Pin;
+use std::sync::PoisonError;
+use std::time::Duration;
+
+/// A boxed future, for naming the type of an [AsyncLazy] initializer.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// An async [Lazy](crate::api::lazy::Lazy), initialized by the future returned by its
+/// initializer. The initializer is called again if a previous initializing future was dropped
+/// before completing. If an initializing future panics, the cell is poisoned.
+/// ```
+/// use safe_once::future::AsyncLazyLock;
+/// static GREETING: AsyncLazyLock<String> =
+///     AsyncLazyLock::new(|| Box::pin(async { "hello".to_string() }));
+/// async fn greeting() -> &'static str {
+///     GREETING.force().await
+/// }
+/// assert_eq!(GREETING.get(), None);
+/// ```
+pub struct AsyncLazy<R: RawFusedAsync, T, F = fn() -> BoxFuture<'static, T>> {
+    once: AsyncOnce<R, T>,
+    init: F,
+}
+
+impl<R: RawFusedAsync, T, F> AsyncLazy<R, T, F> {
+    pub const fn new(init: F) -> Self {
+        AsyncLazy {
+            once: AsyncOnce::new(),
+            init,
+        }
+    }
+
+    /// Return the value if initialized, without waiting.
+    pub fn get_checked(&self) -> Result<Option<&T>, PoisonError<()>> {
+        self.once.try_get_checked()
+    }
+
+    /// Return the value if initialized, without waiting. Panics if poisoned.
+    pub fn get(&self) -> Option<&T> {
+        self.once.try_get()
+    }
+
+    /// Make a poisoned AsyncLazy unforced using exclusive access, so that the next caller runs
+    /// its initializer again. Does nothing unless poisoned.
+    pub fn clear_poison(&mut self) {
+        self.once.clear_poison();
+    }
+
+    pub fn into_inner(self) -> Option<T> {
+        self.once.into_inner()
+    }
+}
+
+impl<R: RawFusedAsync, T, Fut: Future<Output = T>, F: Fn() -> Fut> AsyncLazy<R, T, F> {
+    /// Initialize the value if necessary and return it.
+    #[track_caller]
+    pub fn force_checked(&self) -> impl Future<Output = Result<&T, LockError>> {
+        self.once.get_or_init_checked(&self.init)
+    }
+
+    /// Initialize the value if necessary and return it. Panics if poisoned or on a cycle.
+    #[track_caller]
+    pub fn force(&self) -> impl Future<Output = &T> {
+        let caller = Location::caller();
+        let future = self.force_checked();
+        async move {
+            match future.await {
+                Ok(value) => value,
+                Err(e) => fail(self.once.raw(), e, caller),
+            }
+        }
+    }
+}
+
+impl<R: RawFusedAsync, T, Fut: Future<Output = T>, F: Fn() -> Fut> AsyncLazy<R, T, F> {
+    /// Like [AsyncLazy::force], but returns None if the value is not available within
+    /// `duration`. The cell remains uninitialized if this call was running the initializer.
+    #[track_caller]
+    pub fn force_timeout(&self, duration: Duration) -> impl Future<Output = Option<&T>> {
+        timeout(duration, self.force())
+    }
+}
+
+impl<R: RawFusedAsync, T: Debug, F> Debug for AsyncLazy<R, T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut f = f.debug_tuple("AsyncLazy");
+        match self.get_checked() {
+            Ok(Some(value)) => f.field(value),
+            Ok(None) => f.field(&format_args!("<uninit>")),
+            Err(_) => f.field(&format_args!("<poisoned>")),
+        };
+        f.finish()
+    }
+}
@@ -0,0 +1,47 @@
+//! Once cells for async code, behind the `async` feature.
+//!
+//! Waiting tasks register their [Waker](std::task::Waker) instead of parking their thread, so
+//! these types work with any executor. As with the synchronous types, a cycle is reported
+//! instead of hanging: an initializer that awaits its own cell fails immediately.
+//!
+//! Like the synchronous types, the cells are generic over their lock, a [RawFusedAsync]. The
+//! `Lock` aliases use [RawAsyncLock].
+//!
+//! The feature also adds [OnceLock::wait_async](crate::api::once::Once::wait_async), so that
+//! tasks can await a value that synchronous code initializes. The `tokio` feature adds
+//! [OnceLock::get_or_init_blocking](crate::api::once::Once::get_or_init_blocking), which runs a
+//! synchronous initializer on tokio's blocking thread pool.
+//! ```
+//! # #[cfg(feature = "async")] {
+//! use safe_once::future::AsyncOnceLock;
+//! static CONFIG: AsyncOnceLock<String> = AsyncOnceLock::new();
+//! async fn config() -> &'static str {
+//!     CONFIG.get_or_init(|| async { "loaded".to_string() }).await
+//! }
+//! # }
+//! ```
+
+#[cfg(feature = "tokio")]
+mod blocking;
+mod fused;
+mod lazy;
+mod once;
+mod raw;
+mod raw_async_lock;
+#[cfg(test)]
+mod test;
+mod timer;
+mod wait;
+
+pub use fused::*;
+pub use lazy::*;
+pub use once::*;
+pub use raw::RawFusedAsync;
+pub use raw_async_lock::*;
+pub use timer::*;
+pub(crate) use wait::{register, wake_all};
+pub use wait::{LockAsync, WaitAsync};
+
+pub type AsyncOnceLock<T> = AsyncOnce<RawAsyncLock, T>;
+pub type AsyncLazyLock<T, F = fn() -> BoxFuture<'static, T>> = AsyncLazy<RawAsyncLock, T, F>;
+pub type AsyncFusedLock<T> = AsyncFused<RawAsyncLock, T>;
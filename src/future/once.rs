@@ -0,0 +1,281 @@
+use crate::api::raw::RawFusedState;
+use crate::error::LockError;
+use crate::future::raw::{fail, lock_error, Polling};
+use crate::future::{timeout, RawFusedAsync};
+use std::cell::UnsafeCell;
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::future::{poll_fn, Future};
+use std::mem::{self, MaybeUninit};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe, Location};
+use std::pin::pin;
+use std::sync::PoisonError;
+use std::task::Poll;
+use std::thread;
+use std::time::Duration;
+
+/// An async [Once](crate::api::once::Once). The initializing future runs to completion at most
+/// once; concurrent callers wait for it without blocking their thread. If the initializing future
+/// panics the cell is poisoned. Callers that panic on poison resume a copy of the initializer's
+/// panic payload instead of a generic message, and the `_checked` methods return
+/// [LockError::Poisoned] with its message. If the initializing future is dropped before
+/// completing, another caller runs its own initializer.
+pub struct AsyncOnce<R: RawFusedAsync, T> {
+    raw: R,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Releases the write lock if initialization does not complete.
+struct Unlock<'a, R: RawFusedAsync>(&'a R);
+
+impl<'a, R: RawFusedAsync> Drop for Unlock<'a, R> {
+    fn drop(&mut self) {
+        unsafe {
+            if thread::panicking() {
+                self.0.unlock_poison()
+            } else {
+                self.0.unlock()
+            }
+        }
+    }
+}
+
+impl<R: RawFusedAsync, T> AsyncOnce<R, T> {
+    pub const fn new() -> Self {
+        AsyncOnce {
+            raw: R::UNLOCKED,
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    pub const fn new_init(value: T) -> Self {
+        AsyncOnce {
+            raw: R::READ,
+            value: UnsafeCell::new(MaybeUninit::new(value)),
+        }
+    }
+
+    pub(crate) fn raw(&self) -> &R {
+        &self.raw
+    }
+
+    unsafe fn value(&self) -> &T {
+        unsafe { (*self.value.get()).assume_init_ref() }
+    }
+
+    /// Return the value if initialized, without waiting.
+    pub fn try_get_checked(&self) -> Result<Option<&T>, PoisonError<()>> {
+        match self.raw.try_read_checked()? {
+            RawFusedState::Read => Ok(Some(unsafe { self.value() })),
+            RawFusedState::Write => Ok(None),
+        }
+    }
+
+    /// Return the value if initialized, without waiting. Panics if poisoned.
+    pub fn try_get(&self) -> Option<&T> {
+        self.try_get_checked().unwrap()
+    }
+
+    /// Return the value, initializing it with the future returned by `init` if necessary.
+    #[track_caller]
+    pub fn get_or_try_init_checked<E, F: Future<Output = Result<T, E>>>(
+        &self,
+        init: impl FnOnce() -> F,
+    ) -> impl Future<Output = Result<Result<&T, E>, LockError>> {
+        let caller = Location::caller();
+        async move {
+            match poll_fn(|cx| self.raw.poll_write_checked(cx, caller))
+                .await
+                .map_err(|e| lock_error(&self.raw, e))?
+            {
+                RawFusedState::Read => return Ok(Ok(unsafe { self.value() })),
+                RawFusedState::Write => {}
+            }
+            let unlock = Unlock(&self.raw);
+            let mut init = pin!({
+                let _polling = Polling::new(&self.raw);
+                init()
+            });
+            let value = poll_fn(|cx| {
+                let _polling = Polling::new(&self.raw);
+                match catch_unwind(AssertUnwindSafe(|| init.as_mut().poll(cx))) {
+                    Ok(poll) => poll.map(Ok),
+                    Err(payload) => Poll::Ready(Err(payload)),
+                }
+            })
+            .await;
+            let value = match value {
+                Ok(value) => value,
+                Err(payload) => {
+                    // Record the message for other waiters, and propagate the original panic.
+                    mem::forget(unlock);
+                    unsafe { self.raw.unlock_panic(&*payload) };
+                    resume_unwind(payload)
+                }
+            };
+            match value {
+                Ok(value) => {
+                    unsafe { (*self.value.get()).write(value) };
+                    mem::forget(unlock);
+                    unsafe { self.raw.unlock_fuse() };
+                    Ok(Ok(unsafe { self.value() }))
+                }
+                Err(e) => Ok(Err(e)),
+            }
+        }
+    }
+
+    /// Return the value, initializing it with the future returned by `init` if necessary. If the
+    /// future fails, the cell remains uninitialized. Panics if poisoned or on a cycle.
+    #[track_caller]
+    pub fn get_or_try_init<E, F: Future<Output = Result<T, E>>>(
+        &self,
+        init: impl FnOnce() -> F,
+    ) -> impl Future<Output = Result<&T, E>> {
+        let caller = Location::caller();
+        let future = self.get_or_try_init_checked(init);
+        async move {
+            match future.await {
+                Ok(result) => result,
+                Err(e) => fail(&self.raw, e, caller),
+            }
+        }
+    }
+
+    /// Return the value, initializing it with the future returned by `init` if necessary.
+    #[track_caller]
+    pub fn get_or_init_checked<F: Future<Output = T>>(
+        &self,
+        init: impl FnOnce() -> F,
+    ) -> impl Future<Output = Result<&T, LockError>> {
+        let future = self.get_or_try_init_checked(|| {
+            let init = init();
+            async move { Ok::<T, Infallible>(init.await) }
+        });
+        async move {
+            match future.await? {
+                Ok(value) => Ok(value),
+                Err(e) => match e {},
+            }
+        }
+    }
+
+    /// Return the value, initializing it with the future returned by `init` if necessary. Panics
+    /// if poisoned or on a cycle.
+    #[track_caller]
+    pub fn get_or_init<F: Future<Output = T>>(
+        &self,
+        init: impl FnOnce() -> F,
+    ) -> impl Future<Output = &T> {
+        let caller = Location::caller();
+        let future = self.get_or_init_checked(init);
+        async move {
+            match future.await {
+                Ok(value) => value,
+                Err(e) => fail(&self.raw, e, caller),
+            }
+        }
+    }
+
+    /// Wait until the value is initialized by another task.
+    pub async fn wait_checked(&self) -> Result<&T, LockError> {
+        poll_fn(|cx| self.raw.poll_read_checked(cx))
+            .await
+            .map_err(|e| lock_error(&self.raw, e))?;
+        Ok(unsafe { self.value() })
+    }
+
+    /// Wait until the value is initialized by another task. Panics if poisoned or on a cycle.
+    #[track_caller]
+    pub fn wait(&self) -> impl Future<Output = &T> {
+        let caller = Location::caller();
+        async move {
+            match self.wait_checked().await {
+                Ok(value) => value,
+                Err(e) => fail(&self.raw, e, caller),
+            }
+        }
+    }
+
+    /// Like [AsyncOnce::get_or_init], but returns None if the value is not available
+    /// within `duration`. If this call was running the initializer, the initializer is dropped
+    /// and the cell remains uninitialized; otherwise the other initializer keeps the cell.
+    #[track_caller]
+    pub fn get_or_init_timeout<F: Future<Output = T>>(
+        &self,
+        init: impl FnOnce() -> F,
+        duration: Duration,
+    ) -> impl Future<Output = Option<&T>> {
+        timeout(duration, self.get_or_init(init))
+    }
+
+    /// Like [AsyncOnce::wait], but returns None if the value is not initialized within
+    /// `duration`.
+    #[track_caller]
+    pub fn wait_timeout(&self, duration: Duration) -> impl Future<Output = Option<&T>> {
+        timeout(duration, self.wait())
+    }
+
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        match self.raw.try_get_mut() {
+            Ok(RawFusedState::Read) => Some(unsafe { self.value.get_mut().assume_init_mut() }),
+            _ => None,
+        }
+    }
+
+    /// Make a poisoned AsyncOnce uninitialized using exclusive access, so that the next caller
+    /// runs its initializer again. Does nothing unless poisoned.
+    pub fn clear_poison(&mut self) {
+        if self.raw.try_get_mut().is_err() {
+            self.raw = R::UNLOCKED;
+        }
+    }
+
+    pub fn into_inner(mut self) -> Option<T> {
+        match self.raw.try_get_mut() {
+            Ok(RawFusedState::Read) => {
+                let value = unsafe { self.value.get_mut().assume_init_read() };
+                mem::forget(self);
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<R: RawFusedAsync, T> Drop for AsyncOnce<R, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.get_mut() {
+            unsafe { std::ptr::drop_in_place(value) }
+        }
+    }
+}
+
+impl<R: RawFusedAsync, T> Default for AsyncOnce<R, T> {
+    fn default() -> Self {
+        AsyncOnce::new()
+    }
+}
+
+impl<R: RawFusedAsync, T> From<T> for AsyncOnce<R, T> {
+    fn from(value: T) -> Self {
+        AsyncOnce::new_init(value)
+    }
+}
+
+impl<R: RawFusedAsync, T: Debug> Debug for AsyncOnce<R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut f = f.debug_tuple("AsyncOnce");
+        match self.try_get_checked() {
+            Ok(Some(value)) => f.field(value),
+            Ok(None) => f.field(&format_args!("<uninit>")),
+            Err(_) => f.field(&format_args!("<poisoned>")),
+        };
+        f.finish()
+    }
+}
+
+// The value is shared between tasks on different threads through `&self`.
+unsafe impl<R: RawFusedAsync + Send, T: Send> Send for AsyncOnce<R, T> {}
+
+unsafe impl<R: RawFusedAsync + Send + Sync, T: Send + Sync> Sync for AsyncOnce<R, T> {}
@@ -0,0 +1,157 @@
+//! The async counterpart of [RawFused](crate::api::raw::RawFused), which the async wrappers are
+//! generic over.
+
+use crate::api::raw::{payload_message, RawFusedState};
+use crate::error::{LockError, PoisonCause};
+use std::any::Any;
+use std::panic::{resume_unwind, Location};
+use std::sync::{PoisonError, TryLockError};
+use std::task::{Context, Poll};
+
+/// A lock that can be fused like a [RawFused](crate::api::raw::RawFused), but whose waiters are
+/// tasks instead of threads. Implementing this trait lets a custom waiting strategy, such as one
+/// backed by an executor's own notification primitive, reuse [AsyncOnce](crate::future::AsyncOnce),
+/// [AsyncLazy](crate::future::AsyncLazy) and [AsyncFused](crate::future::AsyncFused).
+///
+/// The states are the same as those of a RawFused. A poll that returns [Poll::Pending] must
+/// arrange for the task to be woken when the state changes.
+///
+/// # Safety
+/// Implementations must provide the memory ordering of a mutex: a transition out of WRITE must
+/// happen-before any caller that subsequently observes the new state.
+pub unsafe trait RawFusedAsync: 'static {
+    const UNLOCKED: Self;
+    const READ: Self;
+    const POISON: Self;
+
+    /// Attempt to obtain a write lock.
+    /// * On UNLOCKED, transition to WRITE and return Write.
+    /// * On WRITE, return Pending, or WouldBlock if a cycle is detected.
+    /// * On READ, return Read.
+    /// * On POISON, return Poisoned.
+    fn poll_write_checked(
+        &self,
+        cx: &mut Context,
+        caller: &'static Location<'static>,
+    ) -> Poll<Result<RawFusedState, TryLockError<()>>>;
+
+    /// Wait until READ or POISON.
+    /// * On UNLOCKED or WRITE, return Pending, or WouldBlock if a cycle is detected.
+    /// * On READ, return Ok.
+    /// * On POISON, return Poisoned.
+    fn poll_read_checked(&self, cx: &mut Context) -> Poll<Result<(), TryLockError<()>>>;
+
+    /// Return the current state without waiting.
+    fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>>;
+
+    /// Called before the holder of the write lock polls its initializer, and paired with a call
+    /// to [RawFusedAsync::exit] afterwards, so that the backend can detect a holder waiting for
+    /// itself. The default implementation does nothing.
+    fn enter(&self) {}
+
+    /// See [RawFusedAsync::enter].
+    fn exit(&self) {}
+
+    /// If the write lock is held, the location of the call that obtained it, if recorded.
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        None
+    }
+
+    /// Transition from WRITE to UNLOCKED, waking waiters.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock. Other states cause undefined behavior.
+    unsafe fn unlock(&self);
+
+    /// Transition from WRITE to POISON, waking waiters.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock. Other states cause undefined behavior.
+    unsafe fn unlock_poison(&self);
+
+    /// Transition from WRITE to POISON like [RawFusedAsync::unlock_poison], recording the
+    /// payload of the panic that poisoned the lock for [RawFusedAsync::panic_payload]. The
+    /// default implementation discards it.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock. Other states cause undefined behavior.
+    unsafe fn unlock_panic(&self, payload: &(dyn Any + Send)) {
+        let _ = payload;
+        unsafe { self.unlock_poison() }
+    }
+
+    /// If poisoned by [RawFusedAsync::unlock_panic], a copy of the panic's payload, which callers
+    /// that panic on poison resume. A payload is only copied if it is a `&'static str` or a
+    /// `String`, as are those of [panic!]; others are replaced by a String describing them.
+    fn panic_payload(&self) -> Option<Box<dyn Any + Send>> {
+        None
+    }
+
+    /// Transition from WRITE to READ, waking waiters.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock. Other states cause undefined behavior.
+    unsafe fn unlock_fuse(&self);
+
+    /// Return the current state.
+    fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>>;
+}
+
+/// Marks the holder of a [RawFusedAsync] as being polled until dropped. See
+/// [RawFusedAsync::enter].
+pub(crate) struct Polling<'a, R: RawFusedAsync>(&'a R);
+
+impl<'a, R: RawFusedAsync> Polling<'a, R> {
+    pub(crate) fn new(raw: &'a R) -> Self {
+        raw.enter();
+        Polling(raw)
+    }
+}
+
+impl<'a, R: RawFusedAsync> Drop for Polling<'a, R> {
+    fn drop(&mut self) {
+        self.0.exit();
+    }
+}
+
+/// Describe `error`, returned by `raw`, as a [LockError].
+pub(crate) fn lock_error<R: RawFusedAsync>(
+    raw: &R,
+    error: impl Into<TryLockError<()>>,
+) -> LockError {
+    match error.into() {
+        TryLockError::WouldBlock => LockError::Cycle {
+            owner: None,
+            location: raw.owner_location(),
+        },
+        TryLockError::Poisoned(_) => LockError::Poisoned {
+            cause: raw.owner_location().map(|location| {
+                let message = raw
+                    .panic_payload()
+                    .map(|payload| payload_message(&*payload));
+                PoisonCause::new(location, message)
+            }),
+        },
+    }
+}
+
+/// Panic with a message describing `error`, which was returned to a request at `caller`. If the
+/// lock was poisoned by a panic, resume a copy of that panic's payload instead.
+pub(crate) fn fail<R: RawFusedAsync>(raw: &R, error: LockError, caller: &Location) -> ! {
+    match error {
+        LockError::Cycle { location, .. } => match location {
+            Some(owner) => panic!(
+                "deadlock: write lock obtained at {} was awaited again at {} by its own holder",
+                owner, caller
+            ),
+            None => panic!(
+                "deadlock: write lock was awaited again at {} by its own holder",
+                caller
+            ),
+        },
+        LockError::Poisoned { .. } => match raw.panic_payload() {
+            Some(payload) => resume_unwind(payload),
+            None => panic!("{}", error),
+        },
+    }
+}
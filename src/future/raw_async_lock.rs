@@ -0,0 +1,221 @@
+use crate::api::raw::{payload_message, RawFusedState};
+use crate::future::RawFusedAsync;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::cell::{RefCell, UnsafeCell};
+use std::panic::Location;
+use std::ptr;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicPtr, AtomicUsize};
+use std::sync::{PoisonError, TryLockError};
+use std::task::{Context, Poll, Waker};
+
+const INIT: usize = 0;
+const LOCKED: usize = 1;
+const READ: usize = 2;
+const POISON: usize = 3;
+
+thread_local! {
+    // The tokens of the futures holding write locks that are currently being polled on this
+    // thread, for cycle detection.
+    static POLLING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+// The number of tokens handed out so far.
+static NEXT_TOKEN: AtomicUsize = AtomicUsize::new(0);
+
+/// The asynchronous counterpart of [RawFusedLock](crate::sync::RawFusedLock): a lock that can be
+/// made permanently read-only, whose waiters are tasks instead of threads.
+pub struct RawAsyncLock {
+    state: AtomicUsize,
+    waiters: Mutex<Vec<Waker>>,
+    // A token identifying the future that obtained the write lock, written by that future.
+    token: AtomicUsize,
+    owner: AtomicPtr<Location<'static>>,
+    // Written by the holder before poisoning, and read only once poisoned.
+    panic: UnsafeCell<Option<Payload>>,
+}
+
+// A copy of the payload of the panic that poisoned the lock.
+enum Payload {
+    Static(&'static str),
+    Owned(String),
+}
+
+impl RawAsyncLock {
+    pub const fn new() -> Self {
+        Self::with_state(INIT)
+    }
+
+    const fn with_state(state: usize) -> Self {
+        RawAsyncLock {
+            state: AtomicUsize::new(state),
+            waiters: Mutex::new(Vec::new()),
+            token: AtomicUsize::new(0),
+            owner: AtomicPtr::new(ptr::null_mut()),
+            panic: UnsafeCell::new(None),
+        }
+    }
+
+    // Whether the future holding the write lock is being polled on this thread. The token is
+    // only compared, so a stale one read while the lock changes hands is harmless: tokens are
+    // never reused, and a future's token is only on the stack while it holds the lock.
+    fn is_polling(&self) -> bool {
+        let token = self.token.load(Relaxed);
+        POLLING.with_borrow(|polling| polling.contains(&token))
+    }
+
+    // Register to be woken when the lock changes state. Returns the state observed after
+    // registering.
+    fn register(&self, cx: &mut Context) -> usize {
+        let mut waiters = self.waiters.lock();
+        if !waiters.iter().any(|waker| waker.will_wake(cx.waker())) {
+            waiters.push(cx.waker().clone());
+        }
+        drop(waiters);
+        self.state.load(Acquire)
+    }
+
+    fn unlock_impl(&self, state: usize) {
+        let mut waiters = self.waiters.lock();
+        self.state.store(state, Release);
+        let waiters = std::mem::take(&mut *waiters);
+        for waiter in waiters {
+            waiter.wake();
+        }
+    }
+}
+
+impl Default for RawAsyncLock {
+    fn default() -> Self {
+        RawAsyncLock::new()
+    }
+}
+
+/// Each future that obtains the write lock is identified by a token. A future that waits for the
+/// lock while the future holding it is being polled on the same thread, such as an initializer
+/// awaiting its own cell, is waiting for itself, so it fails with [TryLockError::WouldBlock]
+/// instead of hanging. A guard of an [AsyncFused](crate::future::AsyncFused) that a task holds
+/// across an `await` is not tied to the task, so a task that awaits the lock while holding the
+/// guard waits forever, as with other async mutexes.
+unsafe impl RawFusedAsync for RawAsyncLock {
+    const UNLOCKED: Self = Self::with_state(INIT);
+    const READ: Self = Self::with_state(READ);
+    const POISON: Self = Self::with_state(POISON);
+
+    fn poll_write_checked(
+        &self,
+        cx: &mut Context,
+        caller: &'static Location<'static>,
+    ) -> Poll<Result<RawFusedState, TryLockError<()>>> {
+        let mut state = self.state.load(Acquire);
+        loop {
+            match state {
+                READ => return Poll::Ready(Ok(RawFusedState::Read)),
+                POISON => return Poll::Ready(Err(TryLockError::Poisoned(PoisonError::new(())))),
+                INIT => match self
+                    .state
+                    .compare_exchange_weak(INIT, LOCKED, Acquire, Acquire)
+                {
+                    Ok(_) => {
+                        self.owner.store(caller as *const _ as *mut _, Relaxed);
+                        self.token
+                            .store(NEXT_TOKEN.fetch_add(1, Relaxed) + 1, Relaxed);
+                        return Poll::Ready(Ok(RawFusedState::Write));
+                    }
+                    Err(new_state) => state = new_state,
+                },
+                _ if self.is_polling() => return Poll::Ready(Err(TryLockError::WouldBlock)),
+                _ => match self.register(cx) {
+                    LOCKED => return Poll::Pending,
+                    new_state => state = new_state,
+                },
+            }
+        }
+    }
+
+    fn poll_read_checked(&self, cx: &mut Context) -> Poll<Result<(), TryLockError<()>>> {
+        let mut state = self.state.load(Acquire);
+        loop {
+            match state {
+                READ => return Poll::Ready(Ok(())),
+                POISON => return Poll::Ready(Err(TryLockError::Poisoned(PoisonError::new(())))),
+                LOCKED if self.is_polling() => return Poll::Ready(Err(TryLockError::WouldBlock)),
+                _ => match self.register(cx) {
+                    new_state if new_state == state => return Poll::Pending,
+                    new_state => state = new_state,
+                },
+            }
+        }
+    }
+
+    fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+        match self.state.load(Acquire) {
+            READ => Ok(RawFusedState::Read),
+            POISON => Err(PoisonError::new(())),
+            _ => Ok(RawFusedState::Write),
+        }
+    }
+
+    fn enter(&self) {
+        let token = self.token.load(Relaxed);
+        POLLING.with_borrow_mut(|polling| polling.push(token));
+    }
+
+    fn exit(&self) {
+        let token = self.token.load(Relaxed);
+        POLLING.with_borrow_mut(|polling| {
+            let index = polling.iter().rposition(|x| *x == token).unwrap();
+            polling.remove(index);
+        });
+    }
+
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        unsafe { self.owner.load(Relaxed).as_ref() }
+    }
+
+    unsafe fn unlock(&self) {
+        self.unlock_impl(INIT)
+    }
+
+    unsafe fn unlock_fuse(&self) {
+        self.unlock_impl(READ)
+    }
+
+    unsafe fn unlock_poison(&self) {
+        self.unlock_impl(POISON)
+    }
+
+    unsafe fn unlock_panic(&self, payload: &(dyn Any + Send)) {
+        let payload = match payload.downcast_ref::<&'static str>() {
+            Some(message) => Payload::Static(message),
+            None => Payload::Owned(payload_message(payload)),
+        };
+        unsafe { *self.panic.get() = Some(payload) };
+        self.unlock_impl(POISON)
+    }
+
+    fn panic_payload(&self) -> Option<Box<dyn Any + Send>> {
+        if self.state.load(Acquire) != POISON {
+            return None;
+        }
+        match unsafe { &*self.panic.get() } {
+            Some(Payload::Static(message)) => Some(Box::new(*message)),
+            Some(Payload::Owned(message)) => Some(Box::new(message.clone())),
+            None => None,
+        }
+    }
+
+    fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+        match *self.state.get_mut() {
+            READ => Ok(RawFusedState::Read),
+            POISON => Err(PoisonError::new(())),
+            _ => Ok(RawFusedState::Write),
+        }
+    }
+}
+
+// The panic payload is only accessed by the holder of the write lock, or once poisoned.
+unsafe impl Send for RawAsyncLock {}
+
+unsafe impl Sync for RawAsyncLock {}
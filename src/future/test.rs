@@ -0,0 +1,524 @@
+use crate::error::LockError;
+use crate::future::AsyncOnceLock;
+use std::future::{poll_fn, Future};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+// Poll `future` once.
+fn poll_once<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    let mut cx = Context::from_waker(Waker::noop());
+    std::pin::Pin::new(future).poll(&mut cx)
+}
+
+async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[test]
+fn test_async_once_lock() {
+    let once = AsyncOnceLock::<usize>::new();
+    assert_eq!(once.try_get(), None);
+    let results: Vec<usize> = thread::scope(|s| {
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let once = &once;
+                s.spawn(move || {
+                    block_on(async {
+                        *once
+                            .get_or_init(|| async move {
+                                for _ in 0..10 {
+                                    yield_now().await;
+                                }
+                                i
+                            })
+                            .await
+                    })
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert!(results.iter().all(|x| *x == results[0]));
+    assert_eq!(block_on(once.wait()), &results[0]);
+    assert_eq!(once.into_inner(), Some(results[0]));
+}
+
+#[test]
+fn test_async_once_lock_cancel() {
+    let once = AsyncOnceLock::<usize>::new();
+    let mut first = Box::pin(once.get_or_init(|| async {
+        yield_now().await;
+        unreachable!()
+    }));
+    assert!(poll_once(&mut first).is_pending());
+    let mut second = Box::pin(once.get_or_init(|| async { 2 }));
+    assert!(poll_once(&mut second).is_pending());
+    drop(first);
+    assert_eq!(poll_once(&mut second), Poll::Ready(&2));
+    assert_eq!(
+        block_on(once.get_or_try_init(|| async { Err::<usize, ()>(()) })),
+        Ok(&2)
+    );
+}
+
+#[test]
+fn test_async_once_lock_cycle() {
+    let once = AsyncOnceLock::<usize>::new();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        block_on(once.get_or_init(|| async { *once.get_or_init(|| async { 1 }).await }))
+    }));
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(
+        message.starts_with("deadlock: write lock obtained at "),
+        "{}",
+        message
+    );
+    assert!(once.try_get_checked().is_err());
+
+    let once = AsyncOnceLock::<usize>::new();
+    assert!(block_on(once.get_or_try_init(|| async { Err(()) })).is_err());
+    assert_eq!(once.try_get(), None);
+    let result = block_on(
+        once.get_or_init_checked(|| async { once.wait_checked().await.copied().unwrap_or(5) }),
+    );
+    assert_eq!(*result.unwrap(), 5);
+}
+
+#[test]
+fn test_async_lazy_lock() {
+    use crate::future::{AsyncLazyLock, BoxFuture};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: AsyncLazyLock<usize> = AsyncLazyLock::new(|| {
+        Box::pin(async {
+            yield_now().await;
+            RUNS.fetch_add(1, Ordering::Relaxed)
+        })
+    });
+    assert_eq!(LAZY.get(), None);
+    let mut cancelled = Box::pin(LAZY.force());
+    assert!(poll_once(&mut cancelled).is_pending());
+    drop(cancelled);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_eq!(*block_on(LAZY.force()), 0));
+        }
+    });
+    assert_eq!(LAZY.get(), Some(&0));
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+
+    let mut poisoned: AsyncLazyLock<usize, fn() -> BoxFuture<'static, usize>> =
+        AsyncLazyLock::new(|| Box::pin(async { panic!("failed") }));
+    assert!(catch_unwind(AssertUnwindSafe(|| block_on(poisoned.force()))).is_err());
+    assert!(poisoned.get_checked().is_err());
+    assert!(block_on(poisoned.force_checked()).is_err());
+    poisoned.clear_poison();
+    assert_eq!(poisoned.get(), None);
+}
+
+#[test]
+fn test_wait_async() {
+    use crate::api::once::OnceEntry;
+    use crate::sync::OnceLock;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let once = OnceLock::<usize>::new();
+    let mut waiting = Box::pin(once.wait_async());
+    assert!(poll_once(&mut waiting).is_pending());
+    drop(waiting);
+    thread::scope(|s| {
+        let waiter = s.spawn(|| *block_on(once.wait_async()));
+        thread::sleep(std::time::Duration::from_millis(10));
+        once.get_or_init(|| 3);
+        assert_eq!(waiter.join().unwrap(), 3);
+    });
+    assert_eq!(block_on(once.wait_async()), &3);
+
+    struct Count(AtomicUsize);
+    impl Wake for Count {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    let once = OnceLock::<usize>::new();
+    let OnceEntry::Vacant(guard) = once.lock() else {
+        unreachable!()
+    };
+    let wakes = Arc::new(Count(AtomicUsize::new(0)));
+    let waker = Waker::from(wakes.clone());
+    let mut waiting = Box::pin(once.wait_async_checked());
+    let mut cx = Context::from_waker(&waker);
+    assert!(waiting.as_mut().poll(&mut cx).is_pending());
+    drop(guard);
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+    assert!(waiting.as_mut().poll(&mut cx).is_pending());
+    once.get_or_init(|| 4);
+    assert!(matches!(
+        waiting.as_mut().poll(&mut cx),
+        Poll::Ready(Ok(&4))
+    ));
+
+    let once = OnceLock::<usize>::new();
+    let OnceEntry::Vacant(guard) = once.lock() else {
+        unreachable!()
+    };
+    let mut locking = Box::pin(once.lock_async_checked());
+    assert!(poll_once(&mut locking).is_pending());
+    drop(guard);
+    let Poll::Ready(Ok(OnceEntry::Vacant(guard))) = poll_once(&mut locking) else {
+        unreachable!()
+    };
+    guard.init(5);
+    assert_eq!(block_on(once.wait_async()), &5);
+
+    // A guard obtained asynchronously is not held by this thread, which waits for it instead of
+    // reporting a cycle.
+    let once = OnceLock::<usize>::new();
+    let Ok(OnceEntry::Vacant(guard)) = block_on(once.lock_async_checked()) else {
+        unreachable!()
+    };
+    thread::scope(|s| {
+        s.spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            guard.init(6);
+        });
+        assert_eq!(once.get_or_init(|| 7), &6);
+    });
+}
+
+#[test]
+fn test_async_fused_lock() {
+    use crate::future::{AsyncFusedEntry, AsyncFusedLock};
+    let fused = AsyncFusedLock::new(Vec::new());
+    assert_eq!(fused.try_read(), None);
+    thread::scope(|s| {
+        let reader = s.spawn(|| block_on(fused.read()).clone());
+        for i in 0..3 {
+            let AsyncFusedEntry::Write(mut guard) = block_on(fused.write()) else {
+                unreachable!()
+            };
+            guard.push(i);
+            block_on(yield_now());
+            guard.push(i + 10);
+        }
+        let AsyncFusedEntry::Write(guard) = block_on(fused.write()) else {
+            unreachable!()
+        };
+        s.spawn(move || assert_eq!(*guard.fuse(), [0, 10, 1, 11, 2, 12]))
+            .join()
+            .unwrap();
+        assert_eq!(reader.join().unwrap(), [0, 10, 1, 11, 2, 12]);
+    });
+    assert!(matches!(block_on(fused.write()), AsyncFusedEntry::Read(_)));
+    assert_eq!(block_on(fused.write()).or_fuse(|_| unreachable!()).len(), 6);
+
+    let mut poisoned = AsyncFusedLock::new(0);
+    assert!(catch_unwind(AssertUnwindSafe(|| {
+        let _guard = block_on(poisoned.write());
+        panic!("failed");
+    }))
+    .is_err());
+    assert!(poisoned.try_read_checked().is_err());
+    assert!(block_on(poisoned.read_checked()).is_err());
+    poisoned.clear_poison();
+    assert_eq!(*block_on(poisoned.write()).or_fuse(|x| *x = 1), 1);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_get_or_init_blocking() {
+    use crate::sync::OnceLock;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static ONCE: OnceLock<usize> = OnceLock::new();
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    static TICKS: AtomicUsize = AtomicUsize::new(0);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let ticker = tokio::spawn(async {
+            while ONCE.try_get().is_none() {
+                TICKS.fetch_add(1, Ordering::Relaxed);
+                yield_now().await;
+            }
+        });
+        let tasks: Vec<_> = (0..3)
+            .map(|i| {
+                tokio::spawn(async move {
+                    *ONCE
+                        .get_or_init_blocking(move || {
+                            RUNS.fetch_add(1, Ordering::Relaxed);
+                            thread::sleep(Duration::from_millis(50));
+                            i
+                        })
+                        .await
+                })
+            })
+            .collect();
+        let mut results = vec![];
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+        ticker.await.unwrap();
+        assert!(results.iter().all(|x| *x == results[0]));
+    });
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+    assert!(TICKS.load(Ordering::Relaxed) > 1);
+
+    let poisoned = Arc::new(OnceLock::<usize>::new());
+    let task = {
+        let poisoned = poisoned.clone();
+        async move { *poisoned.get_or_init_blocking(|| panic!("failed")).await }
+    };
+    let result = runtime.block_on(async { tokio::spawn(task).await });
+    assert!(result.unwrap_err().is_panic());
+    assert!(poisoned.try_get_checked().is_err());
+}
+
+#[test]
+fn test_async_shared_waker() {
+    use crate::future::{AsyncFusedEntry, AsyncFusedLock};
+    // Tasks that share a waker are still distinct owners, so a second task waits for the guard
+    // instead of failing.
+    let fused = AsyncFusedLock::new(0);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut writing = pin!(fused.write());
+    let Poll::Ready(AsyncFusedEntry::Write(guard)) = writing.as_mut().poll(&mut cx) else {
+        unreachable!()
+    };
+    let mut reading = pin!(fused.read_checked());
+    assert!(reading.as_mut().poll(&mut cx).is_pending());
+    let mut locking = pin!(fused.write_checked());
+    assert!(locking.as_mut().poll(&mut cx).is_pending());
+    guard.fuse();
+    assert!(matches!(
+        reading.as_mut().poll(&mut cx),
+        Poll::Ready(Ok(&0))
+    ));
+    assert!(matches!(
+        locking.as_mut().poll(&mut cx),
+        Poll::Ready(Ok(AsyncFusedEntry::Read(&0)))
+    ));
+    let fused = AsyncFusedLock::new(0);
+    thread::scope(|s| {
+        let AsyncFusedEntry::Write(guard) = block_on(fused.write()) else {
+            unreachable!()
+        };
+        let reader = s.spawn(|| *block_on(fused.read()));
+        thread::sleep(std::time::Duration::from_millis(10));
+        guard.fuse();
+        assert_eq!(reader.join().unwrap(), 0);
+    });
+}
+
+#[test]
+fn test_async_timeout() {
+    use crate::future::{timeout, AsyncFusedLock, Sleep};
+    use std::time::{Duration, Instant};
+    let start = Instant::now();
+    block_on(Sleep::new(Duration::from_millis(20)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert_eq!(
+        block_on(timeout(Duration::from_secs(10), async { 1 })),
+        Some(1)
+    );
+    assert_eq!(block_on(timeout(Duration::MAX, async { 2 })), Some(2));
+    assert!(poll_once(&mut Box::pin(Sleep::new(Duration::MAX))).is_pending());
+
+    let once = AsyncOnceLock::<usize>::new();
+    let slow = once.get_or_init_timeout(
+        || async {
+            Sleep::new(Duration::from_secs(10)).await;
+            1
+        },
+        Duration::from_millis(10),
+    );
+    assert_eq!(block_on(slow), None);
+    assert_eq!(once.try_get(), None);
+    assert_eq!(block_on(once.wait_timeout(Duration::from_millis(10))), None);
+    thread::scope(|s| {
+        s.spawn(|| {
+            block_on(once.get_or_init(|| async {
+                Sleep::new(Duration::from_millis(50)).await;
+                2
+            }))
+        });
+        thread::sleep(Duration::from_millis(10));
+        let waiting = once.get_or_init_timeout(|| async { 3 }, Duration::from_millis(1));
+        assert_eq!(block_on(waiting), None);
+        assert_eq!(
+            block_on(once.wait_timeout(Duration::from_secs(10))),
+            Some(&2)
+        );
+    });
+
+    let fused = AsyncFusedLock::new(0);
+    assert_eq!(block_on(fused.read_timeout(Duration::from_millis(1))), None);
+    let guard = block_on(fused.write_timeout(Duration::from_millis(1)));
+    assert!(guard.is_some());
+    assert!(thread::scope(|s| {
+        s.spawn(|| block_on(fused.write_timeout(Duration::from_millis(1))).is_none())
+            .join()
+            .unwrap()
+    }));
+}
+
+#[test]
+fn test_raw_fused_async() {
+    use crate::api::raw::RawFusedState;
+    use crate::future::{AsyncFused, AsyncLazy, AsyncOnce, RawFusedAsync};
+    use std::panic::Location;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::{Acquire, Release};
+    use std::sync::{PoisonError, TryLockError};
+
+    // A backend whose waiters poll again immediately instead of registering their waker.
+    struct Spin(AtomicUsize);
+    fn state(state: usize) -> Result<RawFusedState, PoisonError<()>> {
+        match state {
+            2 => Ok(RawFusedState::Read),
+            3 => Err(PoisonError::new(())),
+            _ => Ok(RawFusedState::Write),
+        }
+    }
+    unsafe impl RawFusedAsync for Spin {
+        const UNLOCKED: Self = Spin(AtomicUsize::new(0));
+        const READ: Self = Spin(AtomicUsize::new(2));
+        const POISON: Self = Spin(AtomicUsize::new(3));
+        fn poll_write_checked(
+            &self,
+            cx: &mut Context,
+            _: &'static Location<'static>,
+        ) -> Poll<Result<RawFusedState, TryLockError<()>>> {
+            match self.0.compare_exchange(0, 1, Acquire, Acquire) {
+                Ok(_) => Poll::Ready(Ok(RawFusedState::Write)),
+                Err(1) => {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+                Err(s) => Poll::Ready(Ok(state(s)?)),
+            }
+        }
+        fn poll_read_checked(&self, cx: &mut Context) -> Poll<Result<(), TryLockError<()>>> {
+            match state(self.0.load(Acquire))? {
+                RawFusedState::Read => Poll::Ready(Ok(())),
+                RawFusedState::Write => {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+            }
+        }
+        fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+            state(self.0.load(Acquire))
+        }
+        unsafe fn unlock(&self) {
+            self.0.store(0, Release)
+        }
+        unsafe fn unlock_poison(&self) {
+            self.0.store(3, Release)
+        }
+        unsafe fn unlock_fuse(&self) {
+            self.0.store(2, Release)
+        }
+        fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+            state(*self.0.get_mut())
+        }
+    }
+
+    let once = AsyncOnce::<Spin, usize>::new();
+    thread::scope(|s| {
+        s.spawn(|| block_on(once.get_or_init(|| async { 1 })));
+        assert_eq!(*block_on(once.wait()), 1);
+    });
+    assert_eq!(block_on(once.get_or_init(|| async { 2 })), &1);
+
+    let lazy = AsyncLazy::<Spin, usize, _>::new(|| async { 3 });
+    assert_eq!(lazy.get(), None);
+    assert_eq!(block_on(lazy.force()), &3);
+
+    let fused = AsyncFused::<Spin, Vec<usize>>::new(vec![]);
+    block_on(async {
+        let mut guard = match fused.write().await {
+            crate::future::AsyncFusedEntry::Write(guard) => guard,
+            crate::future::AsyncFusedEntry::Read(_) => unreachable!(),
+        };
+        guard.push(4);
+        guard.fuse();
+    });
+    assert_eq!(fused.try_read(), Some(&vec![4]));
+}
+
+#[test]
+fn test_async_poison_payload() {
+    let once = AsyncOnceLock::<usize>::new();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        block_on(once.get_or_init(|| async { panic!("config file missing") }))
+    }));
+    assert_eq!(
+        *result.unwrap_err().downcast::<&str>().unwrap(),
+        "config file missing"
+    );
+    let Err(LockError::Poisoned { cause: Some(cause) }) = block_on(once.wait_checked()) else {
+        unreachable!()
+    };
+    assert_eq!(cause.message(), Some("config file missing"));
+    for _ in 0..2 {
+        let result = catch_unwind(AssertUnwindSafe(|| block_on(once.wait())));
+        assert_eq!(
+            *result.unwrap_err().downcast::<&str>().unwrap(),
+            "config file missing"
+        );
+    }
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        block_on(once.get_or_init(|| async { 1 }))
+    }));
+    assert_eq!(
+        *result.unwrap_err().downcast::<&str>().unwrap(),
+        "config file missing"
+    );
+
+    let once = AsyncOnceLock::<usize>::new();
+    let key = "port".to_string();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        block_on(once.get_or_init(|| async { panic!("missing {}", key) }))
+    }));
+    assert!(result.is_err());
+    let result = catch_unwind(AssertUnwindSafe(|| block_on(once.wait())));
+    assert_eq!(
+        *result.unwrap_err().downcast::<String>().unwrap(),
+        "missing port"
+    );
+}
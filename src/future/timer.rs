@@ -0,0 +1,126 @@
+use crate::sync::OnceLock;
+use parking_lot::{Condvar, Mutex};
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap};
+use std::future::{poll_fn, Future};
+use std::hash::BuildHasherDefault;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Pending sleeps, woken by a single background thread.
+struct Timers {
+    deadlines: BinaryHeap<Reverse<(Instant, u64)>>,
+    wakers: HashMap<u64, Waker, BuildHasherDefault<DefaultHasher>>,
+    next_id: u64,
+}
+
+static TIMERS: Mutex<Timers> = Mutex::new(Timers {
+    deadlines: BinaryHeap::new(),
+    wakers: HashMap::with_hasher(BuildHasherDefault::new()),
+    next_id: 0,
+});
+static TIMERS_CHANGED: Condvar = Condvar::new();
+static TIMER_THREAD: OnceLock<()> = OnceLock::new();
+
+fn run_timers() {
+    let mut timers = TIMERS.lock();
+    loop {
+        let now = Instant::now();
+        while let Some(&Reverse((deadline, id))) = timers.deadlines.peek() {
+            if deadline > now {
+                break;
+            }
+            timers.deadlines.pop();
+            if let Some(waker) = timers.wakers.remove(&id) {
+                waker.wake();
+            }
+        }
+        match timers.deadlines.peek() {
+            Some(&Reverse((deadline, _))) => {
+                TIMERS_CHANGED.wait_until(&mut timers, deadline);
+            }
+            None => TIMERS_CHANGED.wait(&mut timers),
+        }
+    }
+}
+
+/// A future that completes at a deadline, for use with any executor. Sleeps are woken by a
+/// background thread started on first use.
+pub struct Sleep {
+    // None if the deadline is too far away to represent, in which case the sleep never completes.
+    deadline: Option<Instant>,
+    id: Option<u64>,
+}
+
+impl Sleep {
+    pub fn until(deadline: Instant) -> Self {
+        Sleep {
+            deadline: Some(deadline),
+            id: None,
+        }
+    }
+
+    pub fn new(duration: Duration) -> Self {
+        Sleep {
+            deadline: Instant::now().checked_add(duration),
+            id: None,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let Some(deadline) = self.deadline else {
+            return Poll::Pending;
+        };
+        if Instant::now() >= deadline {
+            return Poll::Ready(());
+        }
+        TIMER_THREAD.get_or_init(|| {
+            thread::Builder::new()
+                .name("safe-once-timer".to_string())
+                .spawn(run_timers)
+                .expect("failed to spawn timer thread");
+        });
+        let mut timers = TIMERS.lock();
+        let id = match self.id {
+            Some(id) => id,
+            None => {
+                let id = timers.next_id;
+                timers.next_id += 1;
+                timers.deadlines.push(Reverse((deadline, id)));
+                self.id = Some(id);
+                TIMERS_CHANGED.notify_one();
+                id
+            }
+        };
+        timers.wakers.insert(id, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            TIMERS.lock().wakers.remove(&id);
+        }
+    }
+}
+
+/// Run `future` until it completes or `timeout` elapses, returning None on timeout. On timeout
+/// `future` is dropped.
+pub async fn timeout<F: Future>(timeout: Duration, future: F) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut sleep = Sleep::new(timeout);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        Pin::new(&mut sleep).poll(cx).map(|()| None)
+    })
+    .await
+}
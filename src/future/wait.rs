@@ -0,0 +1,129 @@
+use crate::api::once::{Once, OnceEntry};
+use crate::error::LockError;
+use crate::sync::RawFusedLock;
+use parking_lot::Mutex;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::BuildHasherDefault;
+use std::pin::Pin;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+use std::task::{Context, Poll, Waker};
+
+// Tasks waiting for synchronous locks to be fused, keyed by the address the lock's parked threads
+// wait on.
+static WAKERS: Mutex<HashMap<usize, Vec<Waker>, BuildHasherDefault<DefaultHasher>>> =
+    Mutex::new(HashMap::with_hasher(BuildHasherDefault::new()));
+
+// The number of registered wakers, so that fusing only takes the mutex when necessary.
+static WAKER_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Wake `waker` when [wake_all] is called for `addr`. The caller must issue a SeqCst fence
+/// before checking whether it still needs to wait.
+pub(crate) fn register(addr: usize, waker: &Waker) {
+    let mut wakers = WAKERS.lock();
+    let wakers = wakers.entry(addr).or_default();
+    if !wakers.iter().any(|w| w.will_wake(waker)) {
+        wakers.push(waker.clone());
+        WAKER_COUNT.fetch_add(1, SeqCst);
+    }
+}
+
+fn deregister(addr: usize, waker: &Waker) {
+    let mut wakers = WAKERS.lock();
+    if let Some(list) = wakers.get_mut(&addr) {
+        if let Some(index) = list.iter().position(|w| w.will_wake(waker)) {
+            list.swap_remove(index);
+            WAKER_COUNT.fetch_sub(1, Relaxed);
+        }
+        if list.is_empty() {
+            wakers.remove(&addr);
+        }
+    }
+}
+
+/// Wake the tasks registered for `addr`. The caller must issue a SeqCst fence after changing the
+/// state the tasks are waiting for.
+pub(crate) fn wake_all(addr: usize) {
+    if WAKER_COUNT.load(Relaxed) == 0 {
+        return;
+    }
+    let wakers = WAKERS.lock().remove(&addr);
+    for waker in wakers.into_iter().flatten() {
+        WAKER_COUNT.fetch_sub(1, Relaxed);
+        waker.wake();
+    }
+}
+
+/// The future returned by [Once::wait_async_checked].
+pub struct WaitAsync<'a, T> {
+    once: &'a Once<RawFusedLock, T>,
+    // The waker registered by the last poll, to deregister if the future is dropped.
+    waker: Option<Waker>,
+}
+
+impl<'a, T> WaitAsync<'a, T> {
+    pub(crate) fn new(once: &'a Once<RawFusedLock, T>) -> Self {
+        WaitAsync { once, waker: None }
+    }
+}
+
+impl<'a, T> Future for WaitAsync<'a, T> {
+    type Output = Result<&'a T, LockError>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let once = self.once;
+        let result = once.poll_wait(cx);
+        if result.is_pending() && !self.waker.as_ref().is_some_and(|w| w.will_wake(cx.waker())) {
+            self.waker = Some(cx.waker().clone());
+        }
+        if result.is_ready() {
+            self.waker = None;
+        }
+        result
+    }
+}
+
+impl<'a, T> Drop for WaitAsync<'a, T> {
+    fn drop(&mut self) {
+        if let Some(waker) = &self.waker {
+            deregister(self.once.fused_addr(), waker);
+        }
+    }
+}
+
+/// The future returned by [Once::lock_async_checked].
+pub struct LockAsync<'a, T> {
+    once: &'a Once<RawFusedLock, T>,
+    // The waker registered by the last poll, to deregister if the future is dropped.
+    waker: Option<Waker>,
+}
+
+impl<'a, T> LockAsync<'a, T> {
+    pub(crate) fn new(once: &'a Once<RawFusedLock, T>) -> Self {
+        LockAsync { once, waker: None }
+    }
+}
+
+impl<'a, T> Future for LockAsync<'a, T> {
+    type Output = Result<OnceEntry<'a, RawFusedLock, T>, LockError>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let once = self.once;
+        let result = once.poll_lock(cx);
+        if result.is_pending() && !self.waker.as_ref().is_some_and(|w| w.will_wake(cx.waker())) {
+            self.waker = Some(cx.waker().clone());
+        }
+        if result.is_ready() {
+            self.waker = None;
+        }
+        result
+    }
+}
+
+impl<'a, T> Drop for LockAsync<'a, T> {
+    fn drop(&mut self) {
+        if let Some(waker) = &self.waker {
+            deregister(self.once.fused_addr(), waker);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+// Instrumentation of initialization for the `tracing` and `log` features. Without them, every
+// function is empty and Init is zero-sized.
+
+#[cfg(any(feature = "tracing", feature = "log"))]
+use std::time::{Duration, Instant};
+
+// Initializers that take at least this long are logged as warnings rather than debug records.
+#[cfg(feature = "log")]
+const SLOW: Duration = Duration::from_secs(1);
+
+// The registered name of the static containing `addr`, if any.
+#[cfg(any(feature = "tracing", feature = "log"))]
+fn name(addr: *const u8) -> Option<&'static str> {
+    crate::registry::find_containing(addr).map(|r| r.name())
+}
+
+/// An initializer running on the current thread. Reports completion when finished, or poisoning
+/// if dropped by unwinding instead.
+pub(crate) struct Init {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+    #[cfg(feature = "log")]
+    addr: *const u8,
+    #[cfg(feature = "log")]
+    type_name: &'static str,
+    #[cfg(any(feature = "tracing", feature = "log"))]
+    start: Instant,
+    #[cfg(any(feature = "tracing", feature = "log"))]
+    finished: bool,
+}
+
+impl Init {
+    #[inline]
+    pub(crate) fn start(addr: *const u8, type_name: &'static str) -> Self {
+        #[cfg(not(any(feature = "tracing", feature = "log")))]
+        let _ = (addr, type_name);
+        Init {
+            #[cfg(feature = "tracing")]
+            _span: tracing::debug_span!(
+                "safe_once::init",
+                type_name,
+                name = name(addr),
+                address = ?addr
+            )
+            .entered(),
+            #[cfg(feature = "log")]
+            addr,
+            #[cfg(feature = "log")]
+            type_name,
+            #[cfg(any(feature = "tracing", feature = "log"))]
+            start: Instant::now(),
+            #[cfg(any(feature = "tracing", feature = "log"))]
+            finished: false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn finish(#[allow(unused_mut)] mut self) {
+        #[cfg(any(feature = "tracing", feature = "log"))]
+        {
+            let elapsed = self.start.elapsed();
+            #[cfg(feature = "tracing")]
+            tracing::debug!(elapsed = ?elapsed, "initialized");
+            #[cfg(feature = "log")]
+            log::log!(
+                if elapsed >= SLOW {
+                    log::Level::Warn
+                } else {
+                    log::Level::Debug
+                },
+                "{} initialized in {:?}",
+                Cell(self.addr, self.type_name),
+                elapsed
+            );
+            self.finished = true;
+        }
+    }
+}
+
+#[cfg(any(feature = "tracing", feature = "log"))]
+impl Drop for Init {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let elapsed = self.start.elapsed();
+        #[cfg(feature = "tracing")]
+        tracing::warn!(elapsed = ?elapsed, "initializer panicked, poisoning the cell");
+        #[cfg(feature = "log")]
+        log::warn!(
+            "{} initializer panicked after {:?}, poisoning the cell",
+            Cell(self.addr, self.type_name),
+            elapsed
+        );
+    }
+}
+
+/// A write guard for the cell at `addr` was dropped by unwinding, poisoning the cell.
+#[inline]
+pub(crate) fn poisoned(addr: *const u8, type_name: &'static str) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        type_name,
+        name = name(addr),
+        address = ?addr,
+        "write guard dropped while panicking, poisoning the cell"
+    );
+    #[cfg(feature = "log")]
+    log::warn!(
+        "{} write guard dropped while panicking, poisoning the cell",
+        Cell(addr, type_name)
+    );
+    #[cfg(not(any(feature = "tracing", feature = "log")))]
+    let _ = (addr, type_name);
+}
+
+/// The current thread is about to park until another thread unlocks the cell at `addr`.
+#[inline]
+pub(crate) fn parked(addr: *const u8, type_name: Option<&'static str>) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        type_name,
+        name = name(addr),
+        address = ?addr,
+        "waiting for another thread's initializer"
+    );
+    #[cfg(not(feature = "tracing"))]
+    let _ = (addr, type_name);
+}
+
+/// The current thread detected a deadlock, described by `message`.
+#[inline]
+pub(crate) fn deadlock(message: &str) {
+    #[cfg(feature = "log")]
+    log::warn!("{}", message);
+    #[cfg(not(feature = "log"))]
+    let _ = message;
+}
+
+// A cell described by its registered name or address, and its type.
+#[cfg(feature = "log")]
+struct Cell(*const u8, &'static str);
+
+#[cfg(feature = "log")]
+impl core::fmt::Display for Cell {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match name(self.0) {
+            Some(name) => write!(f, "{}: {}", name, self.1),
+            None => write!(f, "{:p}: {}", self.0, self.1),
+        }
+    }
+}
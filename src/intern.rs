@@ -0,0 +1,202 @@
+//! Deduplication of values into shared, long-lived references.
+//!
+//! An [Interner] stores each distinct value once and returns an [Interned] reference to it, which
+//! compares and hashes by address. References live as long as the interner, so a `static`
+//! interner hands out `'static` references.
+//! ```
+//! use safe_once::intern::Interner;
+//! static SYMBOLS: Interner<str> = Interner::new();
+//! let a = SYMBOLS.intern("main");
+//! let b = SYMBOLS.intern(&"main".to_string());
+//! assert_eq!(a, b);
+//! assert!(std::ptr::eq(a.get(), b.get()));
+//! assert_eq!(SYMBOLS.len(), 1);
+//! ```
+
+use crate::append_only::lock;
+use crate::sync::FusedLock;
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::ops::Deref;
+use std::ptr::{self, NonNull};
+
+/// A set of values that are never removed, so that references to them live as long as the set.
+pub struct Interner<T: ?Sized> {
+    // The hasher is deterministic so that the interner can be constructed in a const context.
+    values: FusedLock<HashSet<Stored<T>, BuildHasherDefault<DefaultHasher>>>,
+}
+
+// A heap allocation that does not move when the set grows. Unlike a Box, moving it does not
+// assert unique access, so references to the value remain valid.
+struct Stored<T: ?Sized>(NonNull<T>);
+
+/// A reference to a value in an [Interner]. Two references from the same interner are equal if
+/// and only if their values are equal, so comparison and hashing use the address.
+pub struct Interned<'a, T: ?Sized>(&'a T);
+
+impl<T: ?Sized> Interner<T> {
+    pub const fn new() -> Self {
+        Interner {
+            values: FusedLock::new(HashSet::with_hasher(BuildHasherDefault::new())),
+        }
+    }
+
+    fn extend(&self, value: &T) -> Interned<'_, T> {
+        // Values are boxed and never removed through a shared reference.
+        Interned(unsafe { &*(value as *const T) })
+    }
+
+    /// The number of distinct values.
+    #[track_caller]
+    pub fn len(&self) -> usize {
+        lock(&self.values).len()
+    }
+
+    #[track_caller]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Eq + Hash + ?Sized> Interner<T> {
+    /// Return the interned copy of `value`, if any.
+    #[track_caller]
+    pub fn get(&self, value: &T) -> Option<Interned<'_, T>> {
+        Some(self.extend(lock(&self.values).get(value)?.get()))
+    }
+
+    /// Return the interned copy of `value`, copying it into the interner if necessary.
+    #[track_caller]
+    pub fn intern(&self, value: &T) -> Interned<'_, T>
+    where
+        for<'b> Box<T>: From<&'b T>,
+    {
+        let mut values = lock(&self.values);
+        if let Some(interned) = values.get(value) {
+            return self.extend(interned.get());
+        }
+        let stored = Stored::new(Box::from(value));
+        let interned = self.extend(stored.get());
+        values.insert(stored);
+        interned
+    }
+
+    /// Return the interned copy of `value`, storing `value` itself if necessary.
+    #[track_caller]
+    pub fn intern_boxed(&self, value: Box<T>) -> Interned<'_, T> {
+        let mut values = lock(&self.values);
+        if let Some(interned) = values.get(&*value) {
+            return self.extend(interned.get());
+        }
+        let stored = Stored::new(value);
+        let interned = self.extend(stored.get());
+        values.insert(stored);
+        interned
+    }
+}
+
+impl<T: ?Sized> Default for Interner<T> {
+    fn default() -> Self {
+        Interner::new()
+    }
+}
+
+impl<T: Debug + ?Sized> Debug for Interner<T> {
+    #[track_caller]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_set()
+            .entries(lock(&self.values).iter().map(Stored::get))
+            .finish()
+    }
+}
+
+impl<T: ?Sized> Stored<T> {
+    fn new(value: Box<T>) -> Self {
+        Stored(NonNull::from(Box::leak(value)))
+    }
+
+    fn get(&self) -> &T {
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for Stored<T> {
+    fn borrow(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T: Eq + ?Sized> PartialEq for Stored<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Eq + ?Sized> Eq for Stored<T> {}
+
+impl<T: Hash + ?Sized> Hash for Stored<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state)
+    }
+}
+
+impl<T: ?Sized> Drop for Stored<T> {
+    fn drop(&mut self) {
+        unsafe { drop(Box::from_raw(self.0.as_ptr())) }
+    }
+}
+
+// Stored owns its value like a Box.
+unsafe impl<T: Send + ?Sized> Send for Stored<T> {}
+
+unsafe impl<T: Sync + ?Sized> Sync for Stored<T> {}
+
+impl<'a, T: ?Sized> Interned<'a, T> {
+    pub fn get(self) -> &'a T {
+        self.0
+    }
+}
+
+impl<'a, T: ?Sized> Clone for Interned<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: ?Sized> Copy for Interned<'a, T> {}
+
+impl<'a, T: ?Sized> Deref for Interned<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+
+impl<'a, T: ?Sized> PartialEq for Interned<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl<'a, T: ?Sized> Eq for Interned<'a, T> {}
+
+impl<'a, T: ?Sized> Hash for Interned<'a, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(self.0, state)
+    }
+}
+
+impl<'a, T: Debug + ?Sized> Debug for Interned<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'a, T: Display + ?Sized> Display for Interned<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
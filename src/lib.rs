@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(unused_must_use)]
 #![allow(unused_imports)]
 #![allow(unused_variables)]
@@ -71,20 +72,180 @@
 //! assert_eq!(fused.try_read().unwrap(), &[9,8,7,6,5,4,3,2,1,0]);
 //! ```
 //!
+//! # Poisoning
+//! A cell whose initializer panics is poisoned, and later accesses panic with the message of the
+//! original panic. `clear_poison` makes a poisoned cell uninitialized using exclusive access, and
+//! `try_recover` lets one of the threads sharing it run a new initializer:
+//! ```
+//! use safe_once::sync::OnceLock;
+//! use std::panic::catch_unwind;
+//! static CONFIG: OnceLock<u32> = OnceLock::new();
+//! assert!(catch_unwind(|| CONFIG.get_or_init(|| panic!("unavailable"))).is_err());
+//! assert_eq!(CONFIG.try_recover(|| 1), Some(&1));
+//! assert_eq!(CONFIG.get_or_init(|| 2), &1);
+//! ```
+//!
 //! # Deadlock detection
-//! If a cycle is detected within a single thread, it triggers a panic instead of a deadlock:
+//! If a cycle is detected within a single thread, it triggers a panic instead of a deadlock. The
+//! message names the call that obtained the write lock and the call that requested it again, and
+//! lists each cell in the cycle by its [registered](registry) name or address, the type of its
+//! value, and where its initialization began:
 //! ```
+//! # #[cfg(feature = "owner-location")] {
 //! # use std::panic::catch_unwind;
 //! use safe_once::sync::LazyLock;
 //! static A: LazyLock<String> = LazyLock::new(||B.to_string());
 //! static B: LazyLock<String> = LazyLock::new(||A.to_string());
 //! let result = catch_unwind(||{ &*A; });
-//! assert_eq!(result.unwrap_err().downcast_ref::<String>().unwrap(),
-//!            "called `Result::unwrap()` on an `Err` value: \"WouldBlock\"");
+//! let message = result.unwrap_err().downcast::<String>().unwrap();
+//! assert!(message.starts_with("deadlock: write lock obtained at "));
+//! assert!(message.contains(": alloc::string::String initializing since "));
+//! # }
 //! ```
 //!
+//! Cycles across threads, where each thread waits for a lock held by the next, deadlock by default.
+//! The `deadlock-detection` feature maintains a graph of the threads waiting for [sync] locks and
+//! the threads holding them. The thread that would complete a cycle panics with a description of
+//! every thread and lock in it, which poisons the locks it holds and so wakes the others.
+//! [set_cycle_handler] replaces the panic for either kind of cycle with an abort, or with an error
+//! from the methods that can return one, for programs that cannot unwind safely.
+//!
+//! To diagnose a thread that blocks on a [sync] cell, `owner_thread` reports the name of the thread
+//! initializing it, or its number if it is unnamed. [set_slow_init_handler] installs a
+//! [watchdog] that reports, along with the cell's name and type, every thread that has waited longer
+//! than a threshold.
+//!
+//! # `no_std`
+//! Without the default `std` feature, the crate is `#![no_std]`. The generic [api] wrappers
+//! remain available with the spinning backend in [spin], and the [error] types replace the
+//! standard library's lock errors. The `alloc` feature adds the methods that need an allocator,
+//! such as [Fused::write_arc](api::fused::Fused::write_arc). On single-core targets without atomic
+//! compare-and-swap, the `critical-section` feature adds the backend in `cs`, which masks
+//! interrupts through the [critical-section](https://docs.rs/critical-section) crate. To share a
+//! value with interrupt handlers without masking interrupts, use [cell::isr], whose handlers read
+//! wait-free and panic if they interrupt the initializer.
+//!
+//! On targets without atomic compare-and-swap, such as `thumbv6m-none-eabi`, the `portable-atomic`
+//! feature builds [spin] and [race] on [portable-atomic](https://docs.rs/portable-atomic). Enabling
+//! `critical-section` as well makes it emulate the missing operations with critical sections;
+//! otherwise, portable-atomic must be configured directly, for example with its
+//! `unsafe-assume-single-core` feature. Methods that need `Arc` are unavailable on these targets.
+//!
+//! The `no-poison` feature reduces code size for firmware. [spin] and `cs` collapse to three
+//! states: a panicking initializer unlocks instead of poisoning, so the next caller retries. Other
+//! backends are unaffected.
+//!
+//! # `parking-lot`
+//! The default `parking-lot` feature parks waiting threads with `parking_lot_core`. Without it, the
+//! crate depends only on the standard library: [sync] parks on condition variables instead, and
+//! [sync::std_park] selects a backend that never uses `parking_lot_core`. The `async` feature
+//! requires `parking-lot`.
+//!
+//! # `futex`
+//! The `futex` feature adds `sync::futex`, whose backend waits on the operating system's
+//! futex or `WaitOnAddress` directly and never allocates, so it can be used within a
+//! `#[global_allocator]`.
+//!
+//! # `dyn-backend`
+//! The `dyn-backend` feature adds `sync::dynamic`, whose backend locks and unlocks without atomic
+//! read-modify-write operations until a second thread uses it, and then behaves like the rest of
+//! [sync].
+//!
+//! # `tracing`
+//! The `tracing` feature emits a `safe_once::init` span around each initializer run by a
+//! `get_or_init` or `Lazy` dereference, with the cell's type, its [registered](registry) name if
+//! any, and its address as fields. Within it, a debug event reports completion with the elapsed
+//! time and a warning reports an initializer that panicked. Threads that park waiting for another
+//! thread's initializer emit a debug event, and a write guard dropped while panicking emits a
+//! warning.
+//!
+//! # `log`
+//! For programs that use [log](https://docs.rs/log) instead, the `log` feature records the same
+//! poisoning as warnings, logs each initializer's duration at the debug level, or as a warning if it
+//! took a second or more, and logs the description of each deadlock cycle that the crate detects
+//! before panicking. The two features are independent, and each costs nothing when disabled.
+//!
+//! # `stats`
+//! The `stats` feature makes each [sync] cell count the threads that parked waiting for it and the
+//! time spent in its initializers, read with `stats` methods such as
+//! [Once::stats](api::once::Once::stats) or summed over the process with [stats::global], to find
+//! the cells that threads pile up on at startup. It adds 24 bytes to every [sync] cell.
+//!
+//! # `content-hash`
+//! The `content-hash` feature adds [FusedGuard::fuse_hashed](api::fused::FusedGuard::fuse_hashed),
+//! which records a digest of the value when fusing, read back with
+//! [Fused::content_hash](api::fused::Fused::content_hash). It adds 16 bytes to every cell.
+//!
+//! # `on-fuse`
+//! The `on-fuse` feature adds [Fused::on_fuse](api::fused::Fused::on_fuse), which registers
+//! callbacks to run when a cell becomes read-only. It requires `alloc` and adds 8 bytes to every
+//! cell.
+//!
+//! # `owner-location`
+//! The default `owner-location` feature records where the write lock was obtained by
+//! [RawFusedLock](sync::RawFusedLock), [RawFusedCell](cell::RawFusedCell), and
+//! [RawFusedIsr](cell::RawFusedIsr). Deadlock panics report it, as does the
+//! [PoisonCause](error::PoisonCause) of a poisoned [sync] cell, and without it poisoned cells
+//! report no cause. It adds a pointer to each such cell, which alignment can make costlier: a
+//! `cell::OnceCell<u8>` grows from 2 to 24 bytes. [spin] cells wait rather than report
+//! deadlocks, so they never record it.
+//!
+//! # `shared`
+//! On Linux, the `shared` feature adds `shared::SharedOnceLock`, which lives in shared memory and
+//! is initialized once across processes. A process that dies while initializing it poisons it.
+//!
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 pub mod cell;
+pub mod spin;
+#[cfg(feature = "std")]
 pub mod sync;
 
 pub mod api;
+#[cfg(feature = "std")]
+pub mod append_only;
+mod atomic;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "critical-section")]
+pub mod cs;
+#[cfg(feature = "std")]
+pub mod deadlock;
+pub mod error;
+#[cfg(feature = "async")]
+pub mod future;
+#[cfg(feature = "std")]
+mod instrument;
+#[cfg(feature = "std")]
+pub mod intern;
+#[cfg(feature = "std")]
+pub mod map;
+#[cfg(feature = "std")]
+pub mod observer;
+#[cfg(feature = "rayon")]
+pub mod par;
+#[cfg(feature = "process")]
+pub mod process;
+pub mod race;
+pub mod registry;
+#[cfg(feature = "record-replay")]
+pub mod replay;
+#[cfg(all(feature = "shared", target_os = "linux"))]
+pub mod shared;
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(feature = "std-like")]
+pub mod std_like;
+#[cfg(feature = "std")]
+pub mod swr;
+#[cfg(feature = "warmup")]
+pub mod warmup;
+#[cfg(feature = "std")]
+pub mod watchdog;
+
+#[cfg(feature = "std")]
+pub use deadlock::set_cycle_handler;
+#[cfg(feature = "std")]
+pub use watchdog::set_slow_init_handler;
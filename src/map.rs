@@ -0,0 +1,331 @@
+//! A concurrent map whose values are each initialized at most once.
+//!
+//! Lookups take borrowed keys, and an owned key is only constructed when a new entry is
+//! inserted, so a hit never allocates.
+//! ```
+//! use safe_once::map::OnceMap;
+//! let map = OnceMap::<String, usize>::new();
+//! assert_eq!(*map.get_or_init("hello", |key| key.len()), 5);
+//! assert_eq!(*map.get_or_init("hello", |_| unreachable!()), 5);
+//! assert_eq!(map.entry_ref("world").get(), None);
+//! ```
+//!
+//! A [Memo] pairs a map with a function, caching one result per argument, and an
+//! [OnceTypeMap] holds at most one value of each type.
+
+use crate::sync::OnceLock;
+use std::any::{Any, TypeId};
+use std::borrow::{Borrow, ToOwned};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// A map from keys to values that are each initialized at most once. Entries are never
+/// removed while the map is shared, so references to values live as long as the map.
+pub struct OnceMap<K, V> {
+    // Boxed so that cells do not move when the map grows.
+    map: Mutex<HashMap<K, Box<OnceLock<V>>>>,
+}
+
+/// A borrowed key and the value for it, if initialized. See [OnceMap::entry_ref].
+pub struct EntryRef<'a, K, V, Q: ?Sized> {
+    map: &'a OnceMap<K, V>,
+    key: &'a Q,
+    value: Option<&'a V>,
+}
+
+impl<K: Eq + Hash, V> OnceMap<K, V> {
+    pub fn new() -> Self {
+        OnceMap {
+            map: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn extend(&self, cell: &OnceLock<V>) -> &OnceLock<V> {
+        // Cells are boxed and never removed through a shared reference.
+        unsafe { &*(cell as *const OnceLock<V>) }
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<&OnceLock<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let map = self.map.lock().unwrap();
+        Some(self.extend(map.get(key)?))
+    }
+
+    fn cell<Q>(&self, key: &Q, to_owned: impl FnOnce(&Q) -> K) -> &OnceLock<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mut map = self.map.lock().unwrap();
+        if let Some(cell) = map.get(key) {
+            return self.extend(cell);
+        }
+        let cell = map
+            .entry(to_owned(key))
+            .or_insert_with(|| Box::new(OnceLock::new()));
+        self.extend(cell)
+    }
+
+    /// Return the value for `key` if it has been initialized.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key)?.try_get()
+    }
+
+    /// Return the value for `key`, initializing it with `init` if necessary. The key is only
+    /// converted to an owned key if there is no entry for it.
+    pub fn get_or_init<Q>(&self, key: &Q, init: impl FnOnce(&Q) -> V) -> &V
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ToOwned<Owned = K> + ?Sized,
+    {
+        self.get_or_init_with_key(key, Q::to_owned, init)
+    }
+
+    /// Like [OnceMap::get_or_init], but with a custom conversion to an owned key, for keys such
+    /// as `Arc<str>` or `Box<str>` that are not the [ToOwned] type of their borrowed form.
+    pub fn get_or_init_with_key<Q>(
+        &self,
+        key: &Q,
+        to_owned: impl FnOnce(&Q) -> K,
+        init: impl FnOnce(&Q) -> V,
+    ) -> &V
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if let Some(value) = self.get(key) {
+            return value;
+        }
+        self.cell(key, to_owned).get_or_init(|| init(key))
+    }
+
+    /// Return the value for `key`, initializing it with `init` if necessary. If `init` fails, the
+    /// entry remains uninitialized and a later call may retry.
+    pub fn get_or_try_init<Q, E>(
+        &self,
+        key: &Q,
+        init: impl FnOnce(&Q) -> Result<V, E>,
+    ) -> Result<&V, E>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ToOwned<Owned = K> + ?Sized,
+    {
+        if let Some(value) = self.get(key) {
+            return Ok(value);
+        }
+        self.cell(key, Q::to_owned).lock().or_try_init(|| init(key))
+    }
+
+    /// Look up `key` without inserting it.
+    pub fn entry_ref<'a, Q>(&'a self, key: &'a Q) -> EntryRef<'a, K, V, Q>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        EntryRef {
+            map: self,
+            key,
+            value: self.get(key),
+        }
+    }
+
+    /// The number of entries, including uninitialized entries.
+    pub fn len(&self) -> usize {
+        self.map.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a, K: Eq + Hash + Borrow<Q>, V, Q: Eq + Hash + ?Sized> EntryRef<'a, K, V, Q> {
+    pub fn key(&self) -> &'a Q {
+        self.key
+    }
+
+    /// The value, if it was initialized when this entry was looked up.
+    pub fn get(&self) -> Option<&'a V> {
+        self.value
+    }
+
+    /// Return the value, initializing it with `init` if necessary.
+    pub fn or_init(self, init: impl FnOnce(&Q) -> V) -> &'a V
+    where
+        Q: ToOwned<Owned = K>,
+    {
+        match self.value {
+            Some(value) => value,
+            None => self
+                .map
+                .cell(self.key, Q::to_owned)
+                .get_or_init(|| init(self.key)),
+        }
+    }
+
+    /// Return the value, initializing it with `init` if necessary. If `init` fails, the entry
+    /// remains uninitialized.
+    pub fn or_try_init<E>(self, init: impl FnOnce(&Q) -> Result<V, E>) -> Result<&'a V, E>
+    where
+        Q: ToOwned<Owned = K>,
+    {
+        match self.value {
+            Some(value) => Ok(value),
+            None => self.map.get_or_try_init(self.key, init),
+        }
+    }
+}
+
+// Values are shared between threads through `&self`, so they must be Sync as well as Send.
+unsafe impl<K: Send + Sync, V: Send + Sync> Sync for OnceMap<K, V> {}
+
+impl<K: Eq + Hash, V> Default for OnceMap<K, V> {
+    fn default() -> Self {
+        OnceMap::new()
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for OnceMap<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let map = self.map.lock().unwrap();
+        f.debug_map()
+            .entries(map.iter().map(|(k, v)| (k, v.try_get())))
+            .finish()
+    }
+}
+
+/// A function whose result is computed at most once per distinct argument. Concurrent calls with
+/// the same argument wait for a single computation, and calls with different arguments run in
+/// parallel.
+/// ```
+/// use safe_once::map::Memo;
+/// let squares = Memo::new(|x: &u64| x * x);
+/// assert_eq!(*squares.get(&12), 144);
+/// assert_eq!(squares.len(), 1);
+/// ```
+pub struct Memo<A, T, F = fn(&A) -> T> {
+    map: OnceMap<A, T>,
+    f: F,
+}
+
+impl<A: Eq + Hash, T, F> Memo<A, T, F> {
+    pub fn new(f: F) -> Self {
+        Memo {
+            map: OnceMap::new(),
+            f,
+        }
+    }
+
+    /// Return the result for `arg` if it has been computed.
+    pub fn try_get(&self, arg: &A) -> Option<&T> {
+        self.map.get(arg)
+    }
+
+    /// The number of arguments seen, including those still being computed.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<A: Eq + Hash + Clone, T, F: Fn(&A) -> T> Memo<A, T, F> {
+    /// Return the result for `arg`, computing it if necessary. The argument is only cloned the
+    /// first time it is seen. Panics if an earlier computation for `arg` panicked.
+    pub fn get(&self, arg: &A) -> &T {
+        self.map.get_or_init_with_key(arg, A::clone, &self.f)
+    }
+}
+
+impl<A: Debug, T: Debug, F> Debug for Memo<A, T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+/// A map holding at most one value of each type, each initialized at most once. As with other
+/// once cells, an initializer that requests its own type, directly or through another type's
+/// initializer, panics instead of deadlocking.
+/// ```
+/// use safe_once::map::OnceTypeMap;
+/// struct Config(u32);
+/// let extensions = OnceTypeMap::new();
+/// assert_eq!(extensions.get_or_init(|| Config(8)).0, 8);
+/// assert_eq!(extensions.get::<Config>().unwrap().0, 8);
+/// assert!(extensions.get::<String>().is_none());
+/// ```
+pub struct OnceTypeMap {
+    map: OnceMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl OnceTypeMap {
+    pub fn new() -> Self {
+        OnceTypeMap {
+            map: OnceMap::new(),
+        }
+    }
+
+    fn downcast<T: 'static>(value: &(dyn Any + Send + Sync)) -> &T {
+        value.downcast_ref().unwrap()
+    }
+
+    /// Return the value of type `T` if it has been initialized.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        Some(Self::downcast(&**self.map.get(&TypeId::of::<T>())?))
+    }
+
+    /// Return the value of type `T`, initializing it with `init` if necessary.
+    pub fn get_or_init<T: Any + Send + Sync>(&self, init: impl FnOnce() -> T) -> &T {
+        Self::downcast(&**self.map.get_or_init_with_key(
+            &TypeId::of::<T>(),
+            |id| *id,
+            |_| Box::new(init()),
+        ))
+    }
+
+    /// Return the value of type `T`, initializing it with `init` if necessary. If `init` fails,
+    /// the value remains uninitialized.
+    pub fn get_or_try_init<T: Any + Send + Sync, E>(
+        &self,
+        init: impl FnOnce() -> Result<T, E>,
+    ) -> Result<&T, E> {
+        let value = self.map.get_or_try_init(&TypeId::of::<T>(), |_| {
+            Ok(Box::new(init()?) as Box<dyn Any + Send + Sync>)
+        })?;
+        Ok(Self::downcast(&**value))
+    }
+
+    /// The number of types, including those still being initialized.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl Default for OnceTypeMap {
+    fn default() -> Self {
+        OnceTypeMap::new()
+    }
+}
+
+impl Debug for OnceTypeMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnceTypeMap")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
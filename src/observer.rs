@@ -0,0 +1,37 @@
+//! A process-wide hook for incidents that the crate recovers from internally, but that an
+//! operator may want to know about.
+//! ```
+//! use safe_once::observer::{set_observer, Event};
+//! fn log(event: &Event) {
+//!     eprintln!("safe-once: {:?}", event);
+//! }
+//! set_observer(log);
+//! ```
+
+use std::ptr::null_mut;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::Ordering::{Acquire, Release};
+
+/// An incident reported to the observer.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Parking failed, so waiting threads spin instead.
+    /// See [disable_parking](crate::sync::disable_parking).
+    ParkingUnavailable,
+}
+
+static OBSERVER: AtomicPtr<()> = AtomicPtr::new(null_mut());
+
+/// Call `observer` for every subsequent [Event], replacing any previous observer.
+pub fn set_observer(observer: fn(&Event)) {
+    OBSERVER.store(observer as *mut (), Release);
+}
+
+pub(crate) fn notify(event: &Event) {
+    let observer = OBSERVER.load(Acquire);
+    if !observer.is_null() {
+        let observer: fn(&Event) = unsafe { std::mem::transmute(observer) };
+        observer(event);
+    }
+}
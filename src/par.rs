@@ -0,0 +1,51 @@
+//! Parallel initialization on the [rayon] thread pool.
+//!
+//! Blocking a rayon worker while another thread initializes a cell takes that worker away from
+//! the pool, which may be running the initializer's own parallel work. The helpers here run other
+//! pool jobs while waiting instead. Each cell is still initialized exactly once.
+//! ```
+//! use safe_once::registry::Registered;
+//! use safe_once::sync::LazyLock;
+//! static A: LazyLock<u64> = LazyLock::new(|| (0..1000).sum());
+//! static B: LazyLock<u64> = LazyLock::new(|| (0..2000).sum());
+//! safe_once::par::force_many_par(&[&A, &B]);
+//! assert_eq!(A.try_get(), Some(&499500));
+//! assert_eq!(B.try_get(), Some(&1999000));
+//! ```
+//!
+//! Rayon may run a stolen job on a thread that is already initializing a cell. If that job needs
+//! the same cell, it is reported as a deadlock, even though a different job holds the lock.
+
+use crate::api::fused::{Fused, FusedInvariant};
+use crate::api::raw::RawFused;
+use crate::error::LockError;
+use crate::registry::Registered;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::Yield;
+
+/// Force every cell in parallel on the rayon pool.
+pub fn force_many_par(cells: &[&dyn Registered]) {
+    cells.par_iter().for_each(|cell| cell.force());
+}
+
+impl<R: RawFused, T, I: FusedInvariant<T>> Fused<R, T, I> {
+    /// Like [Fused::read_or_fuse_checked], but a rayon worker runs other pool jobs instead of
+    /// blocking while another thread holds the write lock.
+    #[track_caller]
+    pub fn read_or_fuse_par_checked(&self, modify: impl FnOnce(&mut T)) -> Result<&T, LockError> {
+        loop {
+            if let Some(entry) = self.try_write_checked()? {
+                return Ok(entry.or_fuse(modify));
+            }
+            match rayon::yield_now() {
+                Some(Yield::Executed) => {}
+                Some(Yield::Idle) | None => return self.read_or_fuse_checked(modify),
+            }
+        }
+    }
+    /// Like [Fused::read_or_fuse_par_checked], but panics if poisoned or deadlocked.
+    #[track_caller]
+    pub fn read_or_fuse_par(&self, modify: impl FnOnce(&mut T)) -> &T {
+        self.unwrap_lock(self.read_or_fuse_par_checked(modify))
+    }
+}
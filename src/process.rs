@@ -0,0 +1,120 @@
+//! Initialization that happens once across processes, coordinated through the file system.
+//!
+//! A [OnceProcess] stores its value at a path, serialized as JSON. The first process to
+//! initialize it runs the initializer while holding an advisory lock on a sidecar file, named by
+//! appending `.lock` to the path, and every later process (or later run) reads the stored value
+//! instead.
+//! ```
+//! # #[cfg(feature = "process")] {
+//! use safe_once::process::OnceProcess;
+//! let path = std::env::temp_dir().join(format!("safe-once-doc-{}", std::process::id()));
+//! let id = OnceProcess::<u64>::with_path(&path);
+//! assert_eq!(*id.get_or_init(|| 42).unwrap(), 42);
+//! let again = OnceProcess::<u64>::with_path(&path);
+//! assert_eq!(*again.get_or_init(|| unreachable!()).unwrap(), 42);
+//! # std::fs::remove_file(&path).unwrap();
+//! # std::fs::remove_file(id.lock_path()).unwrap();
+//! # }
+//! ```
+
+use crate::sync::OnceLock;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fmt::{Debug, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+enum Location {
+    Static(&'static str),
+    Owned(PathBuf),
+}
+
+/// A value initialized at most once per path, shared by every process that uses the path.
+pub struct OnceProcess<T> {
+    location: Location,
+    value: OnceLock<T>,
+}
+
+impl<T> OnceProcess<T> {
+    /// Store the value at `path`. Usable in `static` initializers.
+    pub const fn new(path: &'static str) -> Self {
+        OnceProcess {
+            location: Location::Static(path),
+            value: OnceLock::new(),
+        }
+    }
+    /// Store the value at `path`.
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        OnceProcess {
+            location: Location::Owned(path.into()),
+            value: OnceLock::new(),
+        }
+    }
+    /// The path where the value is stored.
+    pub fn path(&self) -> &Path {
+        match &self.location {
+            Location::Static(path) => Path::new(path),
+            Location::Owned(path) => path,
+        }
+    }
+    /// The path of the sidecar file that is locked while initializing: [OnceProcess::path]
+    /// followed by `.lock`.
+    pub fn lock_path(&self) -> PathBuf {
+        self.sibling(".lock")
+    }
+    /// Return the value if this process has already loaded or initialized it.
+    pub fn try_get(&self) -> Option<&T> {
+        self.value.try_get()
+    }
+    fn sibling(&self, extension: &str) -> PathBuf {
+        let mut path = OsString::from(self.path());
+        path.push(extension);
+        PathBuf::from(path)
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> OnceProcess<T> {
+    /// Return the stored value, or run `init` and store its result if no process has done so.
+    /// On error, nothing is stored and a later call may try again.
+    pub fn get_or_init(&self, init: impl FnOnce() -> T) -> io::Result<&T> {
+        self.get_or_try_init(|| Ok(init()))
+    }
+    /// Like [OnceProcess::get_or_init], but `init` may fail.
+    pub fn get_or_try_init(&self, init: impl FnOnce() -> io::Result<T>) -> io::Result<&T> {
+        self.value.lock().or_try_init(|| self.load_or_init(init))
+    }
+    fn load_or_init(&self, init: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.lock_path())?;
+        lock.lock()?;
+        match fs::read(self.path()) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let value = init()?;
+                // Write then rename, so that readers never see a partial file.
+                let temp = self.sibling(".tmp");
+                let mut file = File::create(&temp)?;
+                serde_json::to_writer(&mut file, &value)?;
+                file.flush()?;
+                file.sync_all()?;
+                fs::rename(&temp, self.path())?;
+                Ok(value)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<T: Debug> Debug for OnceProcess<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnceProcess")
+            .field("path", &self.path())
+            .field("value", &self.try_get())
+            .finish()
+    }
+}
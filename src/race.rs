@@ -0,0 +1,457 @@
+//! Once cells and lazy values that each fit in a single atomic word.
+//!
+//! Concurrent initializers race: each may run its initializer, and the first to store its result
+//! wins. There is no parking, no poisoning, and no thread tracking, so these cells are usable in
+//! allocators and signal handlers where [crate::sync::OnceLock] is not.
+//! ```
+//! use safe_once::race::OnceNonZeroUsize;
+//! use std::num::NonZeroUsize;
+//! static PAGE_SIZE: OnceNonZeroUsize = OnceNonZeroUsize::new();
+//! let page_size = PAGE_SIZE.get_or_init(|| NonZeroUsize::new(4096).unwrap());
+//! assert_eq!(page_size.get(), 4096);
+//! ```
+
+#[cfg(target_has_atomic = "64")]
+use crate::atomic::AtomicU64;
+use crate::atomic::Ordering::{Acquire, Release};
+use crate::atomic::{AtomicPtr, AtomicU8, AtomicUsize};
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+use core::fmt::{Debug, Formatter};
+use core::marker::PhantomData;
+use core::mem;
+#[cfg(target_has_atomic = "64")]
+use core::num::NonZeroU64;
+use core::num::NonZeroUsize;
+use core::ptr::null_mut;
+
+macro_rules! once_non_zero {
+    ($(#[$attr:meta])* $name:ident, $atomic:ident, $non_zero:ident) => {
+        $(#[$attr])*
+        #[derive(Default)]
+        pub struct $name {
+            value: $atomic,
+        }
+
+        impl $name {
+            pub const fn new() -> Self {
+                $name {
+                    value: $atomic::new(0),
+                }
+            }
+
+            /// Return the value if initialized.
+            pub fn get(&self) -> Option<$non_zero> {
+                $non_zero::new(self.value.load(Acquire))
+            }
+
+            /// Store `value` if uninitialized. Returns the existing value if already initialized.
+            pub fn set(&self, value: $non_zero) -> Result<(), $non_zero> {
+                match self.value.compare_exchange(0, value.get(), Release, Acquire) {
+                    Ok(_) => Ok(()),
+                    Err(old) => Err(unsafe { $non_zero::new_unchecked(old) }),
+                }
+            }
+
+            /// Return the value, running `init` if uninitialized. If several threads race, each may
+            /// run `init`, and all return the first value stored.
+            pub fn get_or_init(&self, init: impl FnOnce() -> $non_zero) -> $non_zero {
+                match self.get_or_try_init(|| Ok::<_, ()>(init())) {
+                    Ok(value) => value,
+                    Err(()) => unreachable!(),
+                }
+            }
+
+            /// Like [Self::get_or_init], but `init` may fail, leaving the cell uninitialized.
+            pub fn get_or_try_init<E>(
+                &self,
+                init: impl FnOnce() -> Result<$non_zero, E>,
+            ) -> Result<$non_zero, E> {
+                if let Some(value) = self.get() {
+                    return Ok(value);
+                }
+                let value = init()?;
+                match self.set(value) {
+                    Ok(()) => Ok(value),
+                    Err(old) => Ok(old),
+                }
+            }
+        }
+
+        impl Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&self.get()).finish()
+            }
+        }
+    };
+}
+
+once_non_zero!(
+    /// A once cell for a [NonZeroUsize].
+    OnceNonZeroUsize,
+    AtomicUsize,
+    NonZeroUsize
+);
+
+#[cfg(target_has_atomic = "64")]
+once_non_zero!(
+    /// A once cell for a [NonZeroU64].
+    OnceNonZeroU64,
+    AtomicU64,
+    NonZeroU64
+);
+
+/// A once cell for a [bool].
+#[derive(Default)]
+pub struct OnceBool {
+    inner: OnceNonZeroUsize,
+}
+
+impl OnceBool {
+    pub const fn new() -> Self {
+        OnceBool {
+            inner: OnceNonZeroUsize::new(),
+        }
+    }
+
+    fn to_non_zero(value: bool) -> NonZeroUsize {
+        NonZeroUsize::new(value as usize + 1).unwrap()
+    }
+
+    fn from_non_zero(value: NonZeroUsize) -> bool {
+        value.get() == 2
+    }
+
+    /// Return the value if initialized.
+    pub fn get(&self) -> Option<bool> {
+        self.inner.get().map(Self::from_non_zero)
+    }
+
+    /// Store `value` if uninitialized. Returns the existing value if already initialized.
+    pub fn set(&self, value: bool) -> Result<(), bool> {
+        self.inner
+            .set(Self::to_non_zero(value))
+            .map_err(Self::from_non_zero)
+    }
+
+    /// Return the value, running `init` if uninitialized. See [OnceNonZeroUsize::get_or_init].
+    pub fn get_or_init(&self, init: impl FnOnce() -> bool) -> bool {
+        Self::from_non_zero(self.inner.get_or_init(|| Self::to_non_zero(init())))
+    }
+
+    /// Like [OnceBool::get_or_init], but `init` may fail, leaving the cell uninitialized.
+    pub fn get_or_try_init<E>(&self, init: impl FnOnce() -> Result<bool, E>) -> Result<bool, E> {
+        self.inner
+            .get_or_try_init(|| init().map(Self::to_non_zero))
+            .map(Self::from_non_zero)
+    }
+}
+
+impl Debug for OnceBool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("OnceBool").field(&self.get()).finish()
+    }
+}
+
+macro_rules! lazy_int {
+    ($(#[$attr:meta])* $name:ident, $atomic:ident, $int:ident) => {
+        $(#[$attr])*
+        pub struct $name<F = fn() -> $int> {
+            value: $atomic,
+            init: F,
+        }
+
+        impl<F> $name<F> {
+            /// The value that marks the cell as not yet computed.
+            pub const SENTINEL: $int = $int::MAX;
+
+            pub const fn new(init: F) -> Self {
+                $name {
+                    value: $atomic::new(Self::SENTINEL),
+                    init,
+                }
+            }
+        }
+
+        impl<F: Fn() -> $int> $name<F> {
+            /// Return the value, computing it if necessary. If several threads race, each may
+            /// run the initializer, and all return the first value stored. An initializer that
+            /// returns [Self::SENTINEL] runs on every call.
+            #[inline]
+            pub fn get(&self) -> $int {
+                let value = self.value.load(Acquire);
+                if value != Self::SENTINEL {
+                    return value;
+                }
+                self.init()
+            }
+
+            #[cold]
+            fn init(&self) -> $int {
+                let value = (self.init)();
+                match self
+                    .value
+                    .compare_exchange(Self::SENTINEL, value, Release, Acquire)
+                {
+                    Ok(_) => value,
+                    Err(old) => old,
+                }
+            }
+        }
+
+        impl<F> Debug for $name<F> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                let value = self.value.load(Acquire);
+                f.debug_tuple(stringify!($name))
+                    .field(&(value != Self::SENTINEL).then_some(value))
+                    .finish()
+            }
+        }
+    };
+}
+
+lazy_int!(
+    /// A lazily computed [usize] stored in a single atomic word.
+    /// ```
+    /// use safe_once::race::LazyUsize;
+    /// static CPUS: LazyUsize = LazyUsize::new(|| {
+    ///     std::thread::available_parallelism().map_or(1, |n| n.get())
+    /// });
+    /// assert!(CPUS.get() >= 1);
+    /// ```
+    LazyUsize,
+    AtomicUsize,
+    usize
+);
+
+#[cfg(target_has_atomic = "64")]
+lazy_int!(
+    /// A lazily computed [u64] stored in a single atomic word.
+    LazyU64,
+    AtomicU64,
+    u64
+);
+
+/// A lazily computed [bool] stored in a single atomic byte.
+pub struct LazyBool<F = fn() -> bool> {
+    value: AtomicU8,
+    init: F,
+}
+
+const LAZY_BOOL_UNINIT: u8 = 2;
+
+impl<F> LazyBool<F> {
+    pub const fn new(init: F) -> Self {
+        LazyBool {
+            value: AtomicU8::new(LAZY_BOOL_UNINIT),
+            init,
+        }
+    }
+}
+
+impl<F: Fn() -> bool> LazyBool<F> {
+    /// Return the value, computing it if necessary. See [LazyUsize::get].
+    #[inline]
+    pub fn get(&self) -> bool {
+        match self.value.load(Acquire) {
+            LAZY_BOOL_UNINIT => self.init(),
+            value => value != 0,
+        }
+    }
+
+    #[cold]
+    fn init(&self) -> bool {
+        let value = (self.init)();
+        match self
+            .value
+            .compare_exchange(LAZY_BOOL_UNINIT, value as u8, Release, Acquire)
+        {
+            Ok(_) => value,
+            Err(old) => old != 0,
+        }
+    }
+}
+
+impl<F> Debug for LazyBool<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let value = match self.value.load(Acquire) {
+            LAZY_BOOL_UNINIT => None,
+            value => Some(value != 0),
+        };
+        f.debug_tuple("LazyBool").field(&value).finish()
+    }
+}
+
+/// A once cell for a shared reference.
+/// ```
+/// use safe_once::race::OnceRef;
+/// let arena = vec![1, 2, 3];
+/// let first = OnceRef::new();
+/// assert_eq!(first.get_or_init(|| &arena[0]), &1);
+/// assert_eq!(first.set(&arena[1]), Err(&arena[1]));
+/// ```
+pub struct OnceRef<'a, T> {
+    value: AtomicPtr<T>,
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> OnceRef<'a, T> {
+    pub const fn new() -> Self {
+        OnceRef {
+            value: AtomicPtr::new(null_mut()),
+            marker: PhantomData,
+        }
+    }
+
+    /// Return the reference if initialized.
+    pub fn get(&self) -> Option<&'a T> {
+        unsafe { self.value.load(Acquire).as_ref() }
+    }
+
+    /// Store `value` if uninitialized. Returns `value` if already initialized.
+    pub fn set(&self, value: &'a T) -> Result<(), &'a T> {
+        match self
+            .value
+            .compare_exchange(null_mut(), value as *const T as *mut T, Release, Acquire)
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(value),
+        }
+    }
+
+    /// Return the reference, running `init` if uninitialized. If several threads race, each may
+    /// run `init`, and all return the first reference stored.
+    pub fn get_or_init(&self, init: impl FnOnce() -> &'a T) -> &'a T {
+        match self.get_or_try_init(|| Ok::<_, ()>(init())) {
+            Ok(value) => value,
+            Err(()) => unreachable!(),
+        }
+    }
+
+    /// Like [OnceRef::get_or_init], but `init` may fail, leaving the cell uninitialized.
+    pub fn get_or_try_init<E>(&self, init: impl FnOnce() -> Result<&'a T, E>) -> Result<&'a T, E> {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+        let value = init()?;
+        match self.set(value) {
+            Ok(()) => Ok(value),
+            Err(_) => Ok(self.get().unwrap()),
+        }
+    }
+}
+
+impl<'a, T> Default for OnceRef<'a, T> {
+    fn default() -> Self {
+        OnceRef::new()
+    }
+}
+
+impl<'a, T: Debug> Debug for OnceRef<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("OnceRef").field(&self.get()).finish()
+    }
+}
+
+// The cell shares `&'a T` between threads, like `&'a T` itself.
+unsafe impl<'a, T: Sync> Send for OnceRef<'a, T> {}
+
+unsafe impl<'a, T: Sync> Sync for OnceRef<'a, T> {}
+
+/// A once cell for a [Box], including boxes of unsized values such as `Box<dyn Trait>` and
+/// `Box<str>`. The cell is a single pointer-sized word; because a pointer to an unsized value
+/// does not fit in one atomic word, the box is stored in a second small allocation. Threads that
+/// lose the race drop their boxes.
+/// ```
+/// use safe_once::race::OnceBox;
+/// static NAME: OnceBox<str> = OnceBox::new();
+/// assert_eq!(NAME.get_or_init(|| "hello".into()), "hello");
+/// assert_eq!(NAME.set("world".into()), Err("world".into()));
+/// ```
+#[cfg(feature = "alloc")]
+pub struct OnceBox<T: ?Sized> {
+    value: AtomicPtr<Box<T>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> OnceBox<T> {
+    pub const fn new() -> Self {
+        OnceBox {
+            value: AtomicPtr::new(null_mut()),
+        }
+    }
+
+    /// Return the value if initialized.
+    pub fn get(&self) -> Option<&T> {
+        let value = self.value.load(Acquire);
+        unsafe { value.as_ref().map(|value| &**value) }
+    }
+
+    /// Store `value` if uninitialized. Returns `value` if already initialized.
+    pub fn set(&self, value: Box<T>) -> Result<(), Box<T>> {
+        let new = Box::into_raw(Box::new(value));
+        match self
+            .value
+            .compare_exchange(null_mut(), new, Release, Acquire)
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(*unsafe { Box::from_raw(new) }),
+        }
+    }
+
+    /// Return the value, running `init` if uninitialized. If several threads race, each may run
+    /// `init`, and all return the first value stored.
+    pub fn get_or_init(&self, init: impl FnOnce() -> Box<T>) -> &T {
+        match self.get_or_try_init(|| Ok::<_, ()>(init())) {
+            Ok(value) => value,
+            Err(()) => unreachable!(),
+        }
+    }
+
+    /// Like [OnceBox::get_or_init], but `init` may fail, leaving the cell uninitialized.
+    pub fn get_or_try_init<E>(&self, init: impl FnOnce() -> Result<Box<T>, E>) -> Result<&T, E> {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+        // The losing box is dropped here, and the winner is returned.
+        let _ = self.set(init()?);
+        Ok(self.get().unwrap())
+    }
+
+    /// Remove the value using exclusive access.
+    pub fn take(&mut self) -> Option<Box<T>> {
+        let value = mem::replace(self.value.get_mut(), null_mut());
+        if value.is_null() {
+            return None;
+        }
+        Some(*unsafe { Box::from_raw(value) })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> Drop for OnceBox<T> {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> Default for OnceBox<T> {
+    fn default() -> Self {
+        OnceBox::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized + Debug> Debug for OnceBox<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("OnceBox").field(&self.get()).finish()
+    }
+}
+
+// The cell owns a `Box<T>` and shares `&T` between threads.
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized + Send> Send for OnceBox<T> {}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: ?Sized + Send + Sync> Sync for OnceBox<T> {}
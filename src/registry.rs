@@ -0,0 +1,206 @@
+//! A registry of cells declared anywhere in the program, collected at link time.
+//!
+//! With the `distributed-slice` feature, [register!](crate::register) places a [Registration]
+//! for a static cell into a linker section, so that every registered cell can be enumerated
+//! through [registrations] without any runtime registration cost.
+//! ```
+//! # #[cfg(feature = "distributed-slice")] {
+//! use safe_once::sync::LazyLock;
+//! static CONFIG: LazyLock<String> = LazyLock::new(|| "config".to_string());
+//! safe_once::register!(CONFIG);
+//! let registration = safe_once::registry::find("CONFIG").unwrap();
+//! assert!(!registration.is_initialized());
+//! registration.force();
+//! assert!(registration.is_initialized());
+//! # }
+//! ```
+
+#[cfg(feature = "distributed-slice")]
+#[doc(hidden)]
+pub use linkme;
+
+/// A cell that can be listed in the registry.
+pub trait Registered: Sync {
+    /// Initialize the cell if it knows how to initialize itself.
+    fn force(&self);
+    /// Return true if the cell is initialized (or fused).
+    fn is_initialized(&self) -> bool;
+}
+
+/// An entry in the registry. The layout is `repr(C)` so that the linker section has a fixed
+/// element layout across crates.
+#[repr(C)]
+pub struct Registration {
+    name: &'static str,
+    force: fn(),
+    is_initialized: fn() -> bool,
+    bounds: fn() -> (*const u8, usize),
+}
+
+impl Registration {
+    #[doc(hidden)]
+    pub const fn new(
+        name: &'static str,
+        force: fn(),
+        is_initialized: fn() -> bool,
+        bounds: fn() -> (*const u8, usize),
+    ) -> Self {
+        Registration {
+            name,
+            force,
+            is_initialized,
+            bounds,
+        }
+    }
+    /// The path of the registered static, as written at the registration site.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    /// Initialize the registered cell if it knows how to initialize itself.
+    pub fn force(&self) {
+        (self.force)()
+    }
+    /// Return true if the registered cell is initialized.
+    pub fn is_initialized(&self) -> bool {
+        (self.is_initialized)()
+    }
+    /// Return true if `addr` lies within the registered cell.
+    pub fn contains(&self, addr: *const u8) -> bool {
+        let (start, len) = (self.bounds)();
+        start <= addr && addr < start.wrapping_add(len)
+    }
+}
+
+#[cfg(feature = "distributed-slice")]
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static REGISTRY: [Registration];
+
+/// All registered cells, in an unspecified order. Empty without the `distributed-slice` feature.
+pub fn registrations() -> &'static [Registration] {
+    #[cfg(feature = "distributed-slice")]
+    {
+        &REGISTRY
+    }
+    #[cfg(not(feature = "distributed-slice"))]
+    {
+        &[]
+    }
+}
+
+/// Find a registration by name.
+pub fn find(name: &str) -> Option<&'static Registration> {
+    registrations().iter().find(|r| r.name == name)
+}
+
+/// Find the registration of the cell containing `addr`.
+pub fn find_containing(addr: *const u8) -> Option<&'static Registration> {
+    registrations().iter().find(|r| r.contains(addr))
+}
+
+#[cfg(all(debug_assertions, feature = "std"))]
+pub(crate) use init_order::{check_initialized_before, declare_initialized_before};
+
+// Ordering assertions between cells, checked in debug builds with std.
+#[cfg(all(debug_assertions, feature = "std"))]
+mod init_order {
+    use super::{find_containing, Registered};
+    use std::mem::size_of_val;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::sync::Mutex;
+
+    // A declaration that `before` must be initialized before the cell occupying `after`.
+    struct InitOrder {
+        before: &'static dyn Registered,
+        before_addr: *const u8,
+        after: (*const u8, usize),
+    }
+
+    unsafe impl Send for InitOrder {}
+
+    static HAS_INIT_ORDERS: AtomicBool = AtomicBool::new(false);
+    static INIT_ORDERS: Mutex<Vec<InitOrder>> = Mutex::new(Vec::new());
+
+    fn describe(addr: *const u8) -> String {
+        match find_containing(addr) {
+            Some(registration) => registration.name.to_string(),
+            None => format!("{:?}", addr),
+        }
+    }
+
+    fn bounds<T: Registered>(cell: &'static T) -> (*const u8, usize) {
+        (cell as *const T as *const u8, size_of_val(cell))
+    }
+
+    // Record that `before` must be initialized before `after`. Panics if already violated.
+    pub(crate) fn declare_initialized_before<A: Registered, B: Registered>(
+        before: &'static A,
+        after: &'static B,
+    ) {
+        if after.is_initialized() && !before.is_initialized() {
+            violated(bounds(before).0, bounds(after).0);
+        }
+        INIT_ORDERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(InitOrder {
+                before,
+                before_addr: bounds(before).0,
+                after: bounds(after),
+            });
+        HAS_INIT_ORDERS.store(true, Relaxed);
+    }
+
+    // Called when the cell containing `addr` is about to become initialized.
+    pub(crate) fn check_initialized_before(addr: *const u8) {
+        if !HAS_INIT_ORDERS.load(Relaxed) {
+            return;
+        }
+        let violation = INIT_ORDERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|o| {
+                o.after.0 <= addr
+                    && addr < o.after.0.wrapping_add(o.after.1)
+                    && !o.before.is_initialized()
+            })
+            .map(|o| (o.before_addr, o.after.0));
+        if let Some((before, after)) = violation {
+            violated(before, after);
+        }
+    }
+
+    fn violated(before: *const u8, after: *const u8) -> ! {
+        panic!(
+            "{} must be initialized before {}",
+            describe(before),
+            describe(after)
+        )
+    }
+}
+
+/// Add a static cell to the registry.
+#[cfg(feature = "distributed-slice")]
+#[macro_export]
+macro_rules! register {
+    ($cell:path) => {
+        const _: () = {
+            #[$crate::registry::linkme::distributed_slice($crate::registry::REGISTRY)]
+            #[linkme(crate = $crate::registry::linkme)]
+            static REGISTRATION: $crate::registry::Registration =
+                $crate::registry::Registration::new(
+                    ::std::stringify!($cell),
+                    || $crate::registry::Registered::force(&$cell),
+                    || $crate::registry::Registered::is_initialized(&$cell),
+                    || {
+                        (
+                            &$cell as *const _ as *const u8,
+                            ::std::mem::size_of_val(&$cell),
+                        )
+                    },
+                );
+        };
+    };
+}
@@ -0,0 +1,162 @@
+//! Record the order in which registered cells begin initialization, and replay that order in a
+//! later run to reproduce race-dependent startup bugs.
+//!
+//! Only cells added to the [registry](crate::registry) with [register!](crate::register)
+//! participate, since they are identified by name across runs. During replay, a thread that is
+//! about to initialize a cell appearing in the log parks until every earlier event in the log has
+//! happened. Only the order in which initialization begins is enforced; initializers that have
+//! begun may still run concurrently. If the runs diverge and the expected event does not happen
+//! within [REPLAY_TIMEOUT], the expected event is skipped.
+
+use crate::registry;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How long replay waits for the expected event before skipping it.
+pub const REPLAY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A cell beginning initialization on a thread.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Event {
+    pub cell: String,
+    pub thread: String,
+}
+
+/// A sequence of events, serialized as one `cell<TAB>thread` line per event.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Log(pub Vec<Event>);
+
+impl Display for Log {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for event in &self.0 {
+            writeln!(f, "{}\t{}", event.cell, event.thread)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseLogError;
+
+impl FromStr for Log {
+    type Err = ParseLogError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .map(|line| {
+                let (cell, thread) = line.split_once('\t').ok_or(ParseLogError)?;
+                Ok(Event {
+                    cell: cell.to_string(),
+                    thread: thread.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Log)
+    }
+}
+
+enum Mode {
+    Off,
+    Record(Vec<Event>),
+    Replay { log: Vec<Event>, next: usize },
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static MODE: Mutex<Mode> = Mutex::new(Mode::Off);
+static CONDVAR: Condvar = Condvar::new();
+
+fn set_mode(mode: Mode) -> Mode {
+    let mut guard = MODE.lock().unwrap();
+    ACTIVE.store(!matches!(mode, Mode::Off), Ordering::Relaxed);
+    let old = std::mem::replace(&mut *guard, mode);
+    CONDVAR.notify_all();
+    old
+}
+
+/// Start recording, discarding any previous recording or replay.
+pub fn record() {
+    set_mode(Mode::Record(vec![]));
+}
+
+/// Start replaying `log`, discarding any previous recording or replay.
+pub fn replay(log: Log) {
+    set_mode(Mode::Replay {
+        log: log.0,
+        next: 0,
+    });
+}
+
+/// Stop recording or replaying, and return the recorded events (if recording).
+pub fn stop() -> Log {
+    match set_mode(Mode::Off) {
+        Mode::Record(events) => Log(events),
+        _ => Log::default(),
+    }
+}
+
+fn cell_name(addr: *const u8) -> Option<&'static str> {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return None;
+    }
+    registry::find_containing(addr).map(|r| r.name())
+}
+
+/// Called by raw locks before attempting to take a write lock that may block.
+pub(crate) fn before_lock(addr: *const u8) {
+    let Some(name) = cell_name(addr) else {
+        return;
+    };
+    let mut guard = MODE.lock().unwrap();
+    loop {
+        let Mode::Replay { log, next } = &mut *guard else {
+            return;
+        };
+        let Some(position) = log[*next..].iter().position(|e| e.cell == name) else {
+            return;
+        };
+        if position == 0 {
+            return;
+        }
+        let expected = *next;
+        let (new_guard, timeout) = CONDVAR.wait_timeout(guard, REPLAY_TIMEOUT).unwrap();
+        guard = new_guard;
+        if timeout.timed_out() {
+            if let Mode::Replay { next, .. } = &mut *guard {
+                if *next == expected {
+                    *next += 1;
+                    CONDVAR.notify_all();
+                }
+            }
+        }
+    }
+}
+
+/// Called by raw locks after taking a write lock.
+pub(crate) fn after_lock(addr: *const u8) {
+    let Some(name) = cell_name(addr) else {
+        return;
+    };
+    let mut guard = MODE.lock().unwrap();
+    match &mut *guard {
+        Mode::Off => {}
+        Mode::Record(events) => {
+            let thread = thread::current();
+            events.push(Event {
+                cell: name.to_string(),
+                thread: match thread.name() {
+                    Some(name) => name.to_string(),
+                    None => format!("{:?}", thread.id()),
+                },
+            });
+        }
+        Mode::Replay { log, next } => {
+            if log.get(*next).is_some_and(|e| e.cell == name) {
+                *next += 1;
+                CONDVAR.notify_all();
+            }
+        }
+    }
+}
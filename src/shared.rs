@@ -0,0 +1,203 @@
+//! Initialization that happens once across processes that map the same shared memory.
+//!
+//! A [SharedOnceLock] is placed in a shared mapping by one process with
+//! [SharedOnceLock::init_in_place], and referenced by the others with
+//! [SharedOnceLock::from_ptr]. Initialization runs under a robust, process-shared mutex, so if a
+//! process dies while initializing, the next process to lock it observes the death and the lock
+//! becomes poisoned instead of hanging.
+//! ```
+//! # #[cfg(all(feature = "shared", target_os = "linux"))] {
+//! use safe_once::shared::SharedOnceLock;
+//! use std::mem::MaybeUninit;
+//! // A real program would place this in a MAP_SHARED mapping.
+//! let mut memory = Box::new(MaybeUninit::<SharedOnceLock<u64>>::uninit());
+//! let once = unsafe { SharedOnceLock::init_in_place(memory.as_mut_ptr()) }.unwrap();
+//! assert_eq!(*once.get_or_init(|| 42), 42);
+//! assert_eq!(once.try_get(), Some(&42));
+//! # }
+//! ```
+
+use crate::error::{PoisonError, TryLockError};
+use std::cell::UnsafeCell;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::mem::MaybeUninit;
+use std::panic::Location;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::thread::panicking;
+
+const UNINIT: u32 = 0;
+const READ: u32 = 1;
+const POISON: u32 = 2;
+
+/// Types that have the same meaning in every process, because they contain no pointers, file
+/// descriptors, or other process-local state.
+///
+/// # Safety
+/// Every bit pattern written by one process must be a valid value of the type in another.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => { $(unsafe impl Pod for $t {})* };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// A value initialized at most once by any of the processes that map it.
+#[repr(C)]
+pub struct SharedOnceLock<T: Pod> {
+    state: AtomicU32,
+    mutex: UnsafeCell<libc::pthread_mutex_t>,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T: Pod + Send> Send for SharedOnceLock<T> {}
+
+unsafe impl<T: Pod + Send + Sync> Sync for SharedOnceLock<T> {}
+
+// Holds the mutex. Unlocks on drop, poisoning if the initializer panicked.
+struct Guard<'a, T: Pod>(&'a SharedOnceLock<T>);
+
+impl<'a, T: Pod> Drop for Guard<'a, T> {
+    fn drop(&mut self) {
+        if panicking() {
+            self.0.state.store(POISON, Release);
+        }
+        unsafe { libc::pthread_mutex_unlock(self.0.mutex.get()) };
+    }
+}
+
+fn check(result: libc::c_int) -> io::Result<()> {
+    match result {
+        0 => Ok(()),
+        error => Err(io::Error::from_raw_os_error(error)),
+    }
+}
+
+impl<T: Pod> SharedOnceLock<T> {
+    /// Initialize an uninitialized SharedOnceLock at `ptr`, typically within a shared mapping,
+    /// and return a reference to it.
+    ///
+    /// # Safety
+    /// `ptr` must be valid and aligned for `'a`, and no other process may use it until this
+    /// returns.
+    pub unsafe fn init_in_place<'a>(ptr: *mut Self) -> io::Result<&'a Self> {
+        unsafe {
+            let mut attr = MaybeUninit::<libc::pthread_mutexattr_t>::uninit();
+            check(libc::pthread_mutexattr_init(attr.as_mut_ptr()))?;
+            let result = (|| {
+                check(libc::pthread_mutexattr_setpshared(
+                    attr.as_mut_ptr(),
+                    libc::PTHREAD_PROCESS_SHARED,
+                ))?;
+                check(libc::pthread_mutexattr_setrobust(
+                    attr.as_mut_ptr(),
+                    libc::PTHREAD_MUTEX_ROBUST,
+                ))?;
+                check(libc::pthread_mutexattr_settype(
+                    attr.as_mut_ptr(),
+                    libc::PTHREAD_MUTEX_ERRORCHECK,
+                ))?;
+                (&raw mut (*ptr).state).write(AtomicU32::new(UNINIT));
+                check(libc::pthread_mutex_init(
+                    UnsafeCell::raw_get(&raw const (*ptr).mutex),
+                    attr.as_ptr(),
+                ))
+            })();
+            libc::pthread_mutexattr_destroy(attr.as_mut_ptr());
+            result?;
+            Ok(&*ptr)
+        }
+    }
+
+    /// Reference a SharedOnceLock that another process initialized with
+    /// [SharedOnceLock::init_in_place].
+    ///
+    /// # Safety
+    /// `ptr` must point to an initialized SharedOnceLock that remains mapped for `'a`.
+    pub unsafe fn from_ptr<'a>(ptr: *const Self) -> &'a Self {
+        unsafe { &*ptr }
+    }
+
+    /// Return the value if initialized, or an error if poisoned.
+    pub fn try_get_checked(&self) -> Result<Option<&T>, PoisonError<()>> {
+        match self.state.load(Acquire) {
+            READ => Ok(Some(unsafe { (*self.value.get()).assume_init_ref() })),
+            POISON => Err(PoisonError::new(())),
+            _ => Ok(None),
+        }
+    }
+
+    /// Like [SharedOnceLock::try_get_checked], but panics if poisoned.
+    pub fn try_get(&self) -> Option<&T> {
+        self.try_get_checked().unwrap()
+    }
+
+    /// Return the value, initializing it with `init` if no process has. Returns an error if
+    /// poisoned, including by a process that died while initializing, or if the current thread
+    /// is already initializing it.
+    pub fn get_or_init_checked(&self, init: impl FnOnce() -> T) -> Result<&T, TryLockError<()>> {
+        if let Some(value) = self.try_get_checked()? {
+            return Ok(value);
+        }
+        match unsafe { libc::pthread_mutex_lock(self.mutex.get()) } {
+            0 => {}
+            libc::EDEADLK => return Err(TryLockError::WouldBlock),
+            libc::EOWNERDEAD => {
+                // The owner died holding the mutex. Keep the mutex usable, and poison the value
+                // unless the owner finished initializing it before dying.
+                let _ = self
+                    .state
+                    .compare_exchange(UNINIT, POISON, Relaxed, Relaxed);
+                unsafe {
+                    libc::pthread_mutex_consistent(self.mutex.get());
+                    libc::pthread_mutex_unlock(self.mutex.get());
+                }
+                return match self.try_get_checked()? {
+                    Some(value) => Ok(value),
+                    None => Err(PoisonError::new(()).into()),
+                };
+            }
+            _ => return Err(PoisonError::new(()).into()),
+        }
+        let guard = Guard(self);
+        match self.state.load(Relaxed) {
+            UNINIT => unsafe {
+                (*self.value.get()).write(init());
+                self.state.store(READ, Release);
+            },
+            READ => {}
+            _ => return Err(PoisonError::new(()).into()),
+        }
+        drop(guard);
+        Ok(unsafe { (*self.value.get()).assume_init_ref() })
+    }
+
+    /// Like [SharedOnceLock::get_or_init_checked], but panics if poisoned or deadlocked.
+    #[track_caller]
+    pub fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+        match self.get_or_init_checked(init) {
+            Ok(value) => value,
+            Err(TryLockError::WouldBlock) => panic!(
+                "deadlock: SharedOnceLock was initialized again at {} by its own initializer",
+                Location::caller()
+            ),
+            Err(TryLockError::Poisoned(e)) => panic!("{:?}", e),
+        }
+    }
+}
+
+impl<T: Pod + Debug> Debug for SharedOnceLock<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_tuple("SharedOnceLock");
+        match self.try_get_checked() {
+            Ok(Some(value)) => d.field(value),
+            Ok(None) => d.field(&format_args!("<uninit>")),
+            Err(_) => d.field(&format_args!("<poisoned>")),
+        };
+        d.finish()
+    }
+}
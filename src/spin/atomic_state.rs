@@ -0,0 +1,78 @@
+use crate::api::raw::RawFusedState;
+use crate::atomic::AtomicU8;
+use crate::atomic::Ordering::{Acquire, Relaxed, Release};
+use crate::error::PoisonError;
+use core::fmt::Formatter;
+
+pub(crate) const UNLOCKED: u8 = 0;
+pub(crate) const WRITE: u8 = 1;
+pub(crate) const READ: u8 = 2;
+pub(crate) const POISON: u8 = 3;
+
+/// The state machine shared by [RawFusedSpin](super::RawFusedSpin) and
+/// [RawFusedIsr](crate::cell::RawFusedIsr), which differ only in what a request does when it
+/// finds the write lock held.
+pub(crate) struct AtomicState {
+    state: AtomicU8,
+}
+
+impl AtomicState {
+    pub(crate) const fn new(state: u8) -> Self {
+        AtomicState {
+            state: AtomicU8::new(state),
+        }
+    }
+
+    pub(crate) fn state(state: u8) -> Result<RawFusedState, PoisonError<()>> {
+        match state {
+            READ => Ok(RawFusedState::Read),
+            POISON => Err(PoisonError::new(())),
+            _ => Ok(RawFusedState::Write),
+        }
+    }
+
+    pub(crate) fn load(&self) -> u8 {
+        self.state.load(Acquire)
+    }
+
+    // Obtain the write lock if unlocked. Returns None if it is held.
+    pub(crate) fn try_write(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        match self
+            .state
+            .compare_exchange(UNLOCKED, WRITE, Acquire, Acquire)
+        {
+            Ok(_) => Ok(Some(RawFusedState::Write)),
+            Err(WRITE) => Ok(None),
+            Err(state) => Ok(Some(Self::state(state)?)),
+        }
+    }
+
+    pub(crate) fn try_write_poisoned(&self) -> bool {
+        self.state
+            .compare_exchange(POISON, WRITE, Acquire, Relaxed)
+            .is_ok()
+    }
+
+    pub(crate) fn is_write_locked(&self) -> bool {
+        self.state.load(Relaxed) == WRITE
+    }
+
+    // Release the write lock, leaving `state`.
+    pub(crate) fn unlock(&self, state: u8) {
+        self.state.store(state, Release);
+    }
+
+    pub(crate) fn get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+        Self::state(*self.state.get_mut())
+    }
+
+    pub(crate) fn debug(&self, name: &str, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let state = match self.state.load(Relaxed) {
+            UNLOCKED => "unlocked",
+            WRITE => "write",
+            READ => "read",
+            _ => "poison",
+        };
+        f.debug_tuple(name).field(&state).finish()
+    }
+}
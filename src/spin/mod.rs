@@ -0,0 +1,33 @@
+//! Implementations for `no_std` targets, whose waiters spin instead of parking.
+//!
+//! Without an operating system there is no thread identity, so unlike [sync](crate::sync) these
+//! types cannot detect that a thread is waiting for itself: reentrant initialization spins
+//! forever instead of panicking. Without std, poisoning relies on the unwinding state, which is
+//! unavailable, so a guard dropped during a panic unlocks instead of poisoning. These types still
+//! work with std, for example in libraries that support both.
+//! ```
+//! use safe_once::spin::{LazySpin, OnceSpin};
+//! static LAZY: LazySpin<u32> = LazySpin::new(|| 42);
+//! static ONCE: OnceSpin<&str> = OnceSpin::new();
+//! assert_eq!(*LAZY, 42);
+//! assert_eq!(*ONCE.get_or_init(|| "hello"), "hello");
+//! ```
+
+pub(crate) mod atomic_state;
+mod raw_fused_spin;
+
+use crate::api::aligned::Aligned;
+use crate::api::fused::Fused;
+use crate::api::lazy::Lazy;
+use crate::api::once::Once;
+use crate::api::pin::OncePin;
+pub use raw_fused_spin::*;
+
+pub type OnceSpin<T> = Once<RawFusedSpin, T>;
+pub type LazySpin<T, F = fn() -> T> = Lazy<RawFusedSpin, T, F>;
+pub type FusedSpin<T> = Fused<RawFusedSpin, T>;
+
+/// A [OnceSpin] whose value is aligned to at least `ALIGN` bytes.
+pub type OnceSpinAligned<T, const ALIGN: usize> = Once<RawFusedSpin, Aligned<T, ALIGN>>;
+/// A [OnceSpin] whose value is pinned once initialized. See [crate::api::pin].
+pub type OnceSpinPin<T> = OncePin<RawFusedSpin, T>;
@@ -0,0 +1,127 @@
+use super::atomic_state::{AtomicState, READ, UNLOCKED, WRITE};
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState};
+use crate::error::{PoisonError, TryLockError};
+use core::fmt::{Debug, Formatter};
+use core::hint::spin_loop;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[cfg(not(feature = "no-poison"))]
+use super::atomic_state::POISON;
+// Without poisoning, a panicking initializer unlocks so that the next caller retries.
+#[cfg(feature = "no-poison")]
+const POISON: u8 = UNLOCKED;
+
+/// A [RawFused] that spins while another thread holds the write lock. It needs neither an
+/// allocator nor an operating system.
+pub struct RawFusedSpin {
+    state: AtomicState,
+}
+
+impl RawFusedSpin {
+    const fn with_state(state: u8) -> Self {
+        RawFusedSpin {
+            state: AtomicState::new(state),
+        }
+    }
+
+    // Spin until the write lock is released, returning the new state.
+    fn spin_while_locked(&self) -> u8 {
+        loop {
+            match self.state.load() {
+                WRITE => spin_loop(),
+                state => return state,
+            }
+        }
+    }
+}
+
+unsafe impl RawFusedConst for RawFusedSpin {
+    const UNLOCKED: Self = RawFusedSpin::with_state(UNLOCKED);
+    const READ: Self = RawFusedSpin::with_state(READ);
+    const POISON: Self = RawFusedSpin::with_state(POISON);
+}
+
+unsafe impl RawFused for RawFusedSpin {
+    type GuardMarker = ();
+
+    fn unlocked() -> Self {
+        Self::UNLOCKED
+    }
+
+    fn read() -> Self {
+        Self::READ
+    }
+
+    fn poisoned() -> Self {
+        Self::POISON
+    }
+
+    fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        loop {
+            if let Some(state) = self.try_write_checked()? {
+                return Ok(state);
+            }
+            self.spin_while_locked();
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        loop {
+            if let Some(state) = self.try_write_checked()? {
+                return Ok(Some(state));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            spin_loop();
+        }
+    }
+
+    fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        self.state.try_write()
+    }
+
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        Ok(AtomicState::state(self.spin_while_locked())?)
+    }
+
+    fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+        AtomicState::state(self.state.load())
+    }
+
+    #[cfg(not(feature = "no-poison"))]
+    fn try_write_poisoned(&self) -> bool {
+        self.state.try_write_poisoned()
+    }
+
+    fn is_write_locked(&self) -> Option<bool> {
+        Some(self.state.is_write_locked())
+    }
+
+    unsafe fn unlock(&self) {
+        self.state.unlock(UNLOCKED);
+    }
+
+    unsafe fn unlock_poison(&self) {
+        self.state.unlock(POISON);
+    }
+
+    unsafe fn unlock_fuse(&self) {
+        self.state.unlock(READ);
+    }
+
+    fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+        self.state.get_mut()
+    }
+}
+
+impl Debug for RawFusedSpin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.state.debug("RawFusedSpin", f)
+    }
+}
@@ -0,0 +1,99 @@
+//! Counters of contention and initialization, for finding the cells that threads pile up on.
+//!
+//! With the `stats` feature, every [sync](crate::sync) cell counts the times threads parked
+//! waiting for it, the initializers it ran, and the time they took. Read them for one cell with
+//! `stats`, as in [OnceLock::stats](crate::api::once::Once::stats), or summed over every cell with
+//! [global].
+//! ```
+//! use safe_once::sync::LazyLock;
+//! static CONFIG: LazyLock<u32> = LazyLock::new(|| 1);
+//! assert_eq!(*CONFIG, 1);
+//! let stats = CONFIG.stats().unwrap();
+//! assert_eq!(stats.init_attempts, 1);
+//! assert!(safe_once::stats::global().init_attempts >= 1);
+//! ```
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+use std::time::{Duration, Instant};
+
+/// A snapshot of the counters of one cell, or of every cell.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of times a thread parked (or spun, if parking is disabled) waiting for the
+    /// cell.
+    pub parks: u64,
+    /// The number of initializers that began running, including those that panicked.
+    pub init_attempts: u64,
+    /// The total time spent in those initializers.
+    pub init_duration: Duration,
+}
+
+/// The counters of one cell, which also add to the [global] counters.
+#[derive(Debug)]
+pub struct Counters {
+    parks: AtomicU64,
+    init_attempts: AtomicU64,
+    init_nanos: AtomicU64,
+}
+
+static GLOBAL: Counters = Counters::new();
+
+/// The counters summed over every cell whose backend counts them.
+pub fn global() -> Stats {
+    GLOBAL.get()
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Counters {
+            parks: AtomicU64::new(0),
+            init_attempts: AtomicU64::new(0),
+            init_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// A snapshot of the counters.
+    pub fn get(&self) -> Stats {
+        Stats {
+            parks: self.parks.load(Relaxed),
+            init_attempts: self.init_attempts.load(Relaxed),
+            init_duration: Duration::from_nanos(self.init_nanos.load(Relaxed)),
+        }
+    }
+
+    pub(crate) fn park(&self) {
+        for counters in [self, &GLOBAL] {
+            counters.parks.fetch_add(1, Relaxed);
+        }
+    }
+}
+
+/// Counts an initializer when it starts, and its duration when dropped, whether or not it
+/// panicked.
+pub(crate) struct Timing<'a> {
+    counters: Option<&'a Counters>,
+    start: Instant,
+}
+
+impl<'a> Timing<'a> {
+    pub(crate) fn start(counters: Option<&'a Counters>) -> Self {
+        for counters in counters.into_iter().flat_map(|x| [x, &GLOBAL]) {
+            counters.init_attempts.fetch_add(1, Relaxed);
+        }
+        Timing {
+            counters,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Timing<'_> {
+    fn drop(&mut self) {
+        let nanos = u64::try_from(self.start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        for counters in self.counters.into_iter().flat_map(|x| [x, &GLOBAL]) {
+            counters.init_nanos.fetch_add(nanos, Relaxed);
+        }
+    }
+}
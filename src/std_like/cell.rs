@@ -0,0 +1,7 @@
+//! Replacements for [std::cell::OnceCell] and [std::cell::LazyCell].
+
+use crate::cell::RawFusedCell;
+use crate::std_like::{StdLazy, StdOnce};
+
+pub type OnceCell<T> = StdOnce<RawFusedCell, T>;
+pub type LazyCell<T, F = fn() -> T> = StdLazy<RawFusedCell, T, F>;
@@ -0,0 +1,181 @@
+//! Drop-in replacements for the standard library's once types, with the same names and methods.
+//!
+//! Replacing `std::sync` or `std::cell` with [sync] or [cell] in imports migrates code to this
+//! crate while keeping the standard behavior where it differs from the rest of the crate: a
+//! panicking initializer leaves a [sync::OnceLock] or [cell::OnceCell] uninitialized instead of
+//! poisoning it. Reentrant initialization still panics instead of deadlocking.
+//! ```
+//! # #[cfg(feature = "std-like")] {
+//! use safe_once::std_like::sync::{LazyLock, OnceLock};
+//! static NAME: OnceLock<String> = OnceLock::new();
+//! static GREETING: LazyLock<String> = LazyLock::new(|| format!("hello {}", NAME.get().unwrap()));
+//! assert_eq!(NAME.set("world".to_string()), Ok(()));
+//! assert_eq!(*GREETING, "hello world");
+//! # }
+//! ```
+
+pub mod cell;
+pub mod sync;
+
+use crate::api::lazy::Lazy;
+use crate::api::once::{Once, OnceEntry};
+use crate::api::raw::{RawFused, RawFusedConst};
+use crate::sync::RawFusedLock;
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+/// The implementation of [sync::OnceLock] and [cell::OnceCell].
+pub struct StdOnce<R: RawFused, T> {
+    once: Once<R, T>,
+}
+
+impl<R: RawFusedConst, T> StdOnce<R, T> {
+    pub const fn new() -> Self {
+        StdOnce { once: Once::new() }
+    }
+}
+
+impl<R: RawFused, T> StdOnce<R, T> {
+    pub fn get(&self) -> Option<&T> {
+        self.once.try_get()
+    }
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.once.get_mut()
+    }
+    #[track_caller]
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.once.set(value)
+    }
+    #[track_caller]
+    pub fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+        match self.get_or_try_init(|| Ok::<T, Infallible>(init())) {
+            Ok(value) => value,
+            Err(e) => match e {},
+        }
+    }
+    /// Like [StdOnce::get_or_init], but `init` may fail, leaving the value uninitialized.
+    #[track_caller]
+    pub fn get_or_try_init<E>(&self, init: impl FnOnce() -> Result<T, E>) -> Result<&T, E> {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+        match self.once.lock() {
+            OnceEntry::Occupied(value) => Ok(value),
+            OnceEntry::Vacant(guard) => match catch_unwind(AssertUnwindSafe(init)) {
+                Ok(value) => Ok(guard.init(value?)),
+                Err(payload) => {
+                    // Unlock instead of poisoning, so that a later call may initialize.
+                    drop(guard);
+                    resume_unwind(payload)
+                }
+            },
+        }
+    }
+    pub fn into_inner(self) -> Option<T> {
+        self.once.into_inner()
+    }
+    pub fn take(&mut self) -> Option<T> {
+        self.once.take()
+    }
+}
+
+impl<T> StdOnce<RawFusedLock, T> {
+    /// Block until the value is initialized.
+    #[track_caller]
+    pub fn wait(&self) -> &T {
+        self.once.wait()
+    }
+}
+
+impl<R: RawFused, T> Default for StdOnce<R, T> {
+    fn default() -> Self {
+        StdOnce {
+            once: Once::default(),
+        }
+    }
+}
+
+impl<R: RawFused, T> From<T> for StdOnce<R, T> {
+    fn from(value: T) -> Self {
+        StdOnce {
+            once: Once::from(value),
+        }
+    }
+}
+
+impl<R: RawFused, T: Clone> Clone for StdOnce<R, T> {
+    fn clone(&self) -> Self {
+        StdOnce {
+            once: self.once.clone(),
+        }
+    }
+}
+
+impl<R: RawFused, T: PartialEq> PartialEq for StdOnce<R, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<R: RawFused, T: Eq> Eq for StdOnce<R, T> {}
+
+impl<R: RawFused, T: Debug> Debug for StdOnce<R, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut f = f.debug_tuple("Once");
+        match self.get() {
+            Some(value) => f.field(value),
+            None => f.field(&format_args!("<uninit>")),
+        };
+        f.finish()
+    }
+}
+
+/// The implementation of [sync::LazyLock] and [cell::LazyCell].
+pub struct StdLazy<R: RawFused, T, F = fn() -> T> {
+    lazy: Lazy<R, T, F>,
+}
+
+impl<R: RawFusedConst, T, F: FnOnce() -> T> StdLazy<R, T, F> {
+    pub const fn new(init: F) -> Self {
+        StdLazy {
+            lazy: Lazy::new(init),
+        }
+    }
+}
+
+impl<R: RawFused, T, F: FnOnce() -> T> StdLazy<R, T, F> {
+    #[track_caller]
+    pub fn force(this: &Self) -> &T {
+        this.lazy.forced()
+    }
+}
+
+impl<R: RawFused, T, F: FnOnce() -> T> Deref for StdLazy<R, T, F> {
+    type Target = T;
+    #[track_caller]
+    fn deref(&self) -> &T {
+        StdLazy::force(self)
+    }
+}
+
+impl<R: RawFused, T: Default> Default for StdLazy<R, T> {
+    fn default() -> Self {
+        StdLazy {
+            lazy: Lazy::default(),
+        }
+    }
+}
+
+impl<R: RawFused, T: Debug, F> Debug for StdLazy<R, T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut f = f.debug_tuple("Lazy");
+        match self.lazy.try_get_checked() {
+            Ok(Some(value)) => f.field(value),
+            Ok(None) => f.field(&format_args!("<uninit>")),
+            Err(_) => f.field(&format_args!("<poisoned>")),
+        };
+        f.finish()
+    }
+}
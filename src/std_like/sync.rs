@@ -0,0 +1,110 @@
+//! Replacements for [std::sync::OnceLock], [std::sync::LazyLock], and [std::sync::Once].
+
+use crate::std_like::{StdLazy, StdOnce};
+use crate::sync::RawFusedLock;
+use std::fmt::{Debug, Formatter};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
+
+pub type OnceLock<T> = StdOnce<RawFusedLock, T>;
+pub type LazyLock<T, F = fn() -> T> = StdLazy<RawFusedLock, T, F>;
+
+/// A replacement for [std::sync::Once]. A panicking closure poisons the Once: later calls to
+/// [Once::call_once] panic, while [Once::call_once_force] runs its closure again.
+pub struct Once {
+    once: crate::sync::OnceLock<()>,
+    // Only accessed while holding the lock of `once`.
+    poisoned: AtomicBool,
+}
+
+/// The state passed to [Once::call_once_force].
+#[derive(Debug)]
+pub struct OnceState {
+    poisoned: bool,
+}
+
+impl OnceState {
+    /// Whether a previous closure panicked.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+}
+
+impl Once {
+    pub const fn new() -> Self {
+        Once {
+            once: crate::sync::OnceLock::new(),
+            poisoned: AtomicBool::new(false),
+        }
+    }
+    /// Run `init` if no closure has completed. Panics if a previous closure panicked.
+    #[track_caller]
+    pub fn call_once(&self, init: impl FnOnce()) {
+        self.call(false, |_| init());
+    }
+    /// Run `init` if no closure has completed, even if a previous closure panicked.
+    #[track_caller]
+    pub fn call_once_force(&self, init: impl FnOnce(&OnceState)) {
+        self.call(true, init);
+    }
+    #[track_caller]
+    fn call(&self, force: bool, init: impl FnOnce(&OnceState)) {
+        if self.is_completed() {
+            return;
+        }
+        let crate::api::once::OnceEntry::Vacant(guard) = self.once.lock() else {
+            return;
+        };
+        let state = OnceState {
+            poisoned: self.poisoned.load(Relaxed),
+        };
+        if state.poisoned && !force {
+            drop(guard);
+            panic!("Once instance has previously been poisoned");
+        }
+        match catch_unwind(AssertUnwindSafe(|| init(&state))) {
+            Ok(()) => {
+                guard.init(());
+            }
+            Err(payload) => {
+                self.poisoned.store(true, Relaxed);
+                drop(guard);
+                resume_unwind(payload)
+            }
+        }
+    }
+    /// Whether a closure has completed.
+    pub fn is_completed(&self) -> bool {
+        self.once.try_get().is_some()
+    }
+    /// Block until a closure has completed. Panics if a closure panicked before the call. Unlike
+    /// the standard library, a call that is already waiting when a closure panics keeps waiting
+    /// for a later closure to complete.
+    #[track_caller]
+    pub fn wait(&self) {
+        if !self.is_completed() && self.once.get_blocking().is_none() {
+            if self.poisoned.load(Relaxed) {
+                panic!("Once instance has previously been poisoned");
+            }
+            self.once.wait();
+        }
+    }
+    /// Block until a closure has completed, ignoring poisoning.
+    #[track_caller]
+    pub fn wait_force(&self) {
+        self.once.wait();
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Once::new()
+    }
+}
+
+impl Debug for Once {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Once").finish_non_exhaustive()
+    }
+}
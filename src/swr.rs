@@ -0,0 +1,256 @@
+//! A lazily fetched value that is served stale while it is revalidated in the background.
+//!
+//! The first access runs the fetch, either blocking ([LazySwr::get]) or on a background thread
+//! ([LazySwr::try_get]). Once a value has been fetched, every access returns it immediately. If a
+//! TTL is configured and the value is older than the TTL, an access also starts a background
+//! fetch, and the result replaces the value as a new generation when it succeeds. Failed
+//! refreshes leave the previous value in place, and delay the next background fetch by a backoff
+//! that doubles with each consecutive failure, from 100 milliseconds up to a minute. A fetch that
+//! panics counts as a failure.
+//! ```
+//! use safe_once::swr::LazySwr;
+//! use std::time::Duration;
+//! let config = LazySwr::new(|| Ok::<_, ()>("config".to_string())).with_ttl(Duration::from_secs(60));
+//! assert_eq!(*config.get().unwrap(), "config");
+//! assert_eq!(config.generation(), 1);
+//! ```
+
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::{AcqRel, Release};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The value of the latest generation, unless it was discarded.
+struct Current<T> {
+    value: Option<(Arc<T>, Instant)>,
+    generation: u64,
+}
+
+/// The latest value of a cache that expires, and when it was stored. Shared by [LazySwr] and
+/// [ExpiringLazy](crate::sync::ExpiringLazy). Readers only contend with stores, which run no
+/// caller code under the lock.
+pub(crate) struct Cached<T> {
+    current: RwLock<Current<T>>,
+}
+
+impl<T> Cached<T> {
+    pub(crate) const fn new() -> Self {
+        Cached {
+            current: RwLock::new(Current {
+                value: None,
+                generation: 0,
+            }),
+        }
+    }
+
+    /// The value, if any, and whether it is at least `ttl` old.
+    pub(crate) fn get(&self, ttl: Option<Duration>) -> Option<(Arc<T>, bool)> {
+        let current = self.current.read().unwrap_or_else(PoisonError::into_inner);
+        let (value, stored_at) = current.value.as_ref()?;
+        let stale = ttl.is_some_and(|ttl| stored_at.elapsed() >= ttl);
+        Some((value.clone(), stale))
+    }
+
+    /// The number of values stored so far.
+    pub(crate) fn generation(&self) -> u64 {
+        let current = self.current.read().unwrap_or_else(PoisonError::into_inner);
+        current.generation
+    }
+
+    /// Store `value` as a new generation.
+    pub(crate) fn store(&self, value: Arc<T>) {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        current.generation += 1;
+        let previous = current.value.replace((value, Instant::now()));
+        // Dropped after unlocking, in case it panics.
+        drop(current);
+        drop(previous);
+    }
+
+    /// Discard the value, keeping the generation.
+    pub(crate) fn clear(&self) {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        let previous = current.value.take();
+        drop(current);
+        drop(previous);
+    }
+}
+
+struct Inner<T, F> {
+    fetch: F,
+    current: Cached<T>,
+    // Held while fetching the first value, so that blocking callers fetch it only once.
+    first: Mutex<()>,
+    refreshing: AtomicBool,
+    backoff: Mutex<Backoff>,
+}
+
+// When background fetches may start again after consecutive failures.
+struct Backoff {
+    retry_at: Option<Instant>,
+    delay: Duration,
+}
+
+const MIN_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Lock `mutex`, ignoring poison: a fetch or destructor that panics leaves no state half-updated.
+fn lock<X>(mutex: &Mutex<X>) -> MutexGuard<'_, X> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A value fetched by `F` on first access and optionally refreshed after a TTL.
+pub struct LazySwr<T, F> {
+    ttl: Option<Duration>,
+    inner: Arc<Inner<T, F>>,
+}
+
+impl<T, E, F> LazySwr<T, F>
+where
+    F: Fn() -> Result<T, E> + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    /// Construct a LazySwr that fetches with `fetch` and never refreshes.
+    pub fn new(fetch: F) -> Self {
+        LazySwr {
+            ttl: None,
+            inner: Arc::new(Inner {
+                fetch,
+                current: Cached::new(),
+                first: Mutex::new(()),
+                refreshing: AtomicBool::new(false),
+                backoff: Mutex::new(Backoff {
+                    retry_at: None,
+                    delay: MIN_BACKOFF,
+                }),
+            }),
+        }
+    }
+
+    /// Refresh the value in the background when an access finds it older than `ttl`.
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        LazySwr {
+            ttl: Some(ttl),
+            ..self
+        }
+    }
+
+    /// Return the current value, fetching it on this thread if there is none. If the fetch
+    /// fails, the error is returned and a later call fetches again.
+    pub fn get(&self) -> Result<Arc<T>, E> {
+        if let Some(value) = self.current() {
+            return Ok(value);
+        }
+        let _first = lock(&self.inner.first);
+        if let Some(value) = self.current() {
+            return Ok(value);
+        }
+        let value = Arc::new((self.inner.fetch)()?);
+        self.inner.store(value.clone());
+        Ok(value)
+    }
+
+    /// Return the current value without blocking. If there is none, start fetching it in the
+    /// background.
+    pub fn try_get(&self) -> Option<Arc<T>> {
+        let value = self.current();
+        if value.is_none() {
+            self.inner.spawn_refresh();
+        }
+        value
+    }
+
+    /// The number of values fetched so far. Each refresh that succeeds starts a new generation.
+    pub fn generation(&self) -> u64 {
+        self.inner.current.generation()
+    }
+
+    // Return the current value, starting a refresh if it is stale.
+    fn current(&self) -> Option<Arc<T>> {
+        let (value, stale) = self.inner.current.get(self.ttl)?;
+        if stale {
+            self.inner.spawn_refresh();
+        }
+        Some(value)
+    }
+}
+
+impl<T, E, F> Inner<T, F>
+where
+    F: Fn() -> Result<T, E> + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    fn store(&self, value: Arc<T>) {
+        *lock(&self.backoff) = Backoff {
+            retry_at: None,
+            delay: MIN_BACKOFF,
+        };
+        self.current.store(value);
+    }
+
+    // Fetch a new value on a background thread, unless a fetch is already running or failed too
+    // recently.
+    fn spawn_refresh(self: &Arc<Self>) {
+        if lock(&self.backoff)
+            .retry_at
+            .is_some_and(|retry_at| Instant::now() < retry_at)
+        {
+            return;
+        }
+        if self.refreshing.swap(true, AcqRel) {
+            return;
+        }
+        let inner = self.clone();
+        thread::spawn(move || {
+            let mut refresh = Refresh {
+                inner: &inner,
+                failed: true,
+            };
+            if let Ok(value) = (inner.fetch)() {
+                refresh.failed = false;
+                inner.store(Arc::new(value));
+            }
+        });
+    }
+}
+
+// Ends a background fetch, even if it panics.
+struct Refresh<'a, T, F> {
+    inner: &'a Inner<T, F>,
+    failed: bool,
+}
+
+impl<T, F> Drop for Refresh<'_, T, F> {
+    fn drop(&mut self) {
+        if self.failed {
+            let mut backoff = lock(&self.inner.backoff);
+            backoff.retry_at = Instant::now().checked_add(backoff.delay);
+            backoff.delay = (backoff.delay * 2).min(MAX_BACKOFF);
+        }
+        self.inner.refreshing.store(false, Release);
+    }
+}
+
+impl<T, F> Clone for LazySwr<T, F> {
+    fn clone(&self) -> Self {
+        LazySwr {
+            ttl: self.ttl,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Debug, F> Debug for LazySwr<T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazySwr")
+            .field("ttl", &self.ttl)
+            .field(
+                "value",
+                &self.inner.current.get(None).map(|(value, _)| value),
+            )
+            .field("generation", &self.inner.current.generation())
+            .finish()
+    }
+}
@@ -0,0 +1,80 @@
+use crate::api::lazy::LazyInit;
+use crate::error::LockError;
+use crate::sync::LazyLock;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A [LazyLock] whose clones share one value, so the initializer runs once no matter which clone
+/// is forced first.
+/// ```
+/// use safe_once::sync::ArcLazy;
+/// let config = ArcLazy::new(|| "config".to_string());
+/// let worker = config.clone();
+/// std::thread::spawn(move || assert_eq!(*worker, "config")).join().unwrap();
+/// assert!(config.try_get().is_some());
+/// ```
+pub struct ArcLazy<T, F = fn() -> T> {
+    lazy: Arc<LazyLock<T, F>>,
+}
+
+impl<T, F> ArcLazy<T, F> {
+    pub fn new(init: F) -> Self {
+        ArcLazy {
+            lazy: Arc::new(LazyLock::new(init)),
+        }
+    }
+    pub fn try_get_checked(&self) -> Result<Option<&T>, LockError> {
+        self.lazy.try_get_checked()
+    }
+    pub fn try_get(&self) -> Option<&T> {
+        self.lazy.try_get()
+    }
+    /// Whether `this` and `other` share a value.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.lazy, &other.lazy)
+    }
+}
+
+impl<T, F: LazyInit<T>> ArcLazy<T, F> {
+    #[track_caller]
+    pub fn try_forced(&self) -> Result<&T, LockError> {
+        self.lazy.try_forced()
+    }
+    #[track_caller]
+    pub fn forced(&self) -> &T {
+        self.lazy.forced()
+    }
+}
+
+impl<T, F> Clone for ArcLazy<T, F> {
+    fn clone(&self) -> Self {
+        ArcLazy {
+            lazy: self.lazy.clone(),
+        }
+    }
+}
+
+impl<T, F: LazyInit<T>> Deref for ArcLazy<T, F> {
+    type Target = T;
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.forced()
+    }
+}
+
+impl<T, F> From<T> for ArcLazy<T, F> {
+    fn from(value: T) -> Self {
+        ArcLazy {
+            lazy: Arc::new(LazyLock::from(value)),
+        }
+    }
+}
+
+impl<T: Debug, F> Debug for ArcLazy<T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArcLazy")
+            .field("value", &self.try_get_checked())
+            .finish()
+    }
+}
@@ -0,0 +1,40 @@
+//! The layers of the [OnceLock] fast path, for localizing performance regressions. Only built
+//! with `RUSTFLAGS="--cfg safe_once_bench"`. See `benches/layers.rs`.
+//!
+//! Each function adds one layer to the previous one. None of them change the behavior of the
+//! cells they inspect.
+
+use crate::api::raw::{RawFused, RawFusedState};
+use crate::sync::{OnceLock, RawFusedLock};
+use std::sync::atomic::Ordering::{Acquire, Relaxed};
+
+/// Load the state without a fence.
+#[inline(never)]
+pub fn state_relaxed(lock: &RawFusedLock) -> bool {
+    lock.load_state(Relaxed).init()
+}
+
+/// Load the state with the acquire fence that publishes the value.
+#[inline(never)]
+pub fn state_acquire(lock: &RawFusedLock) -> bool {
+    lock.load_state(Acquire).init()
+}
+
+/// Check whether parking would be needed, as the slow path does before sleeping.
+#[inline(never)]
+pub fn parking_ready(lock: &RawFusedLock) -> bool {
+    let state = lock.load_state(Acquire);
+    state.locked() && !state.parked()
+}
+
+/// The [RawFused] fast path, including the poison check.
+#[inline(never)]
+pub fn raw_read(lock: &RawFusedLock) -> bool {
+    matches!(lock.try_read_checked(), Ok(RawFusedState::Read))
+}
+
+/// The full fast path through the generic [Once](crate::api::once::Once) plumbing.
+#[inline(never)]
+pub fn once_get<T>(once: &OnceLock<T>) -> Option<&T> {
+    once.try_get()
+}
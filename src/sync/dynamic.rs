@@ -0,0 +1,21 @@
+//! Implementations that avoid synchronization while only one thread uses them.
+//!
+//! [DynRawFused] lets the first thread to use it lock and unlock with plain loads and stores, and
+//! switches every thread to the behavior of [RawFusedLock](super::RawFusedLock) once a second
+//! thread arrives. They detect reentrant initialization like the rest of [sync](super).
+//! ```
+//! use safe_once::sync::dynamic::{LazyLock, OnceLock};
+//! static LAZY: LazyLock<u32> = LazyLock::new(|| 42);
+//! static ONCE: OnceLock<&str> = OnceLock::new();
+//! assert_eq!(*LAZY, 42);
+//! assert_eq!(*ONCE.get_or_init(|| "hello"), "hello");
+//! ```
+
+use crate::api::fused::Fused;
+use crate::api::lazy::Lazy;
+use crate::api::once::Once;
+use crate::sync::DynRawFused;
+
+pub type OnceLock<T> = Once<DynRawFused, T>;
+pub type LazyLock<T, F = fn() -> T> = Lazy<DynRawFused, T, F>;
+pub type FusedLock<T> = Fused<DynRawFused, T>;
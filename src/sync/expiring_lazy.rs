@@ -0,0 +1,96 @@
+use crate::api::fused::{FusedEntry, FusedGuard};
+use crate::swr::Cached;
+use crate::sync::{FusedLock, RawFusedLock};
+use std::fmt::{Debug, Formatter};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A lazy value that is recomputed by the first access after it is older than a TTL. Callers
+/// that hold the previous [Arc] keep using it, and readers of a fresh value never wait for each
+/// other. See also [crate::swr::LazySwr], which shares its expiry and refreshes in the background
+/// instead of blocking the caller.
+/// ```
+/// use safe_once::sync::ExpiringLazy;
+/// use std::time::Duration;
+/// static TOKEN: ExpiringLazy<String> =
+///     ExpiringLazy::new(Duration::from_secs(300), || "token".to_string());
+/// assert_eq!(*TOKEN.get(), "token");
+/// ```
+pub struct ExpiringLazy<T, F = fn() -> T> {
+    ttl: Duration,
+    current: Cached<T>,
+    // Never fused; the write lock serializes initializers and detects reentrant initialization.
+    initializing: FusedLock<()>,
+    init: F,
+}
+
+impl<T, F> ExpiringLazy<T, F> {
+    pub const fn new(ttl: Duration, init: F) -> Self {
+        ExpiringLazy {
+            ttl,
+            current: Cached::new(),
+            initializing: FusedLock::new(()),
+            init,
+        }
+    }
+
+    /// Return the value if it has been computed and has not expired.
+    pub fn try_get(&self) -> Option<Arc<T>> {
+        match self.current.get(Some(self.ttl)) {
+            Some((value, false)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Make the next access recompute the value.
+    pub fn invalidate(&self) {
+        self.current.clear();
+    }
+}
+
+impl<T, F: Fn() -> T> ExpiringLazy<T, F> {
+    /// Return the value, computing it if it is missing or expired. Concurrent callers wait for a
+    /// single computation. If the initializer panics, the expired value is discarded and a later
+    /// call computes it again.
+    #[track_caller]
+    pub fn get(&self) -> Arc<T> {
+        if let Some(value) = self.try_get() {
+            return value;
+        }
+        let initializing = self.lock();
+        if let Some(value) = self.try_get() {
+            return value;
+        }
+        self.current.clear();
+        match catch_unwind(AssertUnwindSafe(&self.init)) {
+            Ok(value) => {
+                let value = Arc::new(value);
+                self.current.store(value.clone());
+                value
+            }
+            Err(payload) => {
+                // Unlock instead of poisoning.
+                drop(initializing);
+                resume_unwind(payload)
+            }
+        }
+    }
+
+    #[track_caller]
+    fn lock(&self) -> FusedGuard<'_, RawFusedLock, ()> {
+        match self.initializing.write() {
+            FusedEntry::Write(guard) => guard,
+            FusedEntry::Read(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T: Debug, F> Debug for ExpiringLazy<T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExpiringLazy")
+            .field("ttl", &self.ttl)
+            .field("value", &self.try_get())
+            .finish()
+    }
+}
@@ -0,0 +1,21 @@
+//! Implementations that wait on the operating system's futex and never allocate.
+//!
+//! [RawFusedFutex] keeps its entire state in the lock word, so these types may be used within a
+//! `#[global_allocator]`, where parking_lot_core's allocation of its parking table would recurse
+//! into the allocator. They detect reentrant initialization like the rest of [sync](super).
+//! ```
+//! use safe_once::sync::futex::{LazyLock, OnceLock};
+//! static LAZY: LazyLock<u32> = LazyLock::new(|| 42);
+//! static ONCE: OnceLock<&str> = OnceLock::new();
+//! assert_eq!(*LAZY, 42);
+//! assert_eq!(*ONCE.get_or_init(|| "hello"), "hello");
+//! ```
+
+use crate::api::fused::Fused;
+use crate::api::lazy::Lazy;
+use crate::api::once::Once;
+use crate::sync::RawFusedFutex;
+
+pub type OnceLock<T> = Once<RawFusedFutex, T>;
+pub type LazyLock<T, F = fn() -> T> = Lazy<RawFusedFutex, T, F>;
+pub type FusedLock<T> = Fused<RawFusedFutex, T>;
@@ -0,0 +1,255 @@
+use crate::api::raw::SpinWait;
+use crate::sync::park;
+use crate::sync::parking_disabled;
+use crate::sync::raw_fused_lock::parking_failed;
+use crate::sync::thread_id::ThreadId;
+use std::cell::UnsafeCell;
+use std::fmt::{Debug, Formatter};
+use std::mem;
+use std::ops::Deref;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::{self, null_mut};
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicPtr, AtomicUsize};
+use std::thread;
+
+// The low bits of the pointer.
+const INIT: usize = 0b00;
+const LOCKED: usize = 0b01;
+const READ: usize = 0b10;
+const POISON: usize = 0b11;
+const TAG_MASK: usize = 0b11;
+
+enum Slot<T> {
+    Init(Box<dyn FnOnce() -> T + Send>),
+    Value(T),
+    Empty,
+}
+
+// The heap allocation, which holds a boxed initializer until forced and the value afterwards.
+struct Node<T> {
+    // The thread running the initializer, for deadlock detection.
+    owner: AtomicUsize,
+    slot: UnsafeCell<Slot<T>>,
+}
+
+/// A [LazyLock](crate::sync::LazyLock) that is two pointers inline whatever the size of the
+/// value, for tables of many lazy values. The value is stored in a heap allocation, made when
+/// first forced, whose pointer is tagged with the state.
+/// ```
+/// use safe_once::sync::LazyBox;
+/// static NAME: LazyBox<String> = LazyBox::new(|| "name".to_string());
+/// assert_eq!(*NAME, "name");
+/// let table: Vec<LazyBox<String>> = (0..3).map(|i| LazyBox::boxed(move || i.to_string())).collect();
+/// assert_eq!(*table[1], "1");
+/// assert_eq!(size_of::<LazyBox<[u8; 1024]>>(), 2 * size_of::<usize>());
+/// ```
+pub struct LazyBox<T> {
+    // Null until forced, unless constructed with a boxed initializer.
+    ptr: AtomicPtr<Node<T>>,
+    init: Option<fn() -> T>,
+}
+
+impl<T> LazyBox<T> {
+    /// Construct a LazyBox that runs `init` when first forced. Allocates nothing until then, so
+    /// it can be used in statics.
+    pub const fn new(init: fn() -> T) -> Self {
+        LazyBox {
+            ptr: AtomicPtr::new(null_mut()),
+            init: Some(init),
+        }
+    }
+
+    /// Like [LazyBox::new], but accepts a closure, which is boxed immediately.
+    pub fn boxed(init: impl FnOnce() -> T + Send + 'static) -> Self {
+        LazyBox {
+            ptr: AtomicPtr::new(Box::into_raw(Box::new(Node {
+                owner: AtomicUsize::new(0),
+                slot: UnsafeCell::new(Slot::Init(Box::new(init))),
+            }))),
+            init: None,
+        }
+    }
+
+    fn node(state: *mut Node<T>) -> *mut Node<T> {
+        state.map_addr(|addr| addr & !TAG_MASK)
+    }
+
+    fn tag(state: *mut Node<T>) -> usize {
+        state.addr() & TAG_MASK
+    }
+
+    unsafe fn value<'a>(state: *mut Node<T>) -> &'a T {
+        match unsafe { &*(*Self::node(state)).slot.get() } {
+            Slot::Value(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Return the value if already initialized, without forcing. Panics if poisoned.
+    pub fn try_get(&self) -> Option<&T> {
+        let state = self.ptr.load(Acquire);
+        match Self::tag(state) {
+            READ => Some(unsafe { Self::value(state) }),
+            POISON => panic!("LazyBox instance has previously been poisoned"),
+            _ => None,
+        }
+    }
+
+    /// Force initialization and return a reference to the value. Panics if poisoned or
+    /// deadlocked.
+    #[track_caller]
+    pub fn force(this: &Self) -> &T {
+        let state = this.ptr.load(Acquire);
+        if Self::tag(state) == READ {
+            return unsafe { Self::value(state) };
+        }
+        this.force_slow()
+    }
+
+    #[cold]
+    #[track_caller]
+    fn force_slow(&self) -> &T {
+        let tid = ThreadId::current().0;
+        let mut spin = SpinWait::new();
+        loop {
+            let state = self.ptr.load(Acquire);
+            match Self::tag(state) {
+                READ => return unsafe { Self::value(state) },
+                POISON => panic!("LazyBox instance has previously been poisoned"),
+                INIT if state.is_null() => {
+                    let node = Box::into_raw(Box::new(Node {
+                        owner: AtomicUsize::new(tid),
+                        slot: UnsafeCell::new(Slot::Empty),
+                    }));
+                    if self
+                        .ptr
+                        .compare_exchange(state, node.map_addr(|a| a | LOCKED), Acquire, Relaxed)
+                        .is_ok()
+                    {
+                        return self.run(node, self.init.unwrap());
+                    }
+                    drop(unsafe { Box::from_raw(node) });
+                }
+                INIT => {
+                    if self
+                        .ptr
+                        .compare_exchange_weak(
+                            state,
+                            state.map_addr(|a| a | LOCKED),
+                            Acquire,
+                            Relaxed,
+                        )
+                        .is_ok()
+                    {
+                        let node = unsafe { &*state };
+                        node.owner.store(tid, Relaxed);
+                        let slot = unsafe { &mut *node.slot.get() };
+                        let Slot::Init(init) = mem::replace(slot, Slot::Empty) else {
+                            unreachable!()
+                        };
+                        return self.run(state, init);
+                    }
+                }
+                _ if unsafe { &*Self::node(state) }.owner.load(Relaxed) == tid => {
+                    panic!("deadlock: LazyBox was forced again by its own initializer")
+                }
+                _ if spin.spin() => {}
+                _ => self.park(state),
+            }
+        }
+    }
+
+    // Run `init` with the write lock of `node` held.
+    fn run(&self, node: *mut Node<T>, init: impl FnOnce() -> T) -> &T {
+        // Poison and wake waiters if the initializer panics.
+        struct Poison<'a, T>(&'a LazyBox<T>, *mut Node<T>);
+        impl<'a, T> Drop for Poison<'a, T> {
+            fn drop(&mut self) {
+                self.0.publish(self.1.map_addr(|a| a | POISON));
+            }
+        }
+        let poison = Poison(self, node);
+        let value = init();
+        unsafe { *(*node).slot.get() = Slot::Value(value) };
+        mem::forget(poison);
+        let state = node.map_addr(|a| a | READ);
+        self.publish(state);
+        unsafe { Self::value(state) }
+    }
+
+    fn publish(&self, state: *mut Node<T>) {
+        unsafe { (*Self::node(state)).owner.store(0, Relaxed) };
+        self.ptr.store(state, Release);
+        if parking_disabled() {
+            return;
+        }
+        let addr = ptr::from_ref(self).addr();
+        let unpark = catch_unwind(|| unsafe {
+            park::unpark_all(addr);
+        });
+        if unpark.is_err() {
+            parking_failed();
+        }
+    }
+
+    fn park(&self, state: *mut Node<T>) {
+        if parking_disabled() {
+            return thread::yield_now();
+        }
+        let addr = ptr::from_ref(self).addr();
+        let park = catch_unwind(AssertUnwindSafe(|| unsafe {
+            park::park(
+                addr,
+                || self.ptr.load(Relaxed) == state,
+                || {},
+                |_, _| {},
+                None,
+            );
+        }));
+        if park.is_err() {
+            parking_failed();
+        }
+    }
+}
+
+impl<T> Deref for LazyBox<T> {
+    type Target = T;
+    #[track_caller]
+    fn deref(&self) -> &T {
+        LazyBox::force(self)
+    }
+}
+
+impl<T> Drop for LazyBox<T> {
+    fn drop(&mut self) {
+        let node = Self::node(*self.ptr.get_mut());
+        if !node.is_null() {
+            unsafe { drop(Box::from_raw(node)) }
+        }
+    }
+}
+
+impl<T: Default> Default for LazyBox<T> {
+    fn default() -> Self {
+        LazyBox::new(T::default)
+    }
+}
+
+impl<T: Debug> Debug for LazyBox<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = self.ptr.load(Acquire);
+        let mut f = f.debug_tuple("LazyBox");
+        match Self::tag(state) {
+            READ => f.field(unsafe { Self::value(state) }),
+            POISON => f.field(&format_args!("<poisoned>")),
+            _ => f.field(&format_args!("<uninit>")),
+        };
+        f.finish()
+    }
+}
+
+// The initializer is Send, and the value is shared between threads once forced.
+unsafe impl<T: Send> Send for LazyBox<T> {}
+
+unsafe impl<T: Send + Sync> Sync for LazyBox<T> {}
@@ -0,0 +1,103 @@
+use crate::cell::OnceCell;
+use crate::sync::OnceVec;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+
+static NEXT_THREAD_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static THREAD_INDEX: usize = NEXT_THREAD_INDEX.fetch_add(1, Relaxed);
+}
+
+// A value that is only accessed by the thread with its index, or through `&mut`.
+struct Slot<T>(OnceCell<T>);
+
+unsafe impl<T: Send> Sync for Slot<T> {}
+
+/// A lazy value computed separately by each thread on its first access. Threads are assigned
+/// dense indices that are never reused, so a value is kept, and can be visited by
+/// [iter_mut](LazyThreadLocal::iter_mut), after its thread exits.
+/// ```
+/// use safe_once::sync::LazyThreadLocal;
+/// use std::cell::Cell;
+/// let mut counters = LazyThreadLocal::new(|| Cell::new(0));
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| {
+///             let counter = counters.get();
+///             counter.set(counter.get() + 1);
+///         });
+///     }
+/// });
+/// assert_eq!(counters.iter_mut().map(|c| c.get()).sum::<i32>(), 4);
+/// ```
+pub struct LazyThreadLocal<T, F = fn() -> T> {
+    slots: OnceVec<Slot<T>>,
+    init: F,
+}
+
+impl<T, F> LazyThreadLocal<T, F> {
+    pub const fn new(init: F) -> Self {
+        LazyThreadLocal {
+            slots: OnceVec::new(),
+            init,
+        }
+    }
+
+    fn slot(&self) -> &OnceCell<T> {
+        let index = THREAD_INDEX.with(|index| *index);
+        &self.slots.get_or_init(index, || Slot(OnceCell::new())).0
+    }
+
+    /// Return the value for the current thread if it has been computed.
+    pub fn try_get(&self) -> Option<&T> {
+        self.slot().try_get()
+    }
+
+    /// Iterate over the values computed by every thread.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots
+            .iter_mut()
+            .filter_map(|(_, slot)| slot.0.get_mut())
+    }
+
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.slots
+            .iter_mut()
+            .filter_map(|(_, slot)| slot.0.take())
+            .collect()
+    }
+}
+
+impl<T, F: Fn() -> T> LazyThreadLocal<T, F> {
+    /// Return the value for the current thread, computing it if necessary. Panics if the
+    /// initializer panicked on this thread before, or if it accesses this value.
+    #[track_caller]
+    pub fn get(&self) -> &T {
+        self.slot().get_or_init(&self.init)
+    }
+}
+
+impl<T, F: Fn() -> T> Deref for LazyThreadLocal<T, F> {
+    type Target = T;
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T: Default> Default for LazyThreadLocal<T> {
+    fn default() -> Self {
+        LazyThreadLocal::new(T::default)
+    }
+}
+
+impl<T: Debug, F> Debug for LazyThreadLocal<T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyThreadLocal")
+            .field("value", &self.try_get())
+            .finish()
+    }
+}
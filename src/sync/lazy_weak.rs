@@ -0,0 +1,77 @@
+use crate::api::fused::FusedEntry;
+use crate::sync::FusedLock;
+use std::fmt::{Debug, Formatter};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::{Arc, PoisonError, RwLock, Weak};
+
+/// A lazy value that is dropped when no [Arc] to it remains, and recomputed by the next access.
+/// ```
+/// use safe_once::sync::LazyWeak;
+/// static POOL: LazyWeak<Vec<u8>> = LazyWeak::new(|| vec![0; 1024]);
+/// let pool = POOL.get();
+/// assert!(std::sync::Arc::ptr_eq(&pool, &POOL.get()));
+/// drop(pool);
+/// assert!(POOL.try_get().is_none());
+/// ```
+pub struct LazyWeak<T, F = fn() -> T> {
+    // Readers only contend with the store of a new value, which runs no caller code.
+    weak: RwLock<Weak<T>>,
+    // Never fused; the write lock serializes initializers and detects reentrant initialization.
+    initializing: FusedLock<()>,
+    init: F,
+}
+
+impl<T, F> LazyWeak<T, F> {
+    pub const fn new(init: F) -> Self {
+        LazyWeak {
+            weak: RwLock::new(Weak::new()),
+            initializing: FusedLock::new(()),
+            init,
+        }
+    }
+
+    /// Return the value if it is alive, without computing it.
+    pub fn try_get(&self) -> Option<Arc<T>> {
+        self.weak
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .upgrade()
+    }
+}
+
+impl<T, F: Fn() -> T> LazyWeak<T, F> {
+    /// Return the value, computing it if no [Arc] to it remains. Concurrent callers wait for a
+    /// single computation. If the initializer panics, a later call computes the value again.
+    #[track_caller]
+    pub fn get(&self) -> Arc<T> {
+        if let Some(value) = self.try_get() {
+            return value;
+        }
+        let FusedEntry::Write(initializing) = self.initializing.write() else {
+            unreachable!()
+        };
+        if let Some(value) = self.try_get() {
+            return value;
+        }
+        match catch_unwind(AssertUnwindSafe(&self.init)) {
+            Ok(value) => {
+                let value = Arc::new(value);
+                *self.weak.write().unwrap_or_else(PoisonError::into_inner) = Arc::downgrade(&value);
+                value
+            }
+            Err(payload) => {
+                // Unlock instead of poisoning.
+                drop(initializing);
+                resume_unwind(payload)
+            }
+        }
+    }
+}
+
+impl<T: Debug, F> Debug for LazyWeak<T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyWeak")
+            .field("value", &self.try_get())
+            .finish()
+    }
+}
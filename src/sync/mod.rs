@@ -1,16 +1,80 @@
 //! Implementations that are [Sync](::std::marker::Sync).
 
+mod arc_lazy;
+#[cfg(safe_once_bench)]
+#[doc(hidden)]
+pub mod bench;
+#[cfg(feature = "dyn-backend")]
+pub mod dynamic;
+mod expiring_lazy;
+#[cfg(feature = "futex")]
+pub mod futex;
+mod lazy_box;
+mod lazy_thread_local;
+mod lazy_weak;
+mod once_array;
+mod once_dyn;
+mod once_vec;
+mod park;
+#[cfg(feature = "dyn-backend")]
+mod raw_fused_dyn;
+#[cfg(feature = "futex")]
+mod raw_fused_futex;
 mod raw_fused_lock;
+mod raw_fused_std_thread;
 mod state;
+pub mod std_park;
 #[cfg(test)]
 mod test;
 mod thread_id;
+#[cfg(feature = "deadlock-detection")]
+mod wait_for;
 
+use crate::api::aligned::Aligned;
+use crate::api::cow::LazyCow;
 use crate::api::fused::Fused;
+use crate::api::indirect::OnceIndirect;
 use crate::api::lazy::Lazy;
 use crate::api::once::Once;
+use crate::api::pin::OncePin;
+use crate::api::resettable_lazy::ResettableLazy;
+use crate::api::retry_lazy::RetryLazy;
+use crate::append_only::{FrozenMap, FrozenVec};
+pub use arc_lazy::*;
+pub use expiring_lazy::*;
+pub use lazy_box::*;
+pub use lazy_thread_local::*;
+pub use lazy_weak::*;
+pub use once_array::*;
+pub use once_dyn::*;
+pub use once_vec::*;
+#[cfg(feature = "dyn-backend")]
+pub use raw_fused_dyn::*;
+#[cfg(feature = "futex")]
+pub use raw_fused_futex::*;
 pub use raw_fused_lock::*;
+pub use raw_fused_std_thread::*;
+pub use thread_id::OwnerThread;
 
 pub type OnceLock<T> = Once<RawFusedLock, T>;
 pub type LazyLock<T, F = fn() -> T> = Lazy<RawFusedLock, T, F>;
+/// A [LazyLock] that retries its initializer after a panic instead of poisoning.
+pub type RetryLazyLock<T, F = fn() -> T> = RetryLazy<RawFusedLock, T, F>;
+/// A [LazyLock] that can be reset to rerun its initializer. See [crate::api::resettable_lazy].
+pub type ResettableLazyLock<T, F = fn() -> T> = ResettableLazy<RawFusedLock, T, F>;
 pub type FusedLock<T> = Fused<RawFusedLock, T>;
+
+/// A [OnceLock] whose value is aligned to at least `ALIGN` bytes.
+pub type OnceLockAligned<T, const ALIGN: usize> = Once<RawFusedLock, Aligned<T, ALIGN>>;
+/// A [FusedLock] whose value is aligned to at least `ALIGN` bytes.
+pub type FusedLockAligned<T, const ALIGN: usize> = Fused<RawFusedLock, Aligned<T, ALIGN>>;
+/// A [OnceLock] that stores its value on the heap. See [crate::api::indirect].
+pub type OnceLockIndirect<T> = OnceIndirect<RawFusedLock, T>;
+/// A [OnceLock] whose value is pinned once initialized. See [crate::api::pin].
+pub type OnceLockPin<T> = OncePin<RawFusedLock, T>;
+/// A reference to a default that is cloned when first modified. See [crate::api::cow].
+pub type LazyLockCow<'a, T> = LazyCow<'a, RawFusedLock, T>;
+/// A vector that can be appended to through a shared reference. See [crate::append_only].
+pub type FrozenVecLock<T> = FrozenVec<RawFusedLock, T>;
+/// A map that can be inserted into through a shared reference. See [crate::append_only].
+pub type FrozenMapLock<K, V> = FrozenMap<RawFusedLock, K, V>;
@@ -0,0 +1,281 @@
+use crate::api::raw::SpinWait;
+use crate::sync::park;
+use crate::sync::parking_disabled;
+use crate::sync::raw_fused_lock::parking_failed;
+use std::cell::{RefCell, UnsafeCell};
+use std::fmt::{Debug, Formatter};
+use std::mem::{self, MaybeUninit};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::{self, null_mut};
+use std::slice;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, SeqCst};
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::thread;
+
+// The two bits of each slot.
+const INIT: usize = 0b00;
+const LOCKED: usize = 0b01;
+const READ: usize = 0b10;
+const POISON: usize = 0b11;
+const SLOT_MASK: usize = 0b11;
+const SLOTS_PER_WORD: usize = usize::BITS as usize / 2;
+
+thread_local! {
+    // The (array, index) pairs whose initializers are running on this thread, for deadlock
+    // detection.
+    static INITIALIZING: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A fixed-size array of once cells, for large tables of rarely contended slots. The state of
+/// each slot is two bits of a shared bitset, and all slots share one parking queue, so the
+/// overhead per slot is a quarter of a byte instead of a word.
+/// ```
+/// use safe_once::sync::OnceArray;
+/// static TABLE: OnceArray<String, 10000> = OnceArray::new();
+/// assert_eq!(TABLE.get_or_init(1234, || "1234".to_string()), "1234");
+/// assert_eq!(TABLE.get(1234).map(String::as_str), Some("1234"));
+/// assert_eq!(TABLE.get(1235), None);
+/// ```
+/// The values are stored inline, so a large OnceArray should be a static or constructed with
+/// [OnceArray::new_boxed] rather than on the stack.
+pub struct OnceArray<T, const N: usize> {
+    // The bitset, allocated on first use so that the constructors are const.
+    states: AtomicPtr<AtomicUsize>,
+    // Threads parked waiting for any slot, so that initializers can skip unparking.
+    waiters: AtomicUsize,
+    values: [UnsafeCell<MaybeUninit<T>>; N],
+}
+
+impl<T, const N: usize> OnceArray<T, N> {
+    const WORDS: usize = N.div_ceil(SLOTS_PER_WORD);
+
+    pub const fn new() -> Self {
+        OnceArray {
+            states: AtomicPtr::new(null_mut()),
+            waiters: AtomicUsize::new(0),
+            values: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+        }
+    }
+
+    /// Construct a OnceArray on the heap, without moving its values through the stack.
+    pub fn new_boxed() -> Box<Self> {
+        let mut array = Box::<Self>::new_uninit();
+        let ptr = array.as_mut_ptr();
+        unsafe {
+            (&raw mut (*ptr).states).write(AtomicPtr::new(null_mut()));
+            (&raw mut (*ptr).waiters).write(AtomicUsize::new(0));
+            // The values are MaybeUninit, which needs no initialization.
+            array.assume_init()
+        }
+    }
+
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    // The bit offset of the slot at `index` within its word.
+    fn shift(&self, index: usize) -> usize {
+        assert!(
+            index < N,
+            "OnceArray index {} out of range for length {}",
+            index,
+            N
+        );
+        index % SLOTS_PER_WORD * 2
+    }
+
+    fn word(&self, index: usize) -> (&AtomicUsize, usize) {
+        let shift = self.shift(index);
+        let mut states = self.states.load(Acquire);
+        if states.is_null() {
+            states = self.alloc_states();
+        }
+        let states = unsafe { slice::from_raw_parts(states, Self::WORDS) };
+        (&states[index / SLOTS_PER_WORD], shift)
+    }
+
+    #[cold]
+    fn alloc_states(&self) -> *mut AtomicUsize {
+        let states: Box<[AtomicUsize]> = (0..Self::WORDS).map(|_| AtomicUsize::new(0)).collect();
+        let states = Box::into_raw(states).cast::<AtomicUsize>();
+        match self
+            .states
+            .compare_exchange(null_mut(), states, AcqRel, Acquire)
+        {
+            Ok(_) => states,
+            Err(current) => {
+                drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(states, Self::WORDS)) });
+                current
+            }
+        }
+    }
+
+    fn state(&self, index: usize, order: Ordering) -> usize {
+        // Every slot is INIT until the bitset is allocated.
+        if self.states.load(Acquire).is_null() {
+            self.shift(index);
+            return INIT;
+        }
+        let (word, shift) = self.word(index);
+        (word.load(order) >> shift) & SLOT_MASK
+    }
+
+    unsafe fn value(&self, index: usize) -> &T {
+        unsafe { (*self.values[index].get()).assume_init_ref() }
+    }
+
+    /// Return the value at `index` if it has been initialized. Panics if poisoned.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        match self.state(index, Acquire) {
+            READ => Some(unsafe { self.value(index) }),
+            POISON => panic!("OnceArray slot {} has previously been poisoned", index),
+            _ => None,
+        }
+    }
+
+    /// Return the value at `index`, initializing it with `init` if necessary. Panics if
+    /// poisoned or deadlocked.
+    pub fn get_or_init(&self, index: usize, init: impl FnOnce() -> T) -> &T {
+        if let Some(value) = self.get(index) {
+            return value;
+        }
+        self.get_or_init_slow(index, init)
+    }
+
+    #[cold]
+    fn get_or_init_slow(&self, index: usize, init: impl FnOnce() -> T) -> &T {
+        let (word, shift) = self.word(index);
+        let key = (ptr::from_ref(self).addr(), index);
+        let mut spin = SpinWait::new();
+        loop {
+            let current = word.load(Acquire);
+            match (current >> shift) & SLOT_MASK {
+                READ => return unsafe { self.value(index) },
+                POISON => panic!("OnceArray slot {} has previously been poisoned", index),
+                INIT => {
+                    if word
+                        .compare_exchange_weak(current, current | LOCKED << shift, Acquire, Relaxed)
+                        .is_ok()
+                    {
+                        return self.run(index, init);
+                    }
+                }
+                _ if INITIALIZING.with_borrow(|slots| slots.contains(&key)) => panic!(
+                    "deadlock: OnceArray slot {} was initialized again by its own initializer",
+                    index
+                ),
+                _ if spin.spin() => {}
+                _ => self.park(index),
+            }
+        }
+    }
+
+    fn run(&self, index: usize, init: impl FnOnce() -> T) -> &T {
+        // Poison and wake waiters if the initializer panics.
+        struct Poison<'a, T, const N: usize>(&'a OnceArray<T, N>, usize);
+        impl<'a, T, const N: usize> Drop for Poison<'a, T, N> {
+            fn drop(&mut self) {
+                self.0.publish(self.1, POISON);
+            }
+        }
+        let key = (ptr::from_ref(self).addr(), index);
+        INITIALIZING.with_borrow_mut(|slots| slots.push(key));
+        let poison = Poison(self, index);
+        let value = init();
+        unsafe { (*self.values[index].get()).write(value) };
+        mem::forget(poison);
+        self.publish(index, READ);
+        unsafe { self.value(index) }
+    }
+
+    fn publish(&self, index: usize, state: usize) {
+        let key = (ptr::from_ref(self).addr(), index);
+        INITIALIZING.with_borrow_mut(|slots| slots.retain(|slot| *slot != key));
+        let (word, shift) = self.word(index);
+        // The slot is LOCKED, so adding moves it to READ or POISON without touching other slots.
+        word.fetch_add((state - LOCKED) << shift, SeqCst);
+        if self.waiters.load(SeqCst) == 0 || parking_disabled() {
+            return;
+        }
+        let addr = ptr::from_ref(self).addr();
+        let unpark = catch_unwind(|| unsafe {
+            park::unpark_all(addr);
+        });
+        if unpark.is_err() {
+            parking_failed();
+        }
+    }
+
+    fn park(&self, index: usize) {
+        if parking_disabled() {
+            return thread::yield_now();
+        }
+        let addr = ptr::from_ref(self).addr();
+        self.waiters.fetch_add(1, SeqCst);
+        let park = catch_unwind(AssertUnwindSafe(|| unsafe {
+            park::park(
+                addr,
+                || self.state(index, SeqCst) == LOCKED,
+                || {},
+                |_, _| {},
+                None,
+            );
+        }));
+        self.waiters.fetch_sub(1, Relaxed);
+        if park.is_err() {
+            parking_failed();
+        }
+    }
+
+    /// Return the value at `index` mutably if it has been initialized. Exclusive access means no
+    /// synchronization is needed.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        match self.state(index, Relaxed) {
+            READ => Some(unsafe { self.values[index].get_mut().assume_init_mut() }),
+            _ => None,
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for OnceArray<T, N> {
+    fn drop(&mut self) {
+        for index in 0..N {
+            if self.state(index, Relaxed) == READ {
+                unsafe { self.values[index].get_mut().assume_init_drop() }
+            }
+        }
+        let states = *self.states.get_mut();
+        if !states.is_null() {
+            drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(states, Self::WORDS)) });
+        }
+    }
+}
+
+impl<T, const N: usize> Default for OnceArray<T, N> {
+    fn default() -> Self {
+        OnceArray::new()
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for OnceArray<T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut list = f.debug_list();
+        for index in 0..N {
+            match self.state(index, Acquire) {
+                READ => list.entry(unsafe { self.value(index) }),
+                POISON => list.entry(&format_args!("<poisoned>")),
+                _ => list.entry(&format_args!("<uninit>")),
+            };
+        }
+        list.finish()
+    }
+}
+
+// The values are shared between threads through `&self`.
+unsafe impl<T: Send, const N: usize> Send for OnceArray<T, N> {}
+
+unsafe impl<T: Send + Sync, const N: usize> Sync for OnceArray<T, N> {}
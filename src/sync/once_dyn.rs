@@ -0,0 +1,96 @@
+use crate::sync::OnceLock;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::panic::Location;
+
+/// A global implementation of a trait that is installed at most once, such as a logger or a
+/// telemetry sink.
+/// ```
+/// use safe_once::sync::OnceDyn;
+/// trait Sink: Send + Sync {
+///     fn name(&self) -> &str;
+/// }
+/// struct Stderr;
+/// impl Sink for Stderr {
+///     fn name(&self) -> &str {
+///         "stderr"
+///     }
+/// }
+/// static SINK: OnceDyn<dyn Sink> = OnceDyn::new();
+/// SINK.set(Box::new(Stderr)).unwrap();
+/// assert_eq!(SINK.get().unwrap().name(), "stderr");
+/// let error = SINK.set(Box::new(Stderr)).unwrap_err();
+/// assert!(error.to_string().starts_with("already set at "));
+/// ```
+pub struct OnceDyn<D: ?Sized> {
+    once: OnceLock<(Box<D>, &'static Location<'static>)>,
+}
+
+/// The error returned when a [OnceDyn] is set twice. The rejected value is dropped.
+#[derive(Clone, Debug)]
+pub struct SetError {
+    first: &'static Location<'static>,
+    second: &'static Location<'static>,
+}
+
+impl SetError {
+    /// The call that set the value.
+    pub fn first(&self) -> &'static Location<'static> {
+        self.first
+    }
+    /// The call that failed.
+    pub fn second(&self) -> &'static Location<'static> {
+        self.second
+    }
+}
+
+impl Display for SetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "already set at {}; set again at {}",
+            self.first, self.second
+        )
+    }
+}
+
+impl Error for SetError {}
+
+impl<D: ?Sized> OnceDyn<D> {
+    pub const fn new() -> Self {
+        OnceDyn {
+            once: OnceLock::new(),
+        }
+    }
+    /// Install `value`, or return an error naming the call that installed the existing value.
+    #[track_caller]
+    pub fn set(&self, value: Box<D>) -> Result<(), SetError> {
+        let second = Location::caller();
+        self.once.set((value, second)).map_err(|_| SetError {
+            first: self.once.try_get().unwrap().1,
+            second,
+        })
+    }
+    pub fn get(&self) -> Option<&D> {
+        self.once.try_get().map(|(value, _)| &**value)
+    }
+    /// The call that installed the value, if any.
+    pub fn set_at(&self) -> Option<&'static Location<'static>> {
+        self.once.try_get().map(|(_, location)| *location)
+    }
+}
+
+impl<D: ?Sized> Default for OnceDyn<D> {
+    fn default() -> Self {
+        OnceDyn::new()
+    }
+}
+
+impl<D: ?Sized + Debug> Debug for OnceDyn<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnceDyn")
+            .field("value", &self.get())
+            .field("set_at", &self.set_at())
+            .finish()
+    }
+}
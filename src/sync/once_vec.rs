@@ -0,0 +1,234 @@
+use crate::sync::OnceLock;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::Ordering::{AcqRel, Acquire};
+
+// The first bucket holds this many slots, and each bucket is twice the size of the previous.
+const FIRST_BUCKET_BITS: u32 = 5;
+const BUCKETS: usize = (usize::BITS - FIRST_BUCKET_BITS) as usize;
+
+/// An unbounded sequence of [OnceLock]s, each initialized independently. Slots are allocated
+/// lock-free in buckets of doubling size as they are first accessed, and never move, so
+/// references to values live as long as the vector. Accessing a slot allocates every slot in its
+/// bucket, which is about as many slots as precede it.
+/// ```
+/// use safe_once::sync::OnceVec;
+/// static DECODED: OnceVec<String> = OnceVec::new();
+/// assert_eq!(DECODED.get_or_init(1000, || "item 1000".to_string()), "item 1000");
+/// assert_eq!(DECODED.get(1000).map(String::as_str), Some("item 1000"));
+/// assert_eq!(DECODED.get(999), None);
+/// ```
+pub struct OnceVec<T> {
+    buckets: [AtomicPtr<OnceLock<T>>; BUCKETS],
+}
+
+impl<T> OnceVec<T> {
+    pub const fn new() -> Self {
+        OnceVec {
+            buckets: [const { AtomicPtr::new(ptr::null_mut()) }; BUCKETS],
+        }
+    }
+
+    fn bucket_len(bucket: usize) -> usize {
+        1 << (bucket + FIRST_BUCKET_BITS as usize)
+    }
+
+    fn locate(index: usize) -> (usize, usize) {
+        let shifted = index
+            .checked_add(1 << FIRST_BUCKET_BITS)
+            .expect("OnceVec index out of range");
+        let bucket = (shifted.ilog2() - FIRST_BUCKET_BITS) as usize;
+        (bucket, shifted - Self::bucket_len(bucket))
+    }
+
+    /// The slot at `index`, if its bucket has been allocated.
+    pub fn try_slot(&self, index: usize) -> Option<&OnceLock<T>> {
+        let (bucket, offset) = Self::locate(index);
+        let slots = self.buckets[bucket].load(Acquire);
+        if slots.is_null() {
+            return None;
+        }
+        // Buckets are never freed through a shared reference.
+        Some(unsafe { &*slots.add(offset) })
+    }
+
+    /// The slot at `index`, allocating its bucket if necessary.
+    pub fn slot(&self, index: usize) -> &OnceLock<T> {
+        if let Some(slot) = self.try_slot(index) {
+            return slot;
+        }
+        let (bucket, offset) = Self::locate(index);
+        let new: Box<[OnceLock<T>]> = (0..Self::bucket_len(bucket))
+            .map(|_| OnceLock::new())
+            .collect();
+        let new = Box::into_raw(new) as *mut OnceLock<T>;
+        let slots =
+            match self.buckets[bucket].compare_exchange(ptr::null_mut(), new, AcqRel, Acquire) {
+                Ok(_) => new,
+                Err(existing) => {
+                    unsafe { drop(Self::bucket(new, bucket)) };
+                    existing
+                }
+            };
+        unsafe { &*slots.add(offset) }
+    }
+
+    unsafe fn bucket(slots: *mut OnceLock<T>, bucket: usize) -> Box<[OnceLock<T>]> {
+        unsafe {
+            Box::from_raw(ptr::slice_from_raw_parts_mut(
+                slots,
+                Self::bucket_len(bucket),
+            ))
+        }
+    }
+
+    /// Return the value at `index` if it has been initialized.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.try_slot(index)?.try_get()
+    }
+
+    /// Return the value at `index`, initializing it with `init` if necessary.
+    #[track_caller]
+    pub fn get_or_init(&self, index: usize, init: impl FnOnce() -> T) -> &T {
+        self.slot(index).get_or_init(init)
+    }
+
+    /// Return the value at `index`, initializing it with `init` if necessary. If `init` fails, the
+    /// slot remains uninitialized.
+    #[track_caller]
+    pub fn get_or_try_init<E>(
+        &self,
+        index: usize,
+        init: impl FnOnce() -> Result<T, E>,
+    ) -> Result<&T, E> {
+        self.slot(index).lock().or_try_init(init)
+    }
+
+    /// Initialize the value at `index`, or return `value` if it was already initialized.
+    #[track_caller]
+    pub fn set(&self, index: usize, value: T) -> Result<(), T> {
+        self.slot(index).set(value)
+    }
+
+    /// Iterate over the initialized values and their indices.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, slots)| !slots.load(Acquire).is_null())
+            .flat_map(move |(bucket, _)| {
+                let start = Self::bucket_len(bucket) - Self::bucket_len(0);
+                (start..start + Self::bucket_len(bucket))
+                    .filter_map(move |index| Some((index, self.get(index)?)))
+            })
+    }
+}
+
+impl<T> OnceVec<T> {
+    /// Iterate mutably over the initialized values and their indices.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.buckets
+            .iter_mut()
+            .enumerate()
+            .map(|(bucket, slots)| (bucket, *slots.get_mut()))
+            .filter(|(_, slots)| !slots.is_null())
+            .flat_map(|(bucket, slots)| {
+                let start = Self::bucket_len(bucket) - Self::bucket_len(0);
+                let slots =
+                    unsafe { &mut *ptr::slice_from_raw_parts_mut(slots, Self::bucket_len(bucket)) };
+                (start..)
+                    .zip(slots)
+                    .filter_map(|(index, slot)| Some((index, slot.get_mut()?)))
+            })
+    }
+}
+
+impl<T> Drop for OnceVec<T> {
+    fn drop(&mut self) {
+        for (bucket, slots) in self.buckets.iter_mut().enumerate() {
+            if !slots.get_mut().is_null() {
+                unsafe { drop(Self::bucket(*slots.get_mut(), bucket)) };
+            }
+        }
+    }
+}
+
+impl<T> Default for OnceVec<T> {
+    fn default() -> Self {
+        OnceVec::new()
+    }
+}
+
+impl<T: Debug> Debug for OnceVec<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// The vector owns its slots, which are shared between threads through `&self`.
+unsafe impl<T: Send> Send for OnceVec<T> {}
+
+unsafe impl<T: Send + Sync> Sync for OnceVec<T> {}
+
+/// A fixed-length slice of [OnceLock]s, each initialized independently.
+/// ```
+/// use safe_once::sync::OnceSlice;
+/// let slice = OnceSlice::new(3);
+/// assert_eq!(*slice.get_or_init(2, || 'c'), 'c');
+/// assert_eq!(slice[2].try_get(), Some(&'c'));
+/// assert_eq!(slice[0].try_get(), None);
+/// ```
+pub struct OnceSlice<T> {
+    slots: Box<[OnceLock<T>]>,
+}
+
+impl<T> OnceSlice<T> {
+    pub fn new(len: usize) -> Self {
+        OnceSlice {
+            slots: (0..len).map(|_| OnceLock::new()).collect(),
+        }
+    }
+
+    /// Return the value at `index`, initializing it with `init` if necessary. Panics if `index`
+    /// is out of bounds.
+    #[track_caller]
+    pub fn get_or_init(&self, index: usize, init: impl FnOnce() -> T) -> &T {
+        self.slots[index].get_or_init(init)
+    }
+
+    pub fn into_vec(self) -> Vec<Option<T>> {
+        self.slots
+            .into_vec()
+            .into_iter()
+            .map(OnceLock::into_inner)
+            .collect()
+    }
+}
+
+impl<T> Deref for OnceSlice<T> {
+    type Target = [OnceLock<T>];
+    fn deref(&self) -> &Self::Target {
+        &self.slots
+    }
+}
+
+impl<T> FromIterator<Option<T>> for OnceSlice<T> {
+    fn from_iter<I: IntoIterator<Item = Option<T>>>(iter: I) -> Self {
+        OnceSlice {
+            slots: iter
+                .into_iter()
+                .map(|value| value.map_or_else(OnceLock::new, OnceLock::from))
+                .collect(),
+        }
+    }
+}
+
+impl<T: Debug> Debug for OnceSlice<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.slots.iter().map(OnceLock::try_get))
+            .finish()
+    }
+}
@@ -0,0 +1,115 @@
+//! Parking threads on an address until another thread unparks it, with the interface of
+//! `parking_lot_core`. Without the `parking-lot` feature, threads wait on a condition variable
+//! from a fixed table indexed by address.
+
+#[cfg(feature = "parking-lot")]
+mod imp {
+    use parking_lot_core::{DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+    use std::time::Instant;
+
+    pub(crate) unsafe fn park(
+        addr: usize,
+        validate: impl FnOnce() -> bool,
+        before_sleep: impl FnOnce(),
+        timed_out: impl FnOnce(usize, bool),
+        deadline: Option<Instant>,
+    ) {
+        unsafe {
+            parking_lot_core::park(
+                addr,
+                validate,
+                before_sleep,
+                timed_out,
+                DEFAULT_PARK_TOKEN,
+                deadline,
+            );
+        }
+    }
+
+    pub(crate) unsafe fn unpark_all(addr: usize) {
+        unsafe {
+            parking_lot_core::unpark_all(addr, DEFAULT_UNPARK_TOKEN);
+        }
+    }
+}
+
+#[cfg(not(feature = "parking-lot"))]
+mod imp {
+    use crate::sync::thread_id::ThreadId;
+    use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+    use std::time::Instant;
+
+    struct Bucket {
+        // The address and thread of every parked thread. Unparking removes the entries, so a
+        // thread whose entry is gone has been unparked.
+        parked: Mutex<Vec<(usize, usize)>>,
+        condvar: Condvar,
+    }
+
+    const BUCKETS: usize = 64;
+
+    static TABLE: [Bucket; BUCKETS] = [const {
+        Bucket {
+            parked: Mutex::new(Vec::new()),
+            condvar: Condvar::new(),
+        }
+    }; BUCKETS];
+
+    fn bucket(addr: usize) -> &'static Bucket {
+        &TABLE[(addr / 8) % BUCKETS]
+    }
+
+    fn lock(bucket: &Bucket) -> MutexGuard<'_, Vec<(usize, usize)>> {
+        bucket.parked.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) unsafe fn park(
+        addr: usize,
+        validate: impl FnOnce() -> bool,
+        before_sleep: impl FnOnce(),
+        timed_out: impl FnOnce(usize, bool),
+        deadline: Option<Instant>,
+    ) {
+        let bucket = bucket(addr);
+        let entry = (addr, ThreadId::current().0);
+        let mut parked = lock(bucket);
+        if !validate() {
+            return;
+        }
+        parked.push(entry);
+        before_sleep();
+        while parked.contains(&entry) {
+            parked = match deadline {
+                None => bucket
+                    .condvar
+                    .wait(parked)
+                    .unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        parked.retain(|e| *e != entry);
+                        let was_last_thread = !parked.iter().any(|e| e.0 == addr);
+                        return timed_out(addr, was_last_thread);
+                    }
+                    bucket
+                        .condvar
+                        .wait_timeout(parked, deadline - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+            };
+        }
+    }
+
+    pub(crate) unsafe fn unpark_all(addr: usize) {
+        let bucket = bucket(addr);
+        let mut parked = lock(bucket);
+        let len = parked.len();
+        parked.retain(|e| e.0 != addr);
+        if parked.len() != len {
+            bucket.condvar.notify_all();
+        }
+    }
+}
+
+pub(crate) use imp::*;
@@ -0,0 +1,339 @@
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState, SpinWait};
+use crate::sync::state::State;
+use crate::sync::thread_id::ThreadId;
+use crate::sync::{OwnerThread, RawFusedLock};
+use std::fmt::{Debug, Formatter};
+use std::panic::Location;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release, SeqCst};
+use std::sync::atomic::{compiler_fence, AtomicBool, AtomicUsize};
+use std::sync::{PoisonError, TryLockError};
+use std::thread;
+use std::time::Instant;
+
+// The id of the thread that may use the unsynchronized path, or one of the following, none of
+// which are thread ids.
+const UNCLAIMED: usize = 0;
+const REVOKING: usize = 1;
+const SHARED: usize = 2;
+
+static OWNER: AtomicUsize = AtomicUsize::new(UNCLAIMED);
+
+// Whether the owner is within an unsynchronized operation. Only the owner stores to it.
+static IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// A [RawFused] that skips atomic read-modify-write operations while only one thread uses it.
+///
+/// The first thread to use any DynRawFused becomes its owner, and locks and unlocks with plain
+/// loads and stores. When a second thread first uses one, it revokes the owner's fast path with a
+/// process-wide memory barrier (`membarrier` on Linux and Android, `FlushProcessWriteBuffers` on
+/// Windows) and waits for any operation in progress to finish. From then on every thread behaves
+/// as [RawFusedLock], whose state it shares. Mostly single-threaded programs, such as command-line
+/// tools that force many lazies during startup, avoid the cost of synchronization until they
+/// need it. On other platforms, or if the barrier is unavailable, it is always a RawFusedLock.
+///
+/// Reading an initialized cell is as fast as RawFusedLock on every thread.
+pub struct DynRawFused {
+    inner: RawFusedLock,
+}
+
+impl DynRawFused {
+    /// Whether the owning thread still uses the unsynchronized path.
+    pub fn single_threaded() -> bool {
+        OWNER.load(Relaxed) > SHARED
+    }
+
+    // Enter the unsynchronized path if the current thread owns it, and otherwise claim or revoke
+    // it and return None.
+    #[inline]
+    fn unsynchronized() -> Option<Unsynchronized> {
+        let tid = ThreadId::current();
+        let owner = OWNER.load(Acquire);
+        if owner == tid.0 {
+            IN_PROGRESS.store(true, Relaxed);
+            // The revoking thread's barrier orders the store before the load.
+            compiler_fence(SeqCst);
+            let guard = Unsynchronized(tid);
+            return (OWNER.load(Relaxed) == tid.0).then_some(guard);
+        }
+        if owner != SHARED {
+            Self::share(tid, owner);
+        }
+        None
+    }
+
+    #[cold]
+    fn share(tid: ThreadId, mut owner: usize) {
+        let mut spin = SpinWait::new();
+        loop {
+            match owner {
+                SHARED => return,
+                UNCLAIMED => {
+                    // The owner locks without recording its name, so record it now.
+                    ThreadId::current_named();
+                    let next = if barrier::register() { tid.0 } else { SHARED };
+                    match OWNER.compare_exchange(UNCLAIMED, next, Acquire, Acquire) {
+                        Ok(_) => return,
+                        Err(new_owner) => owner = new_owner,
+                    }
+                }
+                REVOKING => {
+                    if !spin.spin() {
+                        thread::yield_now();
+                    }
+                    owner = OWNER.load(Acquire);
+                }
+                _ => match OWNER.compare_exchange(owner, REVOKING, Acquire, Acquire) {
+                    Ok(_) => {
+                        // Afterwards, the owner either sees REVOKING or its IN_PROGRESS is visible.
+                        barrier::run();
+                        while IN_PROGRESS.load(Acquire) {
+                            if !spin.spin() {
+                                thread::yield_now();
+                            }
+                        }
+                        OWNER.store(SHARED, Release);
+                        return;
+                    }
+                    Err(new_owner) => owner = new_owner,
+                },
+            }
+        }
+    }
+
+    // Revoke the fast path unless the current thread owns it, before waiting for or changing the
+    // state atomically. A thread that parked without revoking it would never be woken by the
+    // owner's unsynchronized unlock.
+    fn synchronize() {
+        let _ = Self::unsynchronized();
+    }
+
+    fn unlock_impl(&self, new_state: State, unlock: unsafe fn(&RawFusedLock)) {
+        match Self::unsynchronized() {
+            Some(_guard) => self.inner.unlock_unsynchronized(new_state),
+            None => unsafe { unlock(&self.inner) },
+        }
+    }
+}
+
+// Marks the owner as within an unsynchronized operation until dropped.
+struct Unsynchronized(ThreadId);
+
+impl Drop for Unsynchronized {
+    fn drop(&mut self) {
+        IN_PROGRESS.store(false, Release);
+    }
+}
+
+unsafe impl RawFusedConst for DynRawFused {
+    const UNLOCKED: Self = DynRawFused {
+        inner: RawFusedLock::UNLOCKED,
+    };
+    const READ: Self = DynRawFused {
+        inner: RawFusedLock::READ,
+    };
+    const POISON: Self = DynRawFused {
+        inner: RawFusedLock::POISON,
+    };
+}
+
+unsafe impl RawFused for DynRawFused {
+    type GuardMarker = ();
+
+    fn unlocked() -> Self {
+        Self::UNLOCKED
+    }
+
+    fn read() -> Self {
+        Self::READ
+    }
+
+    fn poisoned() -> Self {
+        Self::POISON
+    }
+
+    #[track_caller]
+    fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        if self.inner.load_state(Acquire).init() {
+            return Ok(RawFusedState::Read);
+        }
+        match Self::unsynchronized() {
+            // Only the current thread could hold the lock.
+            Some(guard) => self
+                .inner
+                .try_lock_unsynchronized(guard.0)?
+                .ok_or(TryLockError::WouldBlock),
+            None => self.inner.write_checked(),
+        }
+    }
+
+    #[track_caller]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        if self.inner.load_state(Acquire).init() {
+            return Ok(Some(RawFusedState::Read));
+        }
+        match Self::unsynchronized() {
+            Some(guard) => match self.inner.try_lock_unsynchronized(guard.0)? {
+                Some(state) => Ok(Some(state)),
+                None => Err(TryLockError::WouldBlock),
+            },
+            None => self.inner.write_until_checked(deadline),
+        }
+    }
+
+    #[track_caller]
+    fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        if self.inner.load_state(Acquire).init() {
+            return Ok(Some(RawFusedState::Read));
+        }
+        match Self::unsynchronized() {
+            Some(guard) => self.inner.try_lock_unsynchronized(guard.0),
+            None => self.inner.try_write_checked(),
+        }
+    }
+
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        if self.inner.load_state(Acquire).init() {
+            return Ok(RawFusedState::Read);
+        }
+        Self::synchronize();
+        self.inner.read_checked()
+    }
+
+    fn wait_read_checked(&self) -> Result<(), TryLockError<()>> {
+        if self.inner.load_state(Acquire).init() {
+            return Ok(());
+        }
+        Self::synchronize();
+        self.inner.wait_read_checked()
+    }
+
+    fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+        if self.inner.load_state(Acquire).init() {
+            return Ok(RawFusedState::Read);
+        }
+        Self::synchronize();
+        self.inner.try_read_checked()
+    }
+
+    #[track_caller]
+    fn try_write_poisoned(&self) -> bool {
+        // The owner may use the atomic path too, so it only needs to revoke others'.
+        Self::synchronize();
+        self.inner.try_write_poisoned()
+    }
+
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        self.inner.owner_location()
+    }
+
+    fn poison_cause(&self) -> Option<crate::error::PoisonCause> {
+        self.inner.poison_cause()
+    }
+
+    fn owner_thread(&self) -> Option<OwnerThread> {
+        self.inner.owner_thread()
+    }
+
+    fn has_waiters(&self) -> Option<bool> {
+        self.inner.has_waiters()
+    }
+
+    #[cfg(feature = "stats")]
+    fn counters(&self) -> Option<&crate::stats::Counters> {
+        self.inner.counters()
+    }
+
+    fn is_write_locked(&self) -> Option<bool> {
+        self.inner.is_write_locked()
+    }
+
+    fn holds_write_lock(&self) -> Option<bool> {
+        self.inner.holds_write_lock()
+    }
+
+    unsafe fn unlock(&self) {
+        self.unlock_impl(State::new(), RawFusedLock::unlock);
+    }
+
+    unsafe fn unlock_poison(&self) {
+        self.unlock_impl(State::new().with_poison(true), RawFusedLock::unlock_poison);
+    }
+
+    unsafe fn unlock_panic(&self, message: String) {
+        self.inner.set_panic(message);
+        unsafe { self.unlock_poison() }
+    }
+
+    unsafe fn unlock_fuse(&self) {
+        self.unlock_impl(State::new().with_init(true), RawFusedLock::unlock_fuse);
+    }
+
+    fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+        self.inner.try_get_mut()
+    }
+}
+
+impl Debug for DynRawFused {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("DynRawFused").field(&self.inner).finish()
+    }
+}
+
+#[cfg(all(any(target_os = "linux", target_os = "android"), not(miri)))]
+mod barrier {
+    const MEMBARRIER_CMD_QUERY: libc::c_long = 0;
+    const MEMBARRIER_CMD_PRIVATE_EXPEDITED: libc::c_long = 1 << 3;
+    const MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED: libc::c_long = 1 << 4;
+
+    fn membarrier(cmd: libc::c_long) -> libc::c_long {
+        unsafe { libc::syscall(libc::SYS_membarrier, cmd, 0) }
+    }
+
+    // Prepare to run barriers, returning false if they are unavailable.
+    pub(super) fn register() -> bool {
+        let supported = membarrier(MEMBARRIER_CMD_QUERY);
+        supported > 0
+            && supported & MEMBARRIER_CMD_PRIVATE_EXPEDITED != 0
+            && membarrier(MEMBARRIER_CMD_REGISTER_PRIVATE_EXPEDITED) == 0
+    }
+
+    // Execute a memory barrier on every running thread of the process.
+    pub(super) fn run() {
+        assert_eq!(
+            membarrier(MEMBARRIER_CMD_PRIVATE_EXPEDITED),
+            0,
+            "membarrier failed after registration"
+        );
+    }
+}
+
+#[cfg(all(windows, not(miri)))]
+mod barrier {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn FlushProcessWriteBuffers();
+    }
+
+    pub(super) fn register() -> bool {
+        true
+    }
+
+    pub(super) fn run() {
+        unsafe { FlushProcessWriteBuffers() }
+    }
+}
+
+#[cfg(any(not(any(target_os = "linux", target_os = "android", windows)), miri))]
+mod barrier {
+    // Without a process-wide barrier, the fast path is never claimed.
+    pub(super) fn register() -> bool {
+        false
+    }
+
+    pub(super) fn run() {
+        unreachable!()
+    }
+}
@@ -0,0 +1,347 @@
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState, SpinWait};
+use crate::sync::thread_id::{OwnerThread, ThreadId};
+use std::fmt::{Debug, Formatter};
+use std::panic::{Location, RefUnwindSafe, UnwindSafe};
+use std::ptr::null_mut;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicPtr, AtomicU32, AtomicUsize};
+use std::sync::{PoisonError, TryLockError};
+use std::time::Instant;
+
+// The futex word. CONTENDED is LOCKED with at least one thread waiting on the futex, so that
+// unlocking only makes a system call when necessary.
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+const CONTENDED: u32 = 2;
+const READ: u32 = 3;
+const POISON: u32 = 4;
+
+/// A [RawFused] that waits on the lock word itself with the operating system's futex (Linux and
+/// Android) or `WaitOnAddress` (Windows), and spins with [std::thread::yield_now] elsewhere.
+///
+/// Unlike the other backends, it never allocates, so it may be used within a
+/// `#[global_allocator]` or while the allocator's own locks are held.
+pub struct RawFusedFutex {
+    state: AtomicU32,
+    owner: AtomicUsize,
+    owner_location: AtomicPtr<Location<'static>>,
+}
+
+impl RawFusedFutex {
+    const fn from_state(state: u32) -> Self {
+        RawFusedFutex {
+            state: AtomicU32::new(state),
+            owner: AtomicUsize::new(0),
+            owner_location: AtomicPtr::new(null_mut()),
+        }
+    }
+
+    // Loop until the state is not LOCKED by another thread. If `lock` then obtain the lock when
+    // UNLOCKED.
+    #[track_caller]
+    fn wait(
+        &self,
+        lock: bool,
+        deadline: Option<Instant>,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        let tid = ThreadId::current().0;
+        let mut spin = SpinWait::new();
+        // After sleeping, other threads may still be waiting, so the lock must be taken as
+        // CONTENDED to ensure they are woken by the next unlock.
+        let mut locked = LOCKED;
+        loop {
+            let state = self.state.load(Acquire);
+            match state {
+                READ => return Ok(Some(RawFusedState::Read)),
+                POISON => return Err(PoisonError::new(()).into()),
+                UNLOCKED if !lock => return Ok(Some(RawFusedState::Write)),
+                UNLOCKED => {
+                    if self
+                        .state
+                        .compare_exchange_weak(UNLOCKED, locked, Acquire, Relaxed)
+                        .is_ok()
+                    {
+                        self.owner.store(tid, Relaxed);
+                        self.owner_location
+                            .store(Location::caller() as *const _ as *mut _, Relaxed);
+                        return Ok(Some(RawFusedState::Write));
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            if self.owner.load(Relaxed) == tid {
+                return Err(TryLockError::WouldBlock);
+            }
+            let timeout = match deadline {
+                None => None,
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(None);
+                    }
+                    Some(deadline - now)
+                }
+            };
+            if spin.spin() {
+                continue;
+            }
+            if state == LOCKED
+                && self
+                    .state
+                    .compare_exchange_weak(LOCKED, CONTENDED, Relaxed, Relaxed)
+                    .is_err()
+            {
+                continue;
+            }
+            futex::wait(&self.state, CONTENDED, timeout);
+            locked = CONTENDED;
+        }
+    }
+
+    fn unlock_impl(&self, new_state: u32) {
+        self.owner.store(0, Relaxed);
+        if self.state.swap(new_state, Release) == CONTENDED {
+            futex::wake_all(&self.state);
+        }
+    }
+}
+
+unsafe impl RawFusedConst for RawFusedFutex {
+    const UNLOCKED: Self = RawFusedFutex::from_state(UNLOCKED);
+    const READ: Self = RawFusedFutex::from_state(READ);
+    const POISON: Self = RawFusedFutex::from_state(POISON);
+}
+
+unsafe impl RawFused for RawFusedFutex {
+    type GuardMarker = ();
+
+    fn unlocked() -> Self {
+        Self::UNLOCKED
+    }
+
+    fn read() -> Self {
+        Self::READ
+    }
+
+    fn poisoned() -> Self {
+        Self::POISON
+    }
+
+    #[track_caller]
+    fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        if self.state.load(Acquire) == READ {
+            return Ok(RawFusedState::Read);
+        }
+        Ok(self.wait(true, None)?.unwrap())
+    }
+
+    #[track_caller]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        self.wait(true, Some(deadline))
+    }
+
+    #[track_caller]
+    fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        match self
+            .state
+            .compare_exchange(UNLOCKED, LOCKED, Acquire, Acquire)
+        {
+            Ok(_) => {
+                self.owner.store(ThreadId::current().0, Relaxed);
+                self.owner_location
+                    .store(Location::caller() as *const _ as *mut _, Relaxed);
+                Ok(Some(RawFusedState::Write))
+            }
+            Err(READ) => Ok(Some(RawFusedState::Read)),
+            Err(POISON) => Err(PoisonError::new(())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        if self.state.load(Acquire) == READ {
+            return Ok(RawFusedState::Read);
+        }
+        Ok(self.wait(false, None)?.unwrap())
+    }
+
+    fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+        match self.state.load(Acquire) {
+            READ => Ok(RawFusedState::Read),
+            POISON => Err(PoisonError::new(())),
+            _ => Ok(RawFusedState::Write),
+        }
+    }
+
+    #[track_caller]
+    fn try_write_poisoned(&self) -> bool {
+        if self
+            .state
+            .compare_exchange(POISON, LOCKED, Acquire, Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        self.owner.store(ThreadId::current().0, Relaxed);
+        self.owner_location
+            .store(Location::caller() as *const _ as *mut _, Relaxed);
+        true
+    }
+
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        if !matches!(self.state.load(Relaxed), LOCKED | CONTENDED) {
+            return None;
+        }
+        unsafe { self.owner_location.load(Relaxed).as_ref() }
+    }
+
+    fn owner_thread(&self) -> Option<OwnerThread> {
+        if !matches!(self.state.load(Relaxed), LOCKED | CONTENDED) {
+            return None;
+        }
+        Some(OwnerThread::new(ThreadId(self.owner.load(Relaxed))))
+    }
+
+    fn has_waiters(&self) -> Option<bool> {
+        Some(self.state.load(Relaxed) == CONTENDED)
+    }
+
+    fn is_write_locked(&self) -> Option<bool> {
+        Some(matches!(self.state.load(Relaxed), LOCKED | CONTENDED))
+    }
+
+    fn holds_write_lock(&self) -> Option<bool> {
+        // Only the current thread stores its id, and it clears it before unlocking.
+        Some(self.owner.load(Relaxed) == ThreadId::current().0)
+    }
+
+    unsafe fn unlock(&self) {
+        self.unlock_impl(UNLOCKED);
+    }
+
+    unsafe fn unlock_fuse(&self) {
+        self.unlock_impl(READ);
+    }
+
+    unsafe fn unlock_poison(&self) {
+        self.unlock_impl(POISON);
+    }
+
+    fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+        match *self.state.get_mut() {
+            READ => Ok(RawFusedState::Read),
+            POISON => Err(PoisonError::new(())),
+            _ => Ok(RawFusedState::Write),
+        }
+    }
+}
+
+impl Debug for RawFusedFutex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = match self.state.load(Relaxed) {
+            UNLOCKED => "Unlocked",
+            LOCKED | CONTENDED => "Locked",
+            READ => "Read",
+            _ => "Poison",
+        };
+        f.debug_struct("RawFusedFutex")
+            .field("state", &state)
+            .finish()
+    }
+}
+
+impl RefUnwindSafe for RawFusedFutex {}
+
+impl UnwindSafe for RawFusedFutex {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod futex {
+    use std::ptr::null;
+    use std::sync::atomic::AtomicU32;
+    use std::time::Duration;
+
+    // Sleep while the word equals `expected`, until woken, the timeout elapses, or spuriously.
+    pub(super) fn wait(word: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+        let timespec = timeout.map(|timeout| libc::timespec {
+            tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+            tv_nsec: timeout.subsec_nanos() as _,
+        });
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                word.as_ptr(),
+                libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                expected,
+                timespec
+                    .as_ref()
+                    .map_or(null(), |t| t as *const libc::timespec),
+            );
+        }
+    }
+
+    pub(super) fn wake_all(word: &AtomicU32) {
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                word.as_ptr(),
+                libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+                i32::MAX,
+            );
+        }
+    }
+}
+
+#[cfg(windows)]
+mod futex {
+    use std::ffi::c_void;
+    use std::sync::atomic::AtomicU32;
+    use std::time::Duration;
+
+    #[link(name = "synchronization")]
+    extern "system" {
+        fn WaitOnAddress(
+            address: *const c_void,
+            compare_address: *const c_void,
+            address_size: usize,
+            milliseconds: u32,
+        ) -> i32;
+        fn WakeByAddressAll(address: *const c_void);
+    }
+
+    const INFINITE: u32 = u32::MAX;
+
+    pub(super) fn wait(word: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+        let milliseconds = timeout.map_or(INFINITE, |timeout| {
+            timeout.as_millis().min(INFINITE as u128 - 1) as u32
+        });
+        unsafe {
+            WaitOnAddress(
+                word.as_ptr() as *const c_void,
+                &expected as *const u32 as *const c_void,
+                size_of::<u32>(),
+                milliseconds,
+            );
+        }
+    }
+
+    pub(super) fn wake_all(word: &AtomicU32) {
+        unsafe { WakeByAddressAll(word.as_ptr() as *const c_void) }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+mod futex {
+    use std::sync::atomic::AtomicU32;
+    use std::time::Duration;
+
+    // Without an address-based wait, yield instead; the caller rechecks the word.
+    pub(super) fn wait(_word: &AtomicU32, _expected: u32, _timeout: Option<Duration>) {
+        std::thread::yield_now();
+    }
+
+    pub(super) fn wake_all(_word: &AtomicU32) {}
+}
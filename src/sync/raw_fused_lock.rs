@@ -1,34 +1,112 @@
-use atomic::Atomic;
-use parking_lot::lock_api::GuardSend;
-use std::cell::UnsafeCell;
+use std::cell::{Cell, UnsafeCell};
 use std::fmt::{Debug, Formatter};
 use std::mem;
 use std::mem::MaybeUninit;
 use std::num::NonZeroUsize;
-use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::{PoisonError, TryLockError};
-use std::thread::{panicking, Thread};
-
-use crate::api::raw::{RawFused, RawFusedState};
-use parking_lot_core::{SpinWait, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
-// use crate::error::{LockError, PoisonError};
-use crate::sync::state::State;
-use crate::sync::thread_id::ThreadId;
+use std::panic::{catch_unwind, AssertUnwindSafe, Location, RefUnwindSafe, UnwindSafe};
+use std::ptr::{self, null_mut};
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release, SeqCst};
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError, TryLockError};
+use std::thread::{self, panicking, Thread};
+use std::time::Instant;
+
+use crate::api::raw::SpinWait;
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState};
+use crate::error::PoisonCause;
+use crate::observer::{self, Event};
+#[cfg(feature = "stats")]
+use crate::stats::Counters;
+use crate::sync::park;
+use crate::sync::state::{AtomicState, State};
+use crate::sync::thread_id::{OwnerThread, ThreadId};
+#[cfg(feature = "deadlock-detection")]
+use crate::sync::wait_for;
+use crate::watchdog::Watchdog;
+
+// Parks counted for the warmup, which stops when the foreground contends with it.
+#[cfg(feature = "warmup")]
+static PARK_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "warmup")]
+thread_local!(static THREAD_PARK_COUNT: Cell<usize> = const { Cell::new(0) });
+
+// The number of threads waiting in wait_read_checked, so that fusing only unparks them when
+// necessary.
+static FUSE_WAITERS: AtomicUsize = AtomicUsize::new(0);
+
+static PARKING_DISABLED: AtomicBool = AtomicBool::new(false);
+
+// Held while cloning or freeing the panic message of any lock, so that a reader never borrows a
+// message that a recovering thread frees meanwhile.
+static PANIC_MESSAGES: Mutex<()> = Mutex::new(());
+
+/// Make threads waiting for a [RawFusedLock] spin instead of parking, for environments where
+/// parking is unavailable. This also happens automatically, and is reported to the
+/// [observer](crate::observer), if parking fails.
+pub fn disable_parking() {
+    PARKING_DISABLED.store(true, Relaxed);
+}
+
+/// Whether waiting threads spin instead of parking. See [disable_parking].
+pub fn parking_disabled() -> bool {
+    PARKING_DISABLED.load(Relaxed)
+}
+
+/// The number of times any thread has parked (or spun, if parking is disabled) waiting for a
+/// [RawFusedLock].
+#[cfg(feature = "warmup")]
+pub fn park_count() -> usize {
+    PARK_COUNT.load(Relaxed)
+}
+
+/// The number of times the current thread has parked waiting for a [RawFusedLock].
+#[cfg(feature = "warmup")]
+pub(crate) fn thread_park_count() -> usize {
+    THREAD_PARK_COUNT.with(|x| x.get())
+}
 
 #[derive(Debug)]
 pub struct RawFusedLock {
-    pub state: Atomic<State>,
+    pub state: AtomicState,
+    // The call that obtained the write lock. Only meaningful while locked, or once poisoned.
+    #[cfg(feature = "owner-location")]
+    owner: AtomicPtr<Location<'static>>,
+    // The message of the panic that poisoned the lock, if recorded. Freed on recovery.
+    panic: AtomicPtr<String>,
+    #[cfg(feature = "stats")]
+    counters: Counters,
+}
+
+#[cold]
+pub(crate) fn parking_failed() {
+    if !PARKING_DISABLED.swap(true, Relaxed) {
+        observer::notify(&Event::ParkingUnavailable);
+    }
 }
 
 impl RawFusedLock {
+    // The first layer of every fast path. Separate so that benchmarks can measure the cost of
+    // the load with and without its fence.
+    #[inline(always)]
+    pub(crate) fn load_state(&self, ordering: Ordering) -> State {
+        self.state.load(ordering)
+    }
+
     #[cold]
-    fn lock_checked_slow(&self, mut state: State) -> Result<RawFusedState, TryLockError<()>> {
-        let tid = ThreadId::current();
+    #[track_caller]
+    fn lock_checked_slow(
+        &self,
+        mut state: State,
+        deadline: Option<Instant>,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        let tid = ThreadId::current_named();
+        #[cfg(feature = "record-replay")]
+        crate::replay::before_lock(self as *const _ as *const u8);
+        let mut watchdog = Watchdog::new();
         loop {
             if state.init() {
-                return Ok(RawFusedState::Read);
+                return Ok(Some(RawFusedState::Read));
             }
             if state.poison() {
                 return Err(PoisonError::new(()).into());
@@ -38,17 +116,23 @@ impl RawFusedLock {
                 if let Err(new_state) = self.state.compare_exchange_weak(
                     state,
                     State::new().with_thread_id(tid).with_locked(true),
-                    Relaxed,
+                    Acquire,
                     Acquire,
                 ) {
                     state = new_state;
                     continue;
                 }
-                return Ok(RawFusedState::Write);
+                self.set_owner();
+                #[cfg(feature = "record-replay")]
+                crate::replay::after_lock(self as *const _ as *const u8);
+                return Ok(Some(RawFusedState::Write));
             }
             if state.thread_id() == tid {
                 return Err(TryLockError::WouldBlock);
             }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(None);
+            }
             if !state.parked() {
                 if let Err(new_state) = self.state.compare_exchange_weak(
                     state,
@@ -61,79 +145,116 @@ impl RawFusedLock {
                 }
                 state = state.with_parked(true);
             }
-            let addr = self as *const _ as usize;
-            let validate = || {
-                let state = self.state.load(Ordering::Relaxed);
-                state.locked() && state.parked()
-            };
-            let before_sleep = || {};
-            let timed_out = |_, _| unreachable!();
-            unsafe {
-                parking_lot_core::park(
-                    addr,
-                    validate,
-                    before_sleep,
-                    timed_out,
-                    DEFAULT_PARK_TOKEN,
-                    None,
-                );
+            #[cfg(feature = "deadlock-detection")]
+            let _waiting = deadline
+                .is_none()
+                .then(|| wait_for::Waiting::new(self, tid))
+                .transpose()?;
+            self.park(watchdog.deadline(deadline));
+            watchdog.check(self);
+            state = self.state.load(Ordering::Acquire);
+        }
+    }
+
+    #[cold]
+    fn read_checked_slow(&self, mut state: State) -> Result<RawFusedState, TryLockError<()>> {
+        let tid = ThreadId::current();
+        let mut watchdog = Watchdog::new();
+        loop {
+            if state.init() {
+                return Ok(RawFusedState::Read);
+            }
+            if state.poison() {
+                return Err(PoisonError::new(()).into());
+            }
+            if !state.locked() {
+                return Ok(RawFusedState::Write);
+            }
+            if state.thread_id() == tid {
+                return Err(TryLockError::WouldBlock);
             }
+            if !state.parked() {
+                if let Err(new_state) = self.state.compare_exchange_weak(
+                    state,
+                    state.with_parked(true),
+                    Relaxed,
+                    Acquire,
+                ) {
+                    state = new_state;
+                    continue;
+                }
+            }
+            #[cfg(feature = "deadlock-detection")]
+            let _waiting = wait_for::Waiting::new(self, tid)?;
+            self.park(watchdog.deadline(None));
+            watchdog.check(self);
             state = self.state.load(Ordering::Acquire);
         }
     }
 
-    // #[cold]
-    // fn get_checked_slow(&self, mut state: State) -> Result<RawFusedState, TryLockError<()>> {
-    //     let tid = ThreadId::current();
-    //     loop {
-    //         if state.init() {
-    //             return Ok(RawFusedState::Read);
-    //         }
-    //         if state.poison() {
-    //             return Err(PoisonError::new(()).into());
-    //         }
-    //         if !state.locked() {
-    //             assert_eq!(state, State::new());
-    //             return Ok(RawFusedState::Write);
-    //         }
-    //         if state.thread_id() == tid {
-    //             return Err(TryLockError::WouldBlock);
-    //         }
-    //         if !state.parked() {
-    //             if let Err(new_state) = self.state.compare_exchange_weak(
-    //                 state, state.with_parked(true), Relaxed, Acquire) {
-    //                 state = new_state;
-    //                 continue;
-    //             }
-    //             state = state.with_parked(true);
-    //         }
-    //         let addr = self as *const _ as usize;
-    //         let validate = || {
-    //             let state = self.state.load(Ordering::Relaxed);
-    //             state.locked() && state.parked()
-    //         };
-    //         let before_sleep = || {};
-    //         let timed_out = |_, _| unreachable!();
-    //         unsafe {
-    //             parking_lot_core::park(
-    //                 addr,
-    //                 validate,
-    //                 before_sleep,
-    //                 timed_out,
-    //                 DEFAULT_PARK_TOKEN,
-    //                 None,
-    //             );
-    //         }
-    //         state = self.state.load(Ordering::Acquire);
-    //     }
-    // }
+    // Park until unlocked, assuming the parked bit is set.
+    fn park(&self, deadline: Option<Instant>) {
+        crate::instrument::parked(
+            self as *const Self as *const u8,
+            crate::watchdog::waiting_type_name(),
+        );
+        if parking_disabled() {
+            return self.spin(deadline);
+        }
+        let addr = ptr::from_ref(self).addr();
+        let validate = || {
+            let state = self.state.load(Ordering::Relaxed);
+            state.locked() && state.parked()
+        };
+        let before_sleep = || {
+            self.count_park();
+        };
+        let timed_out = |_, was_last_thread| {
+            if was_last_thread {
+                self.clear_parked();
+            }
+        };
+        let park = catch_unwind(AssertUnwindSafe(|| unsafe {
+            park::park(addr, validate, before_sleep, timed_out, deadline);
+        }));
+        if park.is_err() {
+            parking_failed();
+            self.spin(deadline);
+        }
+    }
+
+    fn count_park(&self) {
+        #[cfg(feature = "warmup")]
+        {
+            PARK_COUNT.fetch_add(1, Relaxed);
+            THREAD_PARK_COUNT.with(|x| x.set(x.get() + 1));
+        }
+        #[cfg(feature = "stats")]
+        self.counters.park();
+    }
+
+    // Spin with backoff until unlocked or the deadline passes.
+    fn spin(&self, deadline: Option<Instant>) {
+        self.count_park();
+        let mut spin = SpinWait::new();
+        while self.state.load(Relaxed).locked() {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return;
+            }
+            if !spin.spin() {
+                spin.reset();
+                thread::yield_now();
+            }
+        }
+    }
 
     #[cold]
+    #[track_caller]
     fn try_lock_checked_slow(
         &self,
         mut state: State,
+        tid: ThreadId,
     ) -> Result<Option<RawFusedState>, PoisonError<()>> {
-        let tid = ThreadId::current();
         loop {
             if state.init() {
                 return Ok(Some(RawFusedState::Read));
@@ -146,86 +267,407 @@ impl RawFusedLock {
                 if let Err(new_state) = self.state.compare_exchange_weak(
                     state,
                     State::new().with_thread_id(tid).with_locked(true),
-                    Relaxed,
+                    Acquire,
                     Acquire,
                 ) {
                     state = new_state;
                     continue;
                 }
+                self.set_owner();
+                #[cfg(feature = "record-replay")]
+                crate::replay::after_lock(self as *const _ as *const u8);
                 return Ok(Some(RawFusedState::Write));
             }
             return Ok(None);
         }
     }
 
+    // Like try_write_checked, but the write lock is held by a fresh ThreadId::detached instead of
+    // the current thread, so that a task can hold it across an await. Threads that request it
+    // meanwhile, including this one, wait for it instead of reporting a cycle.
+    #[cfg(feature = "async")]
+    #[track_caller]
+    pub(crate) fn try_write_detached(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        let state = self.load_state(Acquire);
+        if state.init() {
+            return Ok(Some(RawFusedState::Read));
+        }
+        self.try_lock_checked_slow(state, ThreadId::detached())
+    }
+
+    // Like try_lock_checked_slow, but with a plain load and store. Only sound while no other
+    // thread can access the lock, as when DynRawFused is unsynchronized.
+    #[cfg(feature = "dyn-backend")]
+    #[track_caller]
+    pub(crate) fn try_lock_unsynchronized(
+        &self,
+        tid: ThreadId,
+    ) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        let state = self.state.load(Relaxed);
+        if state.init() {
+            return Ok(Some(RawFusedState::Read));
+        }
+        if state.poison() {
+            return Err(PoisonError::new(()));
+        }
+        if state.locked() {
+            return Ok(None);
+        }
+        self.state
+            .store(State::new().with_thread_id(tid).with_locked(true), Relaxed);
+        self.set_owner();
+        Ok(Some(RawFusedState::Write))
+    }
+
+    // Like unlock_impl, under the same conditions as try_lock_unsynchronized. No other thread
+    // can be parked, but readers that have not revoked the fast path may observe the new state,
+    // so it is published with Release like a synchronized unlock.
+    #[cfg(feature = "dyn-backend")]
+    pub(crate) fn unlock_unsynchronized(&self, new_state: State) {
+        self.state.store(new_state, Release);
+    }
+
+    // Record the message of a panic while holding the write lock, before poisoning it.
+    pub(crate) fn set_panic(&self, message: String) {
+        self.replace_panic(Box::into_raw(Box::new(message)));
+    }
+
+    // Free the message of the panic that poisoned the lock, once it is no longer poisoned.
+    fn clear_panic(&self) {
+        self.replace_panic(null_mut());
+    }
+
+    fn replace_panic(&self, message: *mut String) {
+        let _messages = PANIC_MESSAGES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let old = self.panic.swap(message, Relaxed);
+        if !old.is_null() {
+            drop(unsafe { Box::from_raw(old) });
+        }
+    }
+
+    fn panic_message(&self) -> Option<String> {
+        let _messages = PANIC_MESSAGES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        unsafe { self.panic.load(Relaxed).as_ref() }.cloned()
+    }
+
+    #[track_caller]
+    fn set_owner(&self) {
+        #[cfg(feature = "owner-location")]
+        self.owner
+            .store(Location::caller() as *const _ as *mut _, Relaxed);
+    }
+
+    // The call that last obtained the write lock, if recorded.
+    fn owner(&self) -> Option<&'static Location<'static>> {
+        #[cfg(feature = "owner-location")]
+        return unsafe { self.owner.load(Relaxed).as_ref() };
+        #[cfg(not(feature = "owner-location"))]
+        None
+    }
+
+    // Wait for an unlocked cell to be fused or poisoned. Threads waiting for the cell to be
+    // unlocked park on the address of the lock, and threads waiting for it to be fused park on
+    // the next address.
+    fn park_until_fused(&self) {
+        FUSE_WAITERS.fetch_add(1, SeqCst);
+        fence(SeqCst);
+        if parking_disabled() {
+            let mut spin = SpinWait::new();
+            while !self.state.load(Relaxed).locked() && !self.fused_or_poisoned() {
+                if !spin.spin() {
+                    spin.reset();
+                    thread::yield_now();
+                }
+            }
+        } else {
+            let addr = self.fuse_waiters_addr();
+            let validate = || !self.state.load(Relaxed).locked() && !self.fused_or_poisoned();
+            let before_sleep = || self.count_park();
+            let park = catch_unwind(AssertUnwindSafe(|| unsafe {
+                park::park(addr, validate, before_sleep, |_, _| {}, None);
+            }));
+            if park.is_err() {
+                parking_failed();
+            }
+        }
+        FUSE_WAITERS.fetch_sub(1, Relaxed);
+    }
+
+    // The address on which threads and tasks wait for the lock to be fused.
+    pub(crate) fn fuse_waiters_addr(&self) -> usize {
+        ptr::from_ref(self).addr() + 1
+    }
+
+    /// Like [RawFused::wait_read_checked], but registers the task's waker instead of parking.
+    /// The write lock may be held by another task on the same thread, so this cannot detect
+    /// cycles.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_read(
+        &self,
+        cx: &mut std::task::Context,
+    ) -> std::task::Poll<Result<(), TryLockError<()>>> {
+        use std::task::Poll;
+        let mut registered = false;
+        loop {
+            let state = self.load_state(Acquire);
+            if state.init() {
+                return Poll::Ready(Ok(()));
+            }
+            if state.poison() {
+                return Poll::Ready(Err(PoisonError::new(()).into()));
+            }
+            if registered {
+                return Poll::Pending;
+            }
+            crate::future::register(self.fuse_waiters_addr(), cx.waker());
+            fence(SeqCst);
+            registered = true;
+        }
+    }
+
+    fn fused_or_poisoned(&self) -> bool {
+        let state = self.state.load(Relaxed);
+        state.init() || state.poison()
+    }
+
+    fn unpark_fuse_waiters(&self) {
+        fence(SeqCst);
+        #[cfg(feature = "async")]
+        crate::future::wake_all(self.fuse_waiters_addr());
+        if FUSE_WAITERS.load(Relaxed) != 0 {
+            let addr = self.fuse_waiters_addr();
+            let unpark = catch_unwind(|| unsafe {
+                park::unpark_all(addr);
+            });
+            if unpark.is_err() {
+                parking_failed();
+            }
+        }
+    }
+
+    fn clear_parked(&self) {
+        let mut state = self.state.load(Relaxed);
+        while state.parked() {
+            match self.state.compare_exchange_weak(
+                state,
+                state.with_parked(false),
+                Relaxed,
+                Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(new_state) => state = new_state,
+            }
+        }
+    }
+
     fn unlock_impl(&self, new_state: State) {
         let old_state = self.state.swap(new_state, Release);
         if old_state.parked() {
-            let addr = self as *const _ as usize;
-            unsafe {
-                parking_lot_core::unpark_all(addr, DEFAULT_UNPARK_TOKEN);
+            let addr = ptr::from_ref(self).addr();
+            let unpark = catch_unwind(|| unsafe {
+                park::unpark_all(addr);
+            });
+            if unpark.is_err() {
+                parking_failed();
             }
         }
     }
 }
 
-unsafe impl RawFused for RawFusedLock {
-    type GuardMarker = GuardSend;
+impl Drop for RawFusedLock {
+    fn drop(&mut self) {
+        let panic = *self.panic.get_mut();
+        if !panic.is_null() {
+            drop(unsafe { Box::from_raw(panic) });
+        }
+    }
+}
+
+unsafe impl RawFusedConst for RawFusedLock {
     const UNLOCKED: Self = RawFusedLock {
-        state: Atomic::new(State::new()),
+        state: AtomicState::new(State::new()),
+        #[cfg(feature = "owner-location")]
+        owner: AtomicPtr::new(null_mut()),
+        panic: AtomicPtr::new(null_mut()),
+        #[cfg(feature = "stats")]
+        counters: Counters::new(),
     };
     const READ: Self = RawFusedLock {
-        state: Atomic::new(State::new().with_init(true)),
+        state: AtomicState::new(State::new().with_init(true)),
+        #[cfg(feature = "owner-location")]
+        owner: AtomicPtr::new(null_mut()),
+        panic: AtomicPtr::new(null_mut()),
+        #[cfg(feature = "stats")]
+        counters: Counters::new(),
     };
     const POISON: Self = RawFusedLock {
-        state: Atomic::new(State::new().with_poison(true)),
+        state: AtomicState::new(State::new().with_poison(true)),
+        #[cfg(feature = "owner-location")]
+        owner: AtomicPtr::new(null_mut()),
+        panic: AtomicPtr::new(null_mut()),
+        #[cfg(feature = "stats")]
+        counters: Counters::new(),
     };
+}
 
+unsafe impl RawFused for RawFusedLock {
+    type GuardMarker = ();
+
+    fn unlocked() -> Self {
+        Self::UNLOCKED
+    }
+
+    fn read() -> Self {
+        Self::READ
+    }
+
+    fn poisoned() -> Self {
+        Self::POISON
+    }
+
+    #[track_caller]
     fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
-        let state = self.state.load(Ordering::Acquire);
+        let state = self.load_state(Acquire);
         if state.init() {
             return Ok(RawFusedState::Read);
         }
-        self.lock_checked_slow(state)
+        Ok(self.lock_checked_slow(state, None)?.unwrap())
     }
 
+    #[track_caller]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        let state = self.load_state(Acquire);
+        if state.init() {
+            return Ok(Some(RawFusedState::Read));
+        }
+        self.lock_checked_slow(state, Some(deadline))
+    }
+
+    #[track_caller]
     fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
-        let state = self.state.load(Ordering::Acquire);
+        let state = self.load_state(Acquire);
         if state.init() {
             return Ok(Some(RawFusedState::Read));
         }
-        self.try_lock_checked_slow(state)
+        self.try_lock_checked_slow(state, ThreadId::current_named())
     }
 
-    // fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
-    //     let state = self.state.load(Ordering::Acquire);
-    //     if state.init() {
-    //         return Ok(RawFusedState::Read);
-    //     }
-    //     self.get_checked_slow(state)
-    // }
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        let state = self.load_state(Acquire);
+        if state.init() {
+            return Ok(RawFusedState::Read);
+        }
+        self.read_checked_slow(state)
+    }
 
     fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
-        let state = self.state.load(Ordering::Acquire);
+        let state = self.load_state(Acquire);
         if state.init() {
             return Ok(RawFusedState::Read);
         }
         if state.poison() {
             return Err(PoisonError::new(()));
         }
-        return Ok(RawFusedState::Write);
+        Ok(RawFusedState::Write)
+    }
+
+    #[track_caller]
+    fn try_write_poisoned(&self) -> bool {
+        let tid = ThreadId::current_named();
+        let poison = State::new().with_poison(true);
+        let locked = State::new().with_thread_id(tid).with_locked(true);
+        while let Err(state) = self
+            .state
+            .compare_exchange_weak(poison, locked, Acquire, Relaxed)
+        {
+            if state != poison {
+                return false;
+            }
+        }
+        self.clear_panic();
+        self.set_owner();
+        true
+    }
+
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        let state = self.state.load(Relaxed);
+        if !state.locked() {
+            return None;
+        }
+        self.owner()
+    }
+
+    unsafe fn unlock_panic(&self, message: String) {
+        self.set_panic(message);
+        unsafe { self.unlock_poison() }
+    }
+
+    fn poison_cause(&self) -> Option<PoisonCause> {
+        // The owner of the write lock is not cleared when poisoning.
+        if !self.state.load(Acquire).poison() {
+            return None;
+        }
+        let location = self.owner()?;
+        Some(PoisonCause::new(location, self.panic_message()))
+    }
+
+    fn owner_thread(&self) -> Option<OwnerThread> {
+        let state = self.state.load(Relaxed);
+        state.locked().then(|| OwnerThread::new(state.thread_id()))
+    }
+
+    fn has_waiters(&self) -> Option<bool> {
+        Some(self.state.load(Relaxed).parked())
+    }
+
+    #[cfg(feature = "stats")]
+    fn counters(&self) -> Option<&Counters> {
+        Some(&self.counters)
+    }
+
+    fn is_write_locked(&self) -> Option<bool> {
+        Some(self.state.load(Relaxed).locked())
+    }
+
+    fn holds_write_lock(&self) -> Option<bool> {
+        let state = self.state.load(Relaxed);
+        Some(state.locked() && state.thread_id() == ThreadId::current())
     }
 
     unsafe fn unlock(&self) {
         self.unlock_impl(State::new());
+        // Async readers and tasks waiting for the write lock re-check the state on release.
+        #[cfg(feature = "async")]
+        {
+            fence(SeqCst);
+            crate::future::wake_all(self.fuse_waiters_addr());
+        }
     }
 
     unsafe fn unlock_fuse(&self) {
         self.unlock_impl(State::new().with_init(true));
+        self.unpark_fuse_waiters();
     }
 
     unsafe fn unlock_poison(&self) {
         self.unlock_impl(State::new().with_poison(true));
+        self.unpark_fuse_waiters();
+    }
+
+    fn wait_read_checked(&self) -> Result<(), TryLockError<()>> {
+        loop {
+            match self.read_checked()? {
+                RawFusedState::Read => return Ok(()),
+                RawFusedState::Write => self.park_until_fused(),
+            }
+        }
     }
 
     fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
@@ -236,7 +678,7 @@ unsafe impl RawFused for RawFusedLock {
         if state.poison() {
             return Err(PoisonError::new(()));
         }
-        return Ok(RawFusedState::Write);
+        Ok(RawFusedState::Write)
     }
 }
 
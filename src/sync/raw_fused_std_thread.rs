@@ -0,0 +1,308 @@
+use crate::api::raw::{RawFused, RawFusedConst, RawFusedState};
+use crate::sync::thread_id::{OwnerThread, ThreadId};
+use std::fmt::{Debug, Formatter};
+use std::panic::{Location, RefUnwindSafe, UnwindSafe};
+use std::ptr::{self, null_mut};
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+use std::sync::{Arc, PoisonError, TryLockError};
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+// The low bits of the state. While LOCKED, the remaining bits point to the most recently queued
+// Waiter. The state is a pointer so that the waiter keeps its provenance while tagged.
+const UNLOCKED: usize = 0b00;
+const LOCKED: usize = 0b01;
+const READ: usize = 0b10;
+const POISON: usize = 0b11;
+const STATE_MASK: usize = 0b11;
+
+// A thread waiting for the lock to be released. Queued waiters form a stack, each holding a
+// reference to the next. A waiter that times out drops its own reference and leaves the node
+// for the unlocking thread to release.
+struct Waiter {
+    thread: Thread,
+    signaled: AtomicBool,
+    next: *const Waiter,
+}
+
+// `next` is written before the waiter is published and only read by the unlocking thread.
+unsafe impl Send for Waiter {}
+
+unsafe impl Sync for Waiter {}
+
+/// A [RawFused] that parks with [std::thread::park] and a waiter list stored in the lock word,
+/// without `parking_lot_core`.
+pub struct RawFusedStdThread {
+    state: AtomicPtr<Waiter>,
+    owner: AtomicUsize,
+    owner_location: AtomicPtr<Location<'static>>,
+}
+
+impl RawFusedStdThread {
+    const fn from_state(state: usize) -> Self {
+        RawFusedStdThread {
+            state: AtomicPtr::new(ptr::without_provenance_mut(state)),
+            owner: AtomicUsize::new(0),
+            owner_location: AtomicPtr::new(null_mut()),
+        }
+    }
+
+    fn tag(state: *mut Waiter) -> usize {
+        state.addr() & STATE_MASK
+    }
+
+    fn node(state: *mut Waiter) -> *mut Waiter {
+        state.map_addr(|addr| addr & !STATE_MASK)
+    }
+
+    // Loop until the state is not LOCKED by another thread. If `lock` then obtain the lock when
+    // UNLOCKED.
+    #[track_caller]
+    fn wait(
+        &self,
+        lock: bool,
+        deadline: Option<Instant>,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        let tid = ThreadId::current().0;
+        let mut state = self.state.load(Acquire);
+        loop {
+            match Self::tag(state) {
+                READ => return Ok(Some(RawFusedState::Read)),
+                POISON => return Err(PoisonError::new(()).into()),
+                UNLOCKED if !lock => return Ok(Some(RawFusedState::Write)),
+                UNLOCKED => {
+                    if let Err(new_state) = self.state.compare_exchange_weak(
+                        state,
+                        ptr::without_provenance_mut(LOCKED),
+                        Acquire,
+                        Acquire,
+                    ) {
+                        state = new_state;
+                        continue;
+                    }
+                    self.owner.store(tid, Relaxed);
+                    self.owner_location
+                        .store(Location::caller() as *const _ as *mut _, Relaxed);
+                    return Ok(Some(RawFusedState::Write));
+                }
+                _ => {}
+            }
+            if self.owner.load(Relaxed) == tid {
+                return Err(TryLockError::WouldBlock);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(None);
+            }
+            let waiter = Arc::new(Waiter {
+                thread: thread::current(),
+                signaled: AtomicBool::new(false),
+                next: Self::node(state),
+            });
+            let node = Arc::into_raw(waiter.clone()).cast_mut();
+            if let Err(new_state) = self.state.compare_exchange_weak(
+                state,
+                node.map_addr(|addr| addr | LOCKED),
+                Release,
+                Acquire,
+            ) {
+                unsafe { drop(Arc::from_raw(node)) };
+                state = new_state;
+                continue;
+            }
+            while !waiter.signaled.load(Acquire) {
+                match deadline {
+                    None => thread::park(),
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            return Ok(None);
+                        }
+                        thread::park_timeout(deadline - now);
+                    }
+                }
+            }
+            state = self.state.load(Acquire);
+        }
+    }
+
+    fn unlock_impl(&self, new_state: usize) {
+        self.owner.store(0, Relaxed);
+        let old_state = self
+            .state
+            .swap(ptr::without_provenance_mut(new_state), AcqRel);
+        let mut node = Self::node(old_state).cast_const();
+        while !node.is_null() {
+            unsafe {
+                let waiter = Arc::from_raw(node);
+                node = waiter.next;
+                let thread = waiter.thread.clone();
+                waiter.signaled.store(true, Release);
+                thread.unpark();
+            }
+        }
+    }
+}
+
+unsafe impl RawFusedConst for RawFusedStdThread {
+    const UNLOCKED: Self = RawFusedStdThread::from_state(UNLOCKED);
+    const READ: Self = RawFusedStdThread::from_state(READ);
+    const POISON: Self = RawFusedStdThread::from_state(POISON);
+}
+
+unsafe impl RawFused for RawFusedStdThread {
+    type GuardMarker = ();
+
+    fn unlocked() -> Self {
+        Self::UNLOCKED
+    }
+
+    fn read() -> Self {
+        Self::READ
+    }
+
+    fn poisoned() -> Self {
+        Self::POISON
+    }
+
+    #[track_caller]
+    fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        if self.state.load(Acquire).addr() == READ {
+            return Ok(RawFusedState::Read);
+        }
+        Ok(self.wait(true, None)?.unwrap())
+    }
+
+    #[track_caller]
+    fn write_until_checked(
+        &self,
+        deadline: Instant,
+    ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+        self.wait(true, Some(deadline))
+    }
+
+    #[track_caller]
+    fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+        match self
+            .state
+            .compare_exchange(
+                ptr::without_provenance_mut(UNLOCKED),
+                ptr::without_provenance_mut(LOCKED),
+                Acquire,
+                Acquire,
+            )
+            .map_err(<*mut Waiter>::addr)
+        {
+            Ok(_) => {
+                self.owner.store(ThreadId::current().0, Relaxed);
+                self.owner_location
+                    .store(Location::caller() as *const _ as *mut _, Relaxed);
+                Ok(Some(RawFusedState::Write))
+            }
+            Err(READ) => Ok(Some(RawFusedState::Read)),
+            Err(POISON) => Err(PoisonError::new(())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+        if self.state.load(Acquire).addr() == READ {
+            return Ok(RawFusedState::Read);
+        }
+        Ok(self.wait(false, None)?.unwrap())
+    }
+
+    fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+        match self.state.load(Acquire).addr() {
+            READ => Ok(RawFusedState::Read),
+            POISON => Err(PoisonError::new(())),
+            _ => Ok(RawFusedState::Write),
+        }
+    }
+
+    #[track_caller]
+    fn try_write_poisoned(&self) -> bool {
+        if self
+            .state
+            .compare_exchange(
+                ptr::without_provenance_mut(POISON),
+                ptr::without_provenance_mut(LOCKED),
+                Acquire,
+                Relaxed,
+            )
+            .is_err()
+        {
+            return false;
+        }
+        self.owner.store(ThreadId::current().0, Relaxed);
+        self.owner_location
+            .store(Location::caller() as *const _ as *mut _, Relaxed);
+        true
+    }
+
+    fn owner_location(&self) -> Option<&'static Location<'static>> {
+        if Self::tag(self.state.load(Relaxed)) != LOCKED {
+            return None;
+        }
+        unsafe { self.owner_location.load(Relaxed).as_ref() }
+    }
+
+    fn owner_thread(&self) -> Option<OwnerThread> {
+        if Self::tag(self.state.load(Relaxed)) != LOCKED {
+            return None;
+        }
+        Some(OwnerThread::new(ThreadId(self.owner.load(Relaxed))))
+    }
+
+    fn has_waiters(&self) -> Option<bool> {
+        let state = self.state.load(Relaxed);
+        Some(Self::tag(state) == LOCKED && !Self::node(state).is_null())
+    }
+
+    fn is_write_locked(&self) -> Option<bool> {
+        Some(Self::tag(self.state.load(Relaxed)) == LOCKED)
+    }
+
+    fn holds_write_lock(&self) -> Option<bool> {
+        // Only the current thread stores its id, and it clears it before unlocking.
+        Some(self.owner.load(Relaxed) == ThreadId::current().0)
+    }
+
+    unsafe fn unlock(&self) {
+        self.unlock_impl(UNLOCKED);
+    }
+
+    unsafe fn unlock_fuse(&self) {
+        self.unlock_impl(READ);
+    }
+
+    unsafe fn unlock_poison(&self) {
+        self.unlock_impl(POISON);
+    }
+
+    fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+        match self.state.get_mut().addr() {
+            READ => Ok(RawFusedState::Read),
+            POISON => Err(PoisonError::new(())),
+            _ => Ok(RawFusedState::Write),
+        }
+    }
+}
+
+impl Debug for RawFusedStdThread {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = match Self::tag(self.state.load(Relaxed)) {
+            UNLOCKED => "Unlocked",
+            LOCKED => "Locked",
+            READ => "Read",
+            _ => "Poison",
+        };
+        f.debug_struct("RawFusedStdThread")
+            .field("state", &state)
+            .finish()
+    }
+}
+
+impl RefUnwindSafe for RawFusedStdThread {}
+
+impl UnwindSafe for RawFusedStdThread {}
@@ -1,7 +1,10 @@
 use crate::sync::thread_id::ThreadId;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread::Thread;
 
 #[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+#[repr(transparent)]
 pub struct State(usize);
 
 const INIT_BIT: usize = 0b0001;
@@ -10,6 +13,10 @@ const PARKED_BIT: usize = 0b0100;
 const POISON_BIT: usize = 0b1000;
 const THREAD_ID_MASK: usize = !(0b1111);
 
+// The flags must fit in the bits that a thread id leaves clear.
+const _: () = assert!(!THREAD_ID_MASK < ThreadId::ALIGN);
+const _: () = assert!(size_of::<State>() == size_of::<AtomicUsize>());
+
 impl State {
     pub const fn new() -> Self {
         State(0)
@@ -67,3 +74,51 @@ impl State {
         State((self.0 & !THREAD_ID_MASK) | id.0)
     }
 }
+
+/// A [State] stored in an [AtomicUsize], so that every operation is lock-free on every target
+/// with pointer-sized atomics.
+#[repr(transparent)]
+pub struct AtomicState(AtomicUsize);
+
+impl AtomicState {
+    pub const fn new(state: State) -> Self {
+        AtomicState(AtomicUsize::new(state.0))
+    }
+
+    #[inline(always)]
+    pub fn load(&self, ordering: Ordering) -> State {
+        State(self.0.load(ordering))
+    }
+
+    pub fn store(&self, state: State, ordering: Ordering) {
+        self.0.store(state.0, ordering)
+    }
+
+    pub fn swap(&self, state: State, ordering: Ordering) -> State {
+        State(self.0.swap(state.0, ordering))
+    }
+
+    pub fn compare_exchange_weak(
+        &self,
+        current: State,
+        new: State,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<State, State> {
+        self.0
+            .compare_exchange_weak(current.0, new.0, success, failure)
+            .map(State)
+            .map_err(State)
+    }
+
+    pub fn get_mut(&mut self) -> &mut State {
+        // State is a transparent wrapper around usize.
+        unsafe { &mut *(self.0.get_mut() as *mut usize as *mut State) }
+    }
+}
+
+impl Debug for AtomicState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.load(Ordering::Relaxed).fmt(f)
+    }
+}
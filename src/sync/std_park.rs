@@ -0,0 +1,21 @@
+//! Implementations whose waiters park with [std::thread::park], using only the standard library.
+//!
+//! [RawFusedStdThread] keeps its waiters in a list referenced by the lock word instead of
+//! parking_lot_core's global table, so these types behave the same with or without the
+//! `parking-lot` feature. They detect reentrant initialization like the rest of [sync](super).
+//! ```
+//! use safe_once::sync::std_park::{LazyLock, OnceLock};
+//! static LAZY: LazyLock<u32> = LazyLock::new(|| 42);
+//! static ONCE: OnceLock<&str> = OnceLock::new();
+//! assert_eq!(*LAZY, 42);
+//! assert_eq!(*ONCE.get_or_init(|| "hello"), "hello");
+//! ```
+
+use crate::api::fused::Fused;
+use crate::api::lazy::Lazy;
+use crate::api::once::Once;
+use crate::sync::RawFusedStdThread;
+
+pub type OnceLock<T> = Once<RawFusedStdThread, T>;
+pub type LazyLock<T, F = fn() -> T> = Lazy<RawFusedStdThread, T, F>;
+pub type FusedLock<T> = Fused<RawFusedStdThread, T>;
@@ -1,7 +1,10 @@
+use crate::api::fused::FusedEntry;
 use crate::api::once::OnceEntry;
-use crate::sync::OnceLock;
+use crate::api::try_deref::TryDeref;
+use crate::error::LockError;
+use crate::sync::{FusedLock, LazyLock, OnceLock};
 use parking_lot::{Mutex, RwLock};
-use std::panic::catch_unwind;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::sync::{Arc, Barrier, PoisonError, TryLockError};
 use std::thread;
 use std::time::Duration;
@@ -43,53 +46,197 @@ fn test_relock() {
     assert_eq!(**once.try_get().unwrap(), 5);
 }
 
+#[test]
+fn test_relock_threads() {
+    let fused = FusedLock::new(vec![]);
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for i in 0..1000 {
+                    match fused.write() {
+                        FusedEntry::Read(_) => unreachable!(),
+                        FusedEntry::Write(mut vec) => vec.push(i),
+                    }
+                }
+            });
+        }
+    });
+    assert_eq!(fused.write().or_fuse(|_| {}).len(), 4000);
+}
+
 #[test]
 fn test_recurrent() {
     let once = OnceLock::<Box<isize>>::new();
     once.get_or_init(|| {
         match once.get_or_init_checked(|| unreachable!()).unwrap_err() {
-            TryLockError::WouldBlock => {}
+            LockError::Cycle { .. } => {}
             _ => panic!(),
         };
         Box::new(5)
     });
 }
 
+#[test]
+fn test_poisoned_into_inner() {
+    let once = OnceLock::<Box<isize>>::new();
+    assert!(catch_unwind(|| {
+        once.get_or_init(|| panic!());
+    })
+    .is_err());
+    assert_eq!(once.into_inner(), None);
+}
+
 #[test]
 fn test_panic() {
+    // The cause of a poisoned cell, which is only recorded with owner-location.
+    fn poison_cause<T>(result: Result<T, LockError>) -> Option<crate::error::PoisonCause> {
+        match result {
+            Err(LockError::Poisoned { cause }) => {
+                assert_eq!(cause.is_some(), cfg!(feature = "owner-location"));
+                cause
+            }
+            _ => panic!(),
+        }
+    }
+
     let once = OnceLock::<Box<isize>>::new();
     assert!(catch_unwind(|| {
         once.get_or_init(|| {
-            panic!();
+            panic!("boom");
         });
     })
     .is_err());
-    let x: PoisonError<()> = once.try_get_checked().unwrap_err();
-}
-
-// #[test]
-// fn test_get_blocking() {
-//     let once = Arc::new(OnceLock::<usize>::new());
-//     let barrier = Arc::new(Barrier::new(2));
-//     let t = thread::spawn({
-//         let once = once.clone();
-//         let barrier = barrier.clone();
-//         move || {
-//             once.get_or_init(|| {
-//                 barrier.wait();
-//                 thread::sleep(Duration::from_millis(100));
-//                 42
-//             });
-//         }
-//     });
-//     barrier.wait();
-//     assert_eq!(once.try_get(), Some(&42));
-// }
+    if let Some(cause) = poison_cause(once.try_get_checked()) {
+        assert_eq!(cause.location().file(), file!());
+        assert_eq!(cause.message(), Some("boom"));
+    }
+    let message = *catch_unwind(|| once.get_or_init(|| Box::new(1)).clone())
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap();
+    assert!(message.starts_with("poisoned lock: "), "{}", message);
+    assert_eq!(
+        message.contains("panicked: boom"),
+        cfg!(feature = "owner-location"),
+        "{}",
+        message
+    );
+    // The message moves with the cell.
+    let moved = Box::new(once);
+    if let Some(cause) = poison_cause(moved.try_get_checked()) {
+        assert_eq!(cause.message(), Some("boom"));
+    }
+
+    let fused = FusedLock::new(1);
+    assert!(catch_unwind(|| {
+        let _entry = fused.write();
+        panic!("boom");
+    })
+    .is_err());
+    if let Some(cause) = poison_cause(fused.try_read_checked()) {
+        assert_eq!(cause.message(), None);
+    }
+
+    // Recovering frees the message, so a later poisoning does not report it.
+    let once = OnceLock::<usize>::new();
+    assert!(catch_unwind(|| once.get_or_init(|| panic!("first"))).is_err());
+    assert!(catch_unwind(|| once.try_recover(|| panic!())).is_err());
+    if let Some(cause) = poison_cause(once.try_get_checked()) {
+        assert_eq!(cause.message(), None);
+    }
+}
+
+#[test]
+fn test_poison_recovery() {
+    let mut once = OnceLock::<usize>::new();
+    assert!(catch_unwind(|| once.get_or_init(|| panic!())).is_err());
+    once.clear_poison();
+    assert_eq!(once.try_get(), None);
+    assert_eq!(once.try_recover(|| unreachable!()), None);
+    assert!(catch_unwind(|| once.get_or_init(|| panic!())).is_err());
+    assert_eq!(once.get_or_reinit(|| 1), &1);
+    assert_eq!(once.try_recover(|| unreachable!()), None);
+
+    let once = OnceLock::<usize>::new();
+    assert!(catch_unwind(|| once.get_or_init(|| panic!())).is_err());
+    assert!(catch_unwind(|| once.try_recover(|| panic!())).is_err());
+    let once = &once;
+    thread::scope(|s| {
+        let recovered: Vec<_> = (0..4)
+            .map(|i| s.spawn(move || once.try_recover(|| i).is_some()))
+            .collect();
+        let recovered = recovered.into_iter().map(|t| t.join().unwrap());
+        assert_eq!(recovered.filter(|&x| x).count(), 1);
+    });
+    assert!(once.try_get().is_some());
+
+    let mut lazy = LazyLock::<usize>::new(|| panic!());
+    assert!(catch_unwind(|| *lazy).is_err());
+    assert_eq!(lazy.try_recover(|| 2), Some(&2));
+    assert_eq!(*lazy, 2);
+    lazy.clear_poison(|| unreachable!());
+    let mut lazy = LazyLock::<usize>::new(|| panic!());
+    assert!(catch_unwind(|| *lazy).is_err());
+    lazy.clear_poison(|| 3);
+    assert_eq!(*lazy, 3);
+
+    let mut fused = FusedLock::new(vec![1]);
+    assert!(catch_unwind(|| {
+        let mut entry = fused.write();
+        let FusedEntry::Write(guard) = &mut entry else {
+            unreachable!()
+        };
+        guard.push(2);
+        panic!();
+    })
+    .is_err());
+    assert_eq!(fused.try_recover(|x| x.push(3)), Some(&vec![1, 2, 3]));
+    let mut fused = FusedLock::new(0);
+    assert!(catch_unwind(|| {
+        let _entry = fused.write();
+        panic!();
+    })
+    .is_err());
+    fused.clear_poison();
+    assert_eq!(fused.read_or_fuse(|x| *x = 1), &1);
+}
+
+#[test]
+fn test_get_blocking() {
+    let once = Arc::new(OnceLock::<usize>::new());
+    let barrier = Arc::new(Barrier::new(2));
+    let t = thread::spawn({
+        let once = once.clone();
+        let barrier = barrier.clone();
+        move || {
+            once.get_or_init(|| {
+                barrier.wait();
+                thread::sleep(Duration::from_millis(100));
+                42
+            });
+        }
+    });
+    barrier.wait();
+    assert_eq!(once.get_blocking(), Some(&42));
+    t.join().unwrap();
+    assert!(OnceLock::<usize>::new().get_blocking().is_none());
+    once.get_or_init(|| {
+        let fresh = OnceLock::<usize>::new();
+        fresh.get_or_init(|| {
+            assert!(matches!(
+                fresh.get_blocking_checked(),
+                Err(LockError::Cycle { .. })
+            ));
+            0
+        });
+        0
+    });
+}
 
 #[test]
 fn test_stress() {
     for threads in 1..=8 {
-        let onces = Arc::new(vec![OnceLock::new(); 1000]);
+        let onces = Arc::new(vec![OnceLock::new(); if cfg!(miri) { 10 } else { 1000 }]);
         let barrier = Arc::new(Barrier::new(threads));
         let wins: usize = (0..threads)
             .map(|_| {
@@ -102,7 +249,6 @@ fn test_stress() {
                         barrier.wait();
                         once.get_or_init(|| {
                             wins += 1;
-                            ()
                         });
                     }
                     wins
@@ -116,3 +262,2134 @@ fn test_stress() {
         assert_eq!(wins, onces.len());
     }
 }
+
+#[test]
+fn test_unfuse() {
+    let mut fused = FusedLock::new(vec![1]);
+    fused.read_or_fuse(|v| v.push(2));
+    assert!(fused
+        .try_write()
+        .is_some_and(|e| matches!(e, FusedEntry::Read(_))));
+    fused.unfuse().unwrap();
+    assert_eq!(fused.try_read(), None);
+    fused.read_or_fuse(|v| v.push(3));
+    assert_eq!(fused.try_read().unwrap(), &[1, 2, 3]);
+}
+
+#[test]
+fn test_try_deref() {
+    static A: LazyLock<String> = LazyLock::new(|| B.try_deref().unwrap_err().to_string());
+    static B: LazyLock<String> = LazyLock::new(|| A.forced().clone());
+    let message = B.try_deref().unwrap();
+    assert!(
+        message.starts_with("deadlock: waiting for the write lock would never finish, held by ")
+    );
+    assert_eq!(
+        message.contains(" since "),
+        cfg!(feature = "owner-location")
+    );
+    let lazy = LazyLock::<Box<usize>>::new(|| panic!());
+    assert!(catch_unwind(|| lazy.forced()).is_err());
+    assert!(matches!(lazy.try_forced(), Err(LockError::Poisoned { .. })));
+}
+
+#[test]
+fn test_replace() {
+    let mut fused = FusedLock::new(1);
+    assert_eq!(fused.replace(2), 1);
+    assert_eq!(fused.try_read(), None);
+    fused.read_or_fuse(|x| *x += 1);
+    assert_eq!(fused.replace(4), 3);
+    assert_eq!(fused.try_read(), Some(&4));
+    let mut fused = FusedLock::poisoned(5);
+    fused.set(6);
+    assert_eq!(*fused.read_or_fuse(|_| {}), 6);
+}
+
+#[cfg(feature = "distributed-slice")]
+#[test]
+fn test_registry() {
+    static LAZY: LazyLock<usize> = LazyLock::new(|| 42);
+    static ONCE: OnceLock<usize> = OnceLock::new();
+    crate::register!(LAZY);
+    crate::register!(ONCE);
+    let lazy = crate::registry::find("LAZY").unwrap();
+    let once = crate::registry::find("ONCE").unwrap();
+    assert!(!lazy.is_initialized());
+    lazy.force();
+    assert!(lazy.is_initialized());
+    once.force();
+    assert!(!once.is_initialized());
+    ONCE.get_or_init(|| 1);
+    assert!(once.is_initialized());
+}
+
+#[test]
+fn test_init_in_place() {
+    let once = OnceLock::<[usize; 1024]>::new();
+    match once.lock() {
+        OnceEntry::Occupied(_) => unreachable!(),
+        OnceEntry::Vacant(x) => unsafe {
+            x.init_in_place(|slot| {
+                let ptr = slot.as_mut_ptr() as *mut usize;
+                for i in 0..1024 {
+                    ptr.add(i).write(i);
+                }
+            });
+        },
+    }
+    assert_eq!(once.try_get().unwrap()[1023], 1023);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_invariant() {
+    let fused = FusedLock::new(vec![3, 1, 2]).with_invariant("sorted", |v| v.is_sorted());
+    let result = catch_unwind(|| {
+        fused.read_or_fuse(|v| v.push(4));
+    });
+    assert_eq!(
+        result.unwrap_err().downcast_ref::<String>().unwrap(),
+        "invariant of sorted violated"
+    );
+    assert!(fused.try_read_checked().is_err());
+    let fused = FusedLock::new(vec![3, 1, 2]).with_invariant("sorted", |v| v.is_sorted());
+    assert_eq!(fused.read_or_fuse(|v| v.sort()), &[1, 2, 3]);
+
+    let mut fused =
+        FusedLock::new_read(vec![1, 2]).with_read_invariant("sorted", |v| v.is_sorted());
+    assert_eq!(fused.try_read(), Some(&vec![1, 2]));
+    fused.write_mut().reverse();
+    assert!(catch_unwind(AssertUnwindSafe(|| fused.try_read())).is_err());
+}
+
+#[cfg(feature = "content-hash")]
+#[test]
+fn test_content_hash() {
+    let a = FusedLock::new(vec![1, 2, 3]);
+    let b = FusedLock::new(vec![3, 2, 1]);
+    assert_eq!(a.content_hash(), None);
+    let (_, hash) = match a.write() {
+        FusedEntry::Read(_) => unreachable!(),
+        FusedEntry::Write(guard) => guard.fuse_hashed(),
+    };
+    assert_eq!(a.content_hash(), Some(hash));
+    match b.write() {
+        FusedEntry::Read(_) => unreachable!(),
+        FusedEntry::Write(mut guard) => {
+            guard.reverse();
+            guard.fuse_hashed();
+        }
+    }
+    assert_eq!(b.content_hash(), Some(hash));
+    b.write().or_fuse(|_| unreachable!());
+    assert_eq!(b.content_hash(), Some(hash));
+}
+
+#[test]
+fn test_or_try_init() {
+    let once = OnceLock::<usize>::new();
+    assert_eq!(once.lock().or_try_init(|| Err("failed")), Err("failed"));
+    assert_eq!(once.try_get(), None);
+    assert_eq!(once.lock().or_try_init(|| Ok::<_, ()>(5)), Ok(&5));
+    assert_eq!(once.lock().or_try_init(|| Err(())), Ok(&5));
+}
+
+#[cfg(feature = "record-replay")]
+#[test]
+fn test_record_replay() {
+    use crate::replay;
+    static ORDER: Mutex<Vec<&str>> = Mutex::new(vec![]);
+    static REPLAY_A: LazyLock<()> = LazyLock::new(|| ORDER.lock().push("A"));
+    static REPLAY_B: LazyLock<()> = LazyLock::new(|| ORDER.lock().push("B"));
+    static REPLAY_C: LazyLock<()> = LazyLock::new(|| ORDER.lock().push("C"));
+    static REPLAY_D: LazyLock<()> = LazyLock::new(|| ORDER.lock().push("D"));
+    crate::register!(REPLAY_A);
+    crate::register!(REPLAY_B);
+    crate::register!(REPLAY_C);
+    crate::register!(REPLAY_D);
+    replay::record();
+    thread::spawn(|| *REPLAY_B).join().unwrap();
+    thread::spawn(|| *REPLAY_A).join().unwrap();
+    let log = replay::stop();
+    assert_eq!(
+        log.0.iter().map(|e| e.cell.as_str()).collect::<Vec<_>>(),
+        ["REPLAY_B", "REPLAY_A"]
+    );
+    let log: replay::Log = log
+        .to_string()
+        .replace("REPLAY_A", "REPLAY_C")
+        .replace("REPLAY_B", "REPLAY_D")
+        .parse()
+        .unwrap();
+    replay::replay(log);
+    let c = thread::spawn(|| *REPLAY_C);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(*ORDER.lock(), ["B", "A"]);
+    let d = thread::spawn(|| *REPLAY_D);
+    c.join().unwrap();
+    d.join().unwrap();
+    replay::stop();
+    assert_eq!(ORDER.lock().len(), 4);
+}
+
+#[test]
+#[allow(clippy::mutable_key_type)]
+fn test_compare() {
+    use std::collections::HashSet;
+    let empty = OnceLock::<usize>::new();
+    let one = OnceLock::from(1);
+    assert_eq!(empty, OnceLock::new());
+    assert_ne!(empty, one);
+    assert!(empty < one);
+    assert!(one < OnceLock::from(2));
+    let set: HashSet<_> = [OnceLock::new(), OnceLock::from(1), OnceLock::from(1)].into();
+    assert_eq!(set.len(), 2);
+    let lazy = LazyLock::<usize>::new(|| 1);
+    assert!(lazy == LazyLock::new(|| 2));
+    assert_eq!(*lazy, 1);
+    assert!(lazy != LazyLock::new(|| 1));
+    assert!(lazy > LazyLock::new(|| 2));
+    let fused = FusedLock::new(1);
+    assert_eq!(fused, FusedLock::new(2));
+    fused.read_or_fuse(|_| {});
+    assert_eq!(fused, FusedLock::new_read(1));
+}
+
+#[test]
+fn test_or_fuse_validated() {
+    let fused = FusedLock::new(vec![1]);
+    let result = fused.write().or_fuse_validated(
+        |v| v.push(0),
+        |v| {
+            if v.is_sorted() {
+                Ok(())
+            } else {
+                Err("unsorted")
+            }
+        },
+    );
+    assert_eq!(result, Err("unsorted"));
+    assert_eq!(fused.try_read(), None);
+    let result = fused
+        .write()
+        .or_fuse_validated(|v| v.push(2), |_| Ok::<_, ()>(()));
+    assert_eq!(result.unwrap(), &[1, 2]);
+}
+
+#[test]
+fn test_clone_lazy() {
+    let lazy = LazyLock::<Vec<usize>>::new(|| vec![1]);
+    let copy = lazy.clone();
+    assert_eq!(copy.try_get(), None);
+    assert_eq!(*copy, [1]);
+    assert_eq!(lazy.try_get(), None);
+    assert_eq!(*lazy, [1]);
+    assert_eq!(lazy.clone().try_get(), Some(&vec![1]));
+
+    struct PanicOnClone;
+    impl Clone for PanicOnClone {
+        fn clone(&self) -> Self {
+            panic!("clone")
+        }
+    }
+    let init = PanicOnClone;
+    let lazy = LazyLock::new(move || {
+        let _ = &init;
+        1
+    });
+    assert!(catch_unwind(AssertUnwindSafe(|| lazy.clone())).is_err());
+    assert_eq!(*lazy, 1);
+}
+
+#[test]
+fn test_const_init() {
+    static ONCE: OnceLock<&str> = OnceLock::new_init("once");
+    static LAZY: LazyLock<&str> = LazyLock::new_value("lazy");
+    static FUSED: FusedLock<&str> = FusedLock::new_read("fused");
+    assert_eq!(ONCE.try_get(), Some(&"once"));
+    assert_eq!(LAZY.try_get(), Some(&"lazy"));
+    assert_eq!(*LAZY, "lazy");
+    assert_eq!(FUSED.try_read(), Some(&"fused"));
+    assert_eq!(*LazyLock::<usize>::from(5), 5);
+}
+
+#[test]
+fn test_get_unchecked() {
+    let once = OnceLock::new();
+    once.get_or_init(|| 5);
+    assert_eq!(unsafe { *once.get_unchecked() }, 5);
+    #[cfg(debug_assertions)]
+    assert!(catch_unwind(|| unsafe { *OnceLock::<usize>::new().get_unchecked() }).is_err());
+}
+
+#[test]
+fn test_aligned() {
+    use crate::api::aligned::Aligned;
+    use crate::sync::{FusedLockAligned, OnceLockAligned};
+    let once = OnceLockAligned::<u8, 256>::new();
+    assert_eq!(**once.get_or_init(|| Aligned::new(1)), 1);
+    assert_eq!(once.try_get().unwrap() as *const _ as usize % 256, 0);
+    let fused = FusedLockAligned::<[u8; 3], 32>::new(Aligned::new([0; 3]));
+    let value = fused.read_or_fuse(|x| x[0] = 1);
+    assert_eq!(value as *const _ as usize % 32, 0);
+    assert_eq!(**value, [1, 0, 0]);
+}
+
+#[test]
+fn test_try_write_for() {
+    let fused = Arc::new(FusedLock::new(0));
+    let barrier = Arc::new(Barrier::new(2));
+    let t = thread::spawn({
+        let fused = fused.clone();
+        let barrier = barrier.clone();
+        move || {
+            let FusedEntry::Write(mut guard) = fused.write() else {
+                unreachable!()
+            };
+            barrier.wait();
+            barrier.wait();
+            *guard = 1;
+            guard.fuse();
+        }
+    });
+    barrier.wait();
+    assert!(fused.try_write_for(Duration::from_millis(10)).is_none());
+    barrier.wait();
+    match fused.try_write_for(Duration::MAX) {
+        Some(FusedEntry::Read(x)) => assert_eq!(*x, 1),
+        _ => unreachable!(),
+    }
+    t.join().unwrap();
+}
+
+#[test]
+fn test_keyed_lazy_cache() {
+    use crate::cache::KeyedLazyCache;
+    let cache = KeyedLazyCache::<usize, usize>::with_capacity(2);
+    let builds = Mutex::new(0);
+    let build = |key: &usize| {
+        *builds.lock() += 1;
+        *key * 2
+    };
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_eq!(*cache.get_or_init(1, build), 2));
+        }
+    });
+    assert_eq!(*builds.lock(), 1);
+    assert_eq!(cache.get_or_try_init(2, |_| Err(())), Err(()));
+    assert_eq!(cache.get_or_try_init(3, |_| Err(())), Err(()));
+    // 2 is the least recently used unbuilt entry; 1 is built and permanent.
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&1), Some(&2));
+    assert_eq!(cache.get(&2), None);
+    assert_eq!(*cache.get_or_init(4, build), 8);
+    assert_eq!(*cache.get_or_init(5, build), 10);
+    assert_eq!(cache.len(), 3);
+}
+
+#[test]
+fn test_once_indirect() {
+    use crate::api::indirect::prefer_indirect;
+    use crate::sync::OnceLockIndirect;
+    const _: () = assert!(prefer_indirect::<[u8; 4096]>() && !prefer_indirect::<u64>());
+    let once = OnceLockIndirect::<[u8; 4096]>::new();
+    assert_eq!(
+        std::mem::size_of_val(&once),
+        std::mem::size_of::<OnceLock<Box<u8>>>()
+    );
+    assert_eq!(once.try_get(), None);
+    assert_eq!(once.get_or_init(|| [1; 4096])[4095], 1);
+    assert_eq!(once.get_or_init(|| unreachable!())[0], 1);
+    assert_eq!(once.into_inner().unwrap()[17], 1);
+}
+
+#[cfg(feature = "owner-location")]
+#[test]
+fn test_deadlock_locations() {
+    fn check(message: Box<dyn std::any::Any + Send>, owner: u32, caller: u32) {
+        let message = message.downcast::<String>().unwrap();
+        for line in [owner, caller] {
+            let location = format!("{}:{}:", file!(), line);
+            assert!(message.contains(&location), "{} in {}", location, message);
+        }
+    }
+    let once = OnceLock::<usize>::new();
+    let line = line!() + 1;
+    let result = catch_unwind(|| once.get_or_init(|| *once.get_or_init(|| 1)));
+    check(result.unwrap_err(), line, line);
+    let fused = crate::cell::FusedCell::new(0);
+    let result = catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _guard = fused.write();
+        fused.write();
+    }));
+    let line = line!() - 3;
+    check(result.unwrap_err(), line, line + 1);
+}
+
+#[test]
+fn test_cycle_message() {
+    static A: LazyLock<String> = LazyLock::new(|| B.to_string());
+    static B: LazyLock<u32> = LazyLock::new(|| A.len() as u32);
+    #[cfg(feature = "distributed-slice")]
+    crate::register!(A);
+    let result = catch_unwind(|| A.len());
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    let cycle = message.split_once("\ncycle:\n").unwrap().1;
+    let lines = cycle.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3, "{}", message);
+    assert!(lines[0].contains(": alloc::string::String initializing since "));
+    assert!(lines[1].contains(": u32 initializing since "));
+    assert!(lines[2].contains(": alloc::string::String requested again at "));
+    assert_eq!(
+        lines[0].split(':').next(),
+        lines[2].split(':').next(),
+        "{}",
+        message
+    );
+    #[cfg(feature = "distributed-slice")]
+    assert!(lines[0].starts_with("  A: "), "{}", message);
+}
+
+#[test]
+fn test_owner_thread() {
+    let once = OnceLock::<usize>::new();
+    let barrier = Barrier::new(2);
+    assert_eq!(once.owner_thread(), None);
+    thread::scope(|s| {
+        thread::Builder::new()
+            .name("config-loader".to_string())
+            .spawn_scoped(s, || {
+                once.get_or_init(|| {
+                    barrier.wait();
+                    barrier.wait();
+                    1
+                })
+            })
+            .unwrap();
+        barrier.wait();
+        let owner = once.owner_thread().unwrap();
+        assert_eq!(owner.name(), Some("config-loader"));
+        assert_eq!(owner.to_string(), "thread 'config-loader'");
+        barrier.wait();
+    });
+    assert_eq!(once.owner_thread(), None);
+    let fused = FusedLock::new(0);
+    thread::scope(|s| {
+        s.spawn(|| {
+            let _guard = fused.write();
+            barrier.wait();
+            barrier.wait();
+        });
+        barrier.wait();
+        let owner = fused.owner_thread().unwrap();
+        assert_eq!(owner.name(), None);
+        assert_eq!(owner.to_string(), format!("thread {}", owner.index()));
+        barrier.wait();
+    });
+}
+
+#[test]
+fn test_debug_state() {
+    let once = OnceLock::<usize>::new();
+    assert_eq!(format!("{:?}", once), "Once { state: Uninit }");
+    let barrier = Barrier::new(2);
+    thread::scope(|s| {
+        thread::Builder::new()
+            .name("loader".to_string())
+            .spawn_scoped(s, || {
+                once.get_or_init(|| {
+                    barrier.wait();
+                    barrier.wait();
+                    1
+                })
+            })
+            .unwrap();
+        barrier.wait();
+        assert_eq!(
+            format!("{:?}", once),
+            "Once { state: Initializing, owner: thread 'loader', waiters: false }"
+        );
+        barrier.wait();
+    });
+    assert_eq!(format!("{:?}", once), "Once { state: Init, value: 1 }");
+    let fused = FusedLock::new(vec![1]);
+    let _ = catch_unwind(AssertUnwindSafe(|| fused.read_or_fuse(|_| panic!())));
+    assert_eq!(format!("{:?}", fused), "Fused { state: Poisoned }");
+}
+
+#[test]
+fn test_build() {
+    let fused = FusedLock::<Vec<usize>>::build(|x| x.extend([1, 2, 3]));
+    assert_eq!(fused.try_read(), Some(&vec![1, 2, 3]));
+    assert!(matches!(fused.write(), FusedEntry::Read(_)));
+    let fused = FusedLock::build_from(String::from("a"), |x| x.push('b'));
+    assert_eq!(fused.try_read().unwrap(), "ab");
+}
+
+#[test]
+fn test_freeze() {
+    use crate::cell::FusedCell;
+    let cell = FusedCell::new(vec![1]);
+    let cell = cell.freeze().unwrap_err();
+    cell.read_or_fuse(|x| x.push(2));
+    let frozen = Arc::new(cell.freeze().unwrap());
+    let t = thread::spawn({
+        let frozen = frozen.clone();
+        move || frozen.len()
+    });
+    assert_eq!(t.join().unwrap(), 2);
+    assert_eq!(**frozen, [1, 2]);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_assert_initialized_before() {
+    static CONFIG: OnceLock<usize> = OnceLock::new();
+    static SERVER: LazyLock<usize> = LazyLock::new(|| 2);
+    static LOGGER: OnceLock<usize> = OnceLock::new();
+    #[cfg(feature = "distributed-slice")]
+    {
+        crate::register!(CONFIG);
+        crate::register!(SERVER);
+    }
+    CONFIG.assert_initialized_before(&SERVER);
+    LOGGER.assert_initialized_before(&CONFIG);
+    LOGGER.get_or_init(|| 0);
+    let result = catch_unwind(|| *SERVER);
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(
+        message.contains("must be initialized before"),
+        "{}",
+        message
+    );
+    #[cfg(feature = "distributed-slice")]
+    assert_eq!(*message, "CONFIG must be initialized before SERVER");
+    assert!(SERVER.try_get_checked().is_err());
+    CONFIG.get_or_init(|| 1);
+}
+
+#[test]
+fn test_new_with_fallback() {
+    let lazy = LazyLock::<usize, _>::new_with_fallback(|| "x".parse().unwrap(), || 7);
+    assert!(catch_unwind(|| *lazy).is_err());
+    assert_eq!(*lazy, 7);
+    assert_eq!(lazy.try_get(), Some(&7));
+    let lazy = LazyLock::<usize, _>::new_with_fallback(|| 1, || unreachable!());
+    assert_eq!(*lazy, 1);
+}
+
+#[test]
+fn test_std_thread_backend() {
+    use crate::api::fused::Fused;
+    use crate::api::once::Once;
+    use crate::sync::RawFusedStdThread;
+    let onces = Arc::new(
+        (0..if cfg!(miri) { 10 } else { 100 })
+            .map(|_| Once::<RawFusedStdThread, usize>::new())
+            .collect::<Vec<_>>(),
+    );
+    let barrier = Arc::new(Barrier::new(4));
+    let threads = (0..4)
+        .map(|i| {
+            let onces = onces.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                for once in onces.iter() {
+                    barrier.wait();
+                    once.get_or_init(|| {
+                        thread::sleep(Duration::from_micros(10));
+                        i
+                    });
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert!(onces.iter().all(|x| x.try_get().is_some()));
+    let once = Once::<RawFusedStdThread, usize>::new();
+    once.get_or_init(|| {
+        assert!(matches!(
+            once.get_or_init_checked(|| unreachable!()),
+            Err(LockError::Cycle { .. })
+        ));
+        1
+    });
+    let fused = Arc::new(Fused::<RawFusedStdThread, usize>::new(0));
+    let FusedEntry::Write(guard) = fused.write() else {
+        unreachable!()
+    };
+    let t = thread::spawn({
+        let fused = fused.clone();
+        move || {
+            assert!(fused.try_write_for(Duration::from_millis(10)).is_none());
+            *fused.read_or_fuse(|_| unreachable!())
+        }
+    });
+    thread::sleep(Duration::from_millis(50));
+    guard.fuse();
+    assert_eq!(t.join().unwrap(), 0);
+}
+
+#[test]
+fn test_retry_lazy() {
+    use crate::sync::RetryLazyLock;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: RetryLazyLock<usize> = RetryLazyLock::new(|| {
+        let attempt = ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+        assert!(attempt >= 2, "not yet reachable");
+        attempt
+    });
+    assert!(catch_unwind(|| *LAZY).is_err());
+    assert!(catch_unwind(|| *LAZY).is_err());
+    assert_eq!(LAZY.try_get(), None);
+    assert_eq!(*LAZY, 2);
+    assert_eq!(*LAZY, 2);
+    assert_eq!(ATTEMPTS.load(Ordering::Relaxed), 3);
+    static CYCLE: RetryLazyLock<usize> = RetryLazyLock::new(|| *CYCLE.forced());
+    let message = catch_unwind(|| *CYCLE).unwrap_err();
+    let message = message.downcast::<String>().unwrap();
+    assert!(message.starts_with("deadlock: "), "{}", message);
+}
+
+#[cfg(feature = "process")]
+#[test]
+fn test_once_process() {
+    use crate::process::OnceProcess;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let path = std::env::temp_dir().join(format!("safe-once-test-{}.json", std::process::id()));
+    let inits = Arc::new(AtomicUsize::new(0));
+    let barrier = Arc::new(Barrier::new(4));
+    let threads = (0..4)
+        .map(|i| {
+            let path = path.clone();
+            let inits = inits.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                // Separate cells, as if in separate processes.
+                let once = OnceProcess::<Vec<usize>>::with_path(path);
+                barrier.wait();
+                once.get_or_init(|| {
+                    inits.fetch_add(1, Ordering::Relaxed);
+                    vec![i]
+                })
+                .unwrap()
+                .clone()
+            })
+        })
+        .collect::<Vec<_>>();
+    let values = threads
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(inits.load(Ordering::Relaxed), 1);
+    assert!(values.iter().all(|v| *v == values[0]));
+    let once = OnceProcess::<Vec<usize>>::with_path(&path);
+    assert_eq!(once.try_get(), None);
+    assert_eq!(once.get_or_init(|| unreachable!()).unwrap(), &values[0]);
+    std::fs::remove_file(&path).unwrap();
+    let failed = OnceProcess::<usize>::with_path(&path);
+    assert!(failed
+        .get_or_try_init(|| Err(std::io::ErrorKind::Other.into()))
+        .is_err());
+    assert!(!path.exists());
+    assert_eq!(*failed.get_or_init(|| 3).unwrap(), 3);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        failed.lock_path(),
+        path.with_file_name(format!("safe-once-test-{}.json.lock", std::process::id()))
+    );
+    std::fs::remove_file(failed.lock_path()).unwrap();
+}
+
+#[test]
+fn test_get_or_init_racy() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let once = OnceLock::<usize>::new();
+    let runs = AtomicUsize::new(0);
+    // Every initializer runs before any value is stored, so none of them holds the lock.
+    let barrier = Barrier::new(4);
+    let values = thread::scope(|s| {
+        (0..4)
+            .map(|i| {
+                let (once, runs, barrier) = (&once, &runs, &barrier);
+                s.spawn(move || {
+                    *once.get_or_init_racy(|| {
+                        runs.fetch_add(1, Ordering::Relaxed);
+                        barrier.wait();
+                        i
+                    })
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(runs.load(Ordering::Relaxed), 4);
+    let winner = *once.try_get().unwrap();
+    assert!(winner < 4);
+    assert_eq!(values, [winner; 4]);
+    // A value computed while another thread holds the lock loses to the value it stores.
+    let once = OnceLock::<usize>::new();
+    let OnceEntry::Vacant(guard) = once.lock() else {
+        unreachable!()
+    };
+    thread::scope(|s| {
+        let t = s.spawn(|| *once.get_or_init_racy(|| 1));
+        thread::sleep(Duration::from_millis(50));
+        assert!(!t.is_finished());
+        guard.init(2);
+        assert_eq!(t.join().unwrap(), 2);
+    });
+    let once = OnceLock::<usize>::new();
+    assert_eq!(
+        *once.get_or_init_racy(|| *once.get_or_init_racy(|| 1) + 1),
+        1
+    );
+    let once = OnceLock::<usize>::new();
+    assert!(catch_unwind(|| once.get_or_init(|| *once.get_or_init_racy(|| 1))).is_err());
+}
+
+#[test]
+fn test_clone_current() {
+    let fused = FusedLock::new(vec![1]);
+    assert_eq!(fused.clone_current(), Some(vec![1]));
+    if let FusedEntry::Write(mut guard) = fused.write() {
+        guard.push(2);
+    }
+    assert_eq!(fused.clone_current(), Some(vec![1, 2]));
+    assert_eq!(fused.try_read(), None);
+    fused.read_or_fuse(|x| {
+        x.push(3);
+    });
+    assert_eq!(fused.clone_current(), Some(vec![1, 2, 3]));
+    assert_eq!(FusedLock::poisoned(0).clone_current(), None);
+
+    struct PanicClone;
+    impl Clone for PanicClone {
+        fn clone(&self) -> Self {
+            panic!()
+        }
+    }
+    let fused = FusedLock::new(PanicClone);
+    assert!(catch_unwind(|| fused.clone_current()).is_err());
+    assert!(fused.try_write().is_some());
+}
+
+#[cfg(feature = "on-fuse")]
+#[test]
+fn test_on_fuse() {
+    let seen = Arc::new(Mutex::new(vec![]));
+    let fused = FusedLock::new(1);
+    for i in 0..2 {
+        let seen = seen.clone();
+        fused.on_fuse(move |x| seen.lock().push((i, *x)));
+    }
+    if let FusedEntry::Write(mut guard) = fused.write() {
+        *guard = 2;
+        let seen = seen.clone();
+        fused.on_fuse(move |x| seen.lock().push((2, *x)));
+    }
+    assert!(seen.lock().is_empty());
+    fused.read_or_fuse(|x| *x += 1);
+    assert_eq!(*seen.lock(), [(0, 3), (1, 3), (2, 3)]);
+    let seen2 = seen.clone();
+    fused.on_fuse(move |x| seen2.lock().push((3, *x)));
+    assert_eq!(seen.lock().len(), 4);
+    let fused = FusedLock::new(0);
+    let seen2 = seen.clone();
+    fused.on_fuse(move |x| seen2.lock().push((4, *x)));
+    let FusedEntry::Write(guard) = fused.write() else {
+        unreachable!()
+    };
+    guard.fuse();
+    assert_eq!(seen.lock()[4], (4, 0));
+    // Another thread's callback waits for the write lock instead of racing with its holder.
+    let fused = FusedLock::new(0);
+    let FusedEntry::Write(guard) = fused.write() else {
+        unreachable!()
+    };
+    thread::scope(|s| {
+        let t = s.spawn(|| {
+            let seen = seen.clone();
+            fused.on_fuse(move |x| seen.lock().push((5, *x)));
+        });
+        thread::sleep(Duration::from_millis(50));
+        assert!(!t.is_finished());
+        drop(guard);
+    });
+    fused.read_or_fuse(|x| *x = 5);
+    assert_eq!(seen.lock()[5], (5, 5));
+    // A guard sent to another thread still counts as held by the thread that obtained it.
+    let fused = FusedLock::new(6);
+    let FusedEntry::Write(guard) = fused.write() else {
+        unreachable!()
+    };
+    let barrier = Barrier::new(2);
+    thread::scope(|s| {
+        s.spawn(|| {
+            barrier.wait();
+            guard.fuse();
+        });
+        let seen = seen.clone();
+        fused.on_fuse(move |x| seen.lock().push((6, *x)));
+        barrier.wait();
+    });
+    assert_eq!(seen.lock()[6], (6, 6));
+}
+
+#[test]
+fn test_once_map() {
+    use crate::map::OnceMap;
+    let map = OnceMap::<Arc<str>, usize>::new();
+    let mut owned = 0;
+    for _ in 0..3 {
+        let value = map.get_or_init_with_key(
+            "key",
+            |k| {
+                owned += 1;
+                Arc::from(k)
+            },
+            |k| k.len(),
+        );
+        assert_eq!(*value, 3);
+    }
+    assert_eq!(owned, 1);
+    let map = OnceMap::<String, usize>::new();
+    let entry = map.entry_ref("a");
+    assert_eq!(entry.get(), None);
+    assert!(map.is_empty());
+    assert_eq!(entry.or_try_init(|_| Err(())), Err(()));
+    assert_eq!(map.get("a"), None);
+    assert_eq!(*map.entry_ref("a").or_init(|k| k.len()), 1);
+    assert_eq!(map.entry_ref("a").get(), Some(&1));
+    let b = map.get_or_init("b", |_| *map.get_or_init("c", |_| 3) + 1);
+    assert_eq!((*b, map.get("c"), map.len()), (4, Some(&3), 3));
+}
+
+#[test]
+fn test_wait_fused() {
+    let fused = Arc::new(FusedLock::new(vec![]));
+    let waiters = (0..3)
+        .map(|_| {
+            let fused = fused.clone();
+            thread::spawn(move || fused.wait_fused().clone())
+        })
+        .collect::<Vec<_>>();
+    thread::sleep(Duration::from_millis(20));
+    if let FusedEntry::Write(mut guard) = fused.write() {
+        guard.push(1);
+    }
+    thread::sleep(Duration::from_millis(20));
+    fused.read_or_fuse(|x| x.push(2));
+    for waiter in waiters {
+        assert_eq!(waiter.join().unwrap(), [1, 2]);
+    }
+    let poisoned = Arc::new(FusedLock::new(0));
+    let waiter = thread::spawn({
+        let poisoned = poisoned.clone();
+        move || poisoned.wait_fused_checked().map(|_| ()).unwrap_err()
+    });
+    thread::sleep(Duration::from_millis(20));
+    let _ = catch_unwind(|| poisoned.read_or_fuse(|_| panic!()));
+    assert!(matches!(waiter.join().unwrap(), LockError::Poisoned { .. }));
+    let cell = crate::cell::FusedCell::new(0);
+    assert!(matches!(
+        cell.wait_fused_checked(),
+        Err(LockError::Cycle { .. })
+    ));
+}
+
+#[test]
+fn test_lock_arc() {
+    use crate::api::once::OwnedOnceEntry;
+    let once = Arc::new(OnceLock::<usize>::new());
+    let guard = match once.lock_arc() {
+        OwnedOnceEntry::Vacant(guard) => guard,
+        OwnedOnceEntry::Occupied(_) => unreachable!(),
+    };
+    let once2 = thread::spawn(move || guard.init(5)).join().unwrap();
+    assert!(Arc::ptr_eq(&once, &once2));
+    assert_eq!(once.try_get(), Some(&5));
+    assert!(matches!(once.lock_arc(), OwnedOnceEntry::Occupied(_)));
+
+    let once = Arc::new(OnceLock::<usize>::new());
+    let OwnedOnceEntry::Vacant(guard) = once.lock_arc() else {
+        unreachable!()
+    };
+    thread::spawn(move || drop(guard)).join().unwrap();
+    assert_eq!(*once.lock_arc().or_init(|| 6).try_get().unwrap(), 6);
+
+    let once = Arc::new(OnceLock::<usize>::new());
+    let OwnedOnceEntry::Vacant(guard) = once.lock_arc() else {
+        unreachable!()
+    };
+    thread::spawn(move || {
+        let _guard = guard;
+        panic!("init failed");
+    })
+    .join()
+    .unwrap_err();
+    assert!(once.try_get_checked().is_err());
+}
+
+#[test]
+fn test_escape_hatches() {
+    extern "C" fn ffi_init(out: *mut u64) {
+        unsafe { out.write(17) }
+    }
+    let once = OnceLock::<u64>::new();
+    let OnceEntry::Vacant(guard) = once.lock() else {
+        unreachable!()
+    };
+    std::mem::forget(guard);
+    ffi_init(once.as_mut_ptr());
+    assert_eq!(unsafe { *once.assume_init_read_state() }, 17);
+    assert_eq!(unsafe { *once.force_read_unchecked() }, 17);
+    assert_eq!(once.try_get(), Some(&17));
+
+    let fused = FusedLock::new(vec![1]);
+    let FusedEntry::Write(guard) = fused.write() else {
+        unreachable!()
+    };
+    std::mem::forget(guard);
+    unsafe { (*fused.as_mut_ptr()).push(2) };
+    assert_eq!(unsafe { fused.assume_init_read_state() }, &[1, 2]);
+    assert_eq!(unsafe { fused.force_read_unchecked() }, &[1, 2]);
+    assert_eq!(fused.try_read(), Some(&vec![1, 2]));
+}
+
+#[test]
+fn test_write_arc() {
+    use crate::api::fused::ArcFusedEntry;
+    let fused = Arc::new(FusedLock::new(vec![]));
+    let ArcFusedEntry::Write(mut guard) = fused.write_arc() else {
+        unreachable!()
+    };
+    guard.push(1);
+    let fused2 = thread::spawn(move || {
+        guard.push(2);
+        guard.fuse()
+    })
+    .join()
+    .unwrap();
+    assert!(Arc::ptr_eq(&fused, &fused2));
+    assert_eq!(fused.try_read(), Some(&vec![1, 2]));
+    assert!(matches!(fused.write_arc(), ArcFusedEntry::Read(_)));
+
+    let fused = Arc::new(FusedLock::new(0));
+    let ArcFusedEntry::Write(mut guard) = fused.write_arc() else {
+        unreachable!()
+    };
+    thread::spawn(move || *guard = 3).join().unwrap();
+    assert_eq!(fused.clone_current(), Some(3));
+
+    let ArcFusedEntry::Write(guard) = fused.write_arc() else {
+        unreachable!()
+    };
+    thread::spawn(move || {
+        let _guard = guard;
+        panic!("build failed");
+    })
+    .join()
+    .unwrap_err();
+    assert!(fused.try_read_checked().is_err());
+}
+
+#[test]
+fn test_lazy_swr() {
+    use crate::swr::LazySwr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let swr = LazySwr::new({
+        let fetches = fetches.clone();
+        move || match fetches.fetch_add(1, Ordering::SeqCst) {
+            0 => Err("unavailable"),
+            n => Ok(n),
+        }
+    })
+    .with_ttl(Duration::from_millis(10));
+    assert_eq!(swr.get(), Err("unavailable"));
+    assert_eq!(swr.generation(), 0);
+    assert_eq!(*swr.get().unwrap(), 1);
+    assert_eq!(*swr.get().unwrap(), 1);
+    thread::sleep(Duration::from_millis(20));
+    // The stale value is returned while the refresh runs.
+    assert_eq!(*swr.get().unwrap(), 1);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while swr.generation() < 2 {
+        assert!(Instant::now() < deadline);
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(*swr.get().unwrap() >= 2);
+
+    let swr = LazySwr::new(|| Ok::<_, ()>(5));
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while swr.try_get().is_none() {
+        assert!(Instant::now() < deadline);
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(swr.generation(), 1);
+
+    // Panicking fetches poison nothing, and the background fetch is retried after a backoff.
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let swr = LazySwr::new({
+        let fetches = fetches.clone();
+        move || match fetches.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => panic!("unavailable"),
+            n => Ok::<_, ()>(n),
+        }
+    });
+    assert!(catch_unwind(AssertUnwindSafe(|| swr.get())).is_err());
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(10);
+    let value = loop {
+        if let Some(value) = swr.try_get() {
+            break value;
+        }
+        assert!(Instant::now() < deadline);
+        thread::sleep(Duration::from_millis(1));
+    };
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(*value, 2);
+    assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    assert_eq!(*swr.get().unwrap(), 2);
+}
+
+#[test]
+fn test_get_mut_or_init() {
+    let mut once = OnceLock::<Vec<usize>>::new();
+    once.get_mut_or_init(Vec::new).push(1);
+    once.get_mut_or_init(|| unreachable!()).push(2);
+    assert_eq!(once.try_get(), Some(&vec![1, 2]));
+    let mut once = OnceLock::<usize>::new();
+    assert!(catch_unwind(|| once.get_or_init(|| panic!())).is_err());
+    let message = catch_unwind(AssertUnwindSafe(|| *once.get_mut_or_init(|| 1))).unwrap_err();
+    let message = message.downcast::<String>().unwrap();
+    assert!(message.starts_with("poisoned lock: "), "{}", message);
+
+    let mut fused = FusedLock::new(vec![1]);
+    fused.write_mut().push(2);
+    assert_eq!(fused.try_read(), None);
+    fused.write().or_fuse(|_| {});
+    fused.write_mut().push(3);
+    assert_eq!(fused.try_read(), Some(&vec![1, 2, 3]));
+}
+
+#[test]
+fn test_guards_send() {
+    use crate::api::fused::{ArcFusedGuard, FusedGuard};
+    use crate::api::once::{OnceGuard, OwnedOnceGuard};
+    use crate::sync::RawFusedLock;
+    fn assert_send<T: Send>() {}
+    assert_send::<FusedGuard<'static, RawFusedLock, usize>>();
+    assert_send::<OnceGuard<'static, RawFusedLock, usize>>();
+    assert_send::<ArcFusedGuard<RawFusedLock, usize>>();
+    assert_send::<OwnedOnceGuard<RawFusedLock, usize>>();
+
+    let once = OnceLock::<usize>::new();
+    thread::scope(|s| {
+        let OnceEntry::Vacant(guard) = once.lock() else {
+            unreachable!()
+        };
+        s.spawn(move || guard.init(3));
+    });
+    assert_eq!(once.try_get(), Some(&3));
+}
+
+#[test]
+fn test_race() {
+    use crate::race::{OnceBool, OnceNonZeroU64, OnceNonZeroUsize};
+    use std::num::{NonZeroU64, NonZeroUsize};
+    let once = &OnceNonZeroUsize::new();
+    assert_eq!(once.get(), None);
+    let values = thread::scope(|s| {
+        (1..=4)
+            .map(|i| s.spawn(move || once.get_or_init(|| NonZeroUsize::new(i).unwrap())))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert!(values.iter().all(|v| Some(*v) == once.get()));
+    assert_eq!(once.set(NonZeroUsize::new(9).unwrap()), Err(values[0]));
+
+    let once = OnceNonZeroU64::new();
+    assert_eq!(once.get_or_try_init(|| Err(())), Err(()));
+    assert_eq!(once.set(NonZeroU64::new(1 << 40).unwrap()), Ok(()));
+    assert_eq!(once.get(), NonZeroU64::new(1 << 40));
+
+    let once = OnceBool::new();
+    assert_eq!(once.get(), None);
+    assert!(!once.get_or_init(|| false));
+    assert_eq!(once.set(true), Err(false));
+    assert_eq!(once.get(), Some(false));
+}
+
+#[cfg(feature = "std-like")]
+#[test]
+fn test_std_like() {
+    use crate::std_like::cell::{LazyCell, OnceCell};
+    use crate::std_like::sync::{LazyLock, Once, OnceLock};
+    let once = OnceLock::<usize>::new();
+    catch_unwind(|| once.get_or_init(|| panic!("init failed"))).unwrap_err();
+    assert_eq!(once.get(), None);
+    thread::scope(|s| {
+        let waiter = s.spawn(|| *once.wait());
+        assert_eq!(once.set(1), Ok(()));
+        assert_eq!(waiter.join().unwrap(), 1);
+    });
+    assert_eq!(once.set(2), Err(2));
+    let mut once = once;
+    *once.get_mut().unwrap() += 1;
+    assert_eq!(once.take(), Some(2));
+    assert_eq!(once.get(), None);
+
+    let cell = OnceCell::<usize>::new();
+    assert_eq!(cell.get_or_try_init(|| Err(())), Err(()));
+    assert_eq!(*cell.get_or_init(|| 3), 3);
+    assert_eq!(cell.into_inner(), Some(3));
+
+    let lazy = LazyLock::new(|| 4);
+    assert_eq!(*LazyLock::force(&lazy), 4);
+    let lazy: LazyCell<Vec<usize>> = LazyCell::default();
+    assert!(lazy.is_empty());
+
+    let once = Once::new();
+    catch_unwind(|| once.call_once(|| panic!("init failed"))).unwrap_err();
+    assert!(!once.is_completed());
+    catch_unwind(|| once.call_once(|| {})).unwrap_err();
+    catch_unwind(|| once.wait()).unwrap_err();
+    let mut poisoned = false;
+    once.call_once_force(|state| poisoned = state.is_poisoned());
+    assert!(poisoned);
+    assert!(once.is_completed());
+    once.call_once(|| unreachable!());
+    once.wait();
+}
+
+#[test]
+fn test_once_set_take() {
+    let mut once = OnceLock::<usize>::new();
+    assert_eq!(once.get_mut(), None);
+    assert_eq!(once.set(1), Ok(()));
+    assert_eq!(once.set(2), Err(2));
+    assert_eq!(*once.wait(), 1);
+    *once.get_mut().unwrap() = 3;
+    assert_eq!(once.take(), Some(3));
+    assert_eq!(once.take(), None);
+}
+
+#[cfg(all(feature = "debug-invariants", debug_assertions))]
+#[test]
+fn test_debug_invariants() {
+    let once = OnceLock::<usize>::new();
+    let message = *catch_unwind(|| unsafe { once.assume_init_read_state() })
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap();
+    assert!(
+        message.starts_with("constructing a FusedGuard requires the write lock of the "),
+        "{}",
+        message
+    );
+    assert_eq!(*once.get_or_init(|| 1), 1);
+    let fused = crate::cell::FusedCell::new(0);
+    catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+        fused.assume_init_read_state()
+    }))
+    .unwrap_err();
+    assert_eq!(fused.try_read(), None);
+}
+
+#[test]
+fn test_once_box() {
+    use crate::race::OnceBox;
+    use std::fmt::Display;
+    let once = &OnceBox::<dyn Display + Send + Sync>::new();
+    assert!(once.get().is_none());
+    let values = thread::scope(|s| {
+        (0..4)
+            .map(|i| s.spawn(move || once.get_or_init(|| Box::new(i)).to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert!(values.iter().all(|v| *v == once.get().unwrap().to_string()));
+    assert!(once.set(Box::new("other")).is_err());
+    assert_eq!(size_of::<OnceBox<str>>(), size_of::<usize>());
+
+    let mut once = OnceBox::<[usize]>::default();
+    assert_eq!(once.get_or_try_init(|| Err(())), Err(()));
+    assert_eq!(once.get_or_init(|| vec![1, 2].into()), &[1, 2]);
+    assert_eq!(once.take().as_deref(), Some(&[1, 2][..]));
+    assert_eq!(once.get(), None);
+}
+
+#[test]
+fn test_once_ref() {
+    use crate::race::OnceRef;
+    let arena = (0..4).map(|i| i.to_string()).collect::<Vec<_>>();
+    let once = &OnceRef::<String>::new();
+    assert_eq!(once.get(), None);
+    let values = thread::scope(|s| {
+        arena
+            .iter()
+            .map(|value| s.spawn(move || once.get_or_init(|| value)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert!(values.iter().all(|v| std::ptr::eq(*v, once.get().unwrap())));
+    assert_eq!(size_of::<OnceRef<String>>(), size_of::<usize>());
+    let once = OnceRef::<String>::default();
+    assert_eq!(once.get_or_try_init(|| Err(())), Err(()));
+    assert_eq!(once.set(&arena[2]), Ok(()));
+    assert_eq!(once.get(), Some(&arena[2]));
+}
+
+#[test]
+fn test_lazy_box() {
+    use crate::sync::LazyBox;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let lazy = LazyBox::new(|| {
+        RUNS.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(10));
+        vec![1, 2]
+    });
+    assert_eq!(lazy.try_get(), None);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_eq!(*lazy, [1, 2]));
+        }
+    });
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+    assert_eq!(lazy.try_get(), Some(&vec![1, 2]));
+    assert_eq!(size_of::<LazyBox<[u8; 1024]>>(), 2 * size_of::<usize>());
+    let values = lazy.to_vec();
+    let boxed = LazyBox::boxed(move || values.len());
+    assert_eq!(*boxed, 2);
+
+    static REENTRANT: LazyBox<usize> = LazyBox::new(|| *REENTRANT + 1);
+    let message = *catch_unwind(|| *REENTRANT)
+        .unwrap_err()
+        .downcast::<&str>()
+        .unwrap();
+    assert_eq!(
+        message,
+        "deadlock: LazyBox was forced again by its own initializer"
+    );
+    catch_unwind(|| *REENTRANT).unwrap_err();
+    assert_eq!(format!("{:?}", REENTRANT), "LazyBox(<poisoned>)");
+}
+
+#[test]
+fn test_once_pin() {
+    use crate::sync::OnceLockPin;
+    use std::marker::PhantomPinned;
+    use std::mem::MaybeUninit;
+    use std::pin::Pin;
+    struct SelfRef {
+        value: usize,
+        this: *const SelfRef,
+        _pinned: PhantomPinned,
+    }
+    let once = Box::pin(OnceLockPin::<SelfRef>::new());
+    let once = once.as_ref();
+    assert!(once.try_get().is_none());
+    let value = unsafe {
+        once.get_or_init_in_place(|mut slot: Pin<&mut MaybeUninit<SelfRef>>| {
+            let this = slot.as_ref().get_ref().as_ptr();
+            slot.as_mut().get_unchecked_mut().write(SelfRef {
+                value: 7,
+                this,
+                _pinned: PhantomPinned,
+            });
+        })
+    };
+    assert_eq!(value.value, 7);
+    assert!(std::ptr::eq(value.this, &*value));
+    let again = once.get_or_init(|| unreachable!());
+    assert!(std::ptr::eq(again.this, &*again));
+
+    let once = OnceLockPin::<usize>::new();
+    assert_eq!(*Pin::new(&once).get_or_init(|| 3), 3);
+    assert_eq!(once.into_inner(), Some(3));
+}
+
+#[test]
+fn test_once_dyn() {
+    use crate::sync::OnceDyn;
+    use std::fmt::Display;
+    let once = OnceDyn::<dyn Display + Send + Sync>::new();
+    assert!(once.get().is_none());
+    let first = std::panic::Location::caller().line() + 1;
+    once.set(Box::new(1)).unwrap();
+    let error = once.set(Box::new(2)).unwrap_err();
+    assert_eq!(error.first().line(), first);
+    assert_eq!(error.second().line(), first + 1);
+    assert_eq!(once.get().unwrap().to_string(), "1");
+    assert_eq!(once.set_at(), Some(error.first()));
+}
+
+#[test]
+fn test_lazy_race() {
+    use crate::race::{LazyBool, LazyU64, LazyUsize};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: LazyUsize = LazyUsize::new(|| RUNS.fetch_add(1, Ordering::Relaxed) + 10);
+    assert_eq!(format!("{:?}", LAZY), "LazyUsize(None)");
+    let values = thread::scope(|s| {
+        (0..4)
+            .map(|_| s.spawn(|| LAZY.get()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert!(values.iter().all(|v| *v == LAZY.get()));
+    let runs = RUNS.load(Ordering::Relaxed);
+    assert!(runs >= 1);
+    LAZY.get();
+    assert_eq!(RUNS.load(Ordering::Relaxed), runs);
+
+    let sentinel = LazyU64::new(|| u64::MAX);
+    assert_eq!(sentinel.get(), LazyU64::<fn() -> u64>::SENTINEL);
+    assert_eq!(format!("{:?}", sentinel), "LazyU64(None)");
+    let flag = LazyBool::new(|| true);
+    assert!(flag.get());
+    assert_eq!(format!("{:?}", flag), "LazyBool(Some(true))");
+}
+
+#[test]
+fn test_lazy_cow() {
+    use crate::cell::LazyCellCow;
+    use crate::sync::LazyLockCow;
+    static DEFAULT: [usize; 2] = [1, 2];
+    static CONFIG: LazyLockCow<[usize; 2]> = LazyLockCow::new(&DEFAULT);
+    assert!(std::ptr::eq(CONFIG.get(), &DEFAULT));
+    assert!(!CONFIG.is_owned());
+    assert_eq!(CONFIG.modify(|config| config[0] = 3), &[3, 2]);
+    assert_eq!(CONFIG.modify(|_| unreachable!()), &[3, 2]);
+    assert_eq!(*CONFIG, [3, 2]);
+    assert_eq!(DEFAULT, [1, 2]);
+
+    let default = vec![1];
+    let cow = LazyCellCow::new(&default);
+    assert_eq!(cow.into_owned(), vec![1]);
+}
+
+#[test]
+fn test_arc_lazy() {
+    use crate::sync::ArcLazy;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let lazy = ArcLazy::new(|| RUNS.fetch_add(1, Ordering::Relaxed) + 1);
+    let clones = (0..4).map(|_| lazy.clone()).collect::<Vec<_>>();
+    assert!(clones.iter().all(|clone| ArcLazy::ptr_eq(clone, &lazy)));
+    let values = clones
+        .into_iter()
+        .map(|clone| thread::spawn(move || *clone))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, [1, 1, 1, 1]);
+    assert_eq!(lazy.try_get(), Some(&1));
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+    assert!(!ArcLazy::ptr_eq(&lazy, &ArcLazy::from(1)));
+}
+
+#[test]
+fn test_lazy_weak() {
+    use crate::sync::LazyWeak;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: LazyWeak<usize> = LazyWeak::new(|| RUNS.fetch_add(1, Ordering::Relaxed));
+    assert!(LAZY.try_get().is_none());
+    let values = thread::scope(|s| {
+        (0..4)
+            .map(|_| s.spawn(|| LAZY.get()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert!(values.iter().all(|v| Arc::ptr_eq(v, &values[0])));
+    assert_eq!(*values[0], 0);
+    drop(values);
+    assert!(LAZY.try_get().is_none());
+    assert_eq!(*LAZY.get(), 1);
+
+    let flaky = LazyWeak::new(|| {
+        if RUNS.fetch_add(1, Ordering::Relaxed) == 2 {
+            panic!("init failed");
+        }
+    });
+    catch_unwind(|| flaky.get()).unwrap_err();
+    flaky.get();
+
+    // Reading does not take the lock held by the initializer.
+    static DEBUGGED: LazyWeak<String> = LazyWeak::new(|| format!("{:?}", DEBUGGED));
+    assert_eq!(*DEBUGGED.get(), "LazyWeak { value: None }");
+}
+
+#[test]
+fn test_expiring_lazy() {
+    use crate::sync::ExpiringLazy;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let lazy = ExpiringLazy::new(Duration::from_millis(20), || {
+        RUNS.fetch_add(1, Ordering::Relaxed)
+    });
+    assert_eq!(lazy.try_get(), None);
+    let first = lazy.get();
+    assert_eq!(*first, 0);
+    assert!(Arc::ptr_eq(&first, &lazy.get()));
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(lazy.try_get(), None);
+    assert_eq!(*lazy.get(), 1);
+    assert_eq!(*first, 0);
+    lazy.invalidate();
+    assert_eq!(*lazy.get(), 2);
+
+    // Reading does not take the lock held by the initializer.
+    static DEBUGGED: ExpiringLazy<String> =
+        ExpiringLazy::new(Duration::from_secs(60), || format!("{:?}", DEBUGGED));
+    assert_eq!(*DEBUGGED.get(), "ExpiringLazy { ttl: 60s, value: None }");
+}
+
+#[test]
+fn test_resettable_lazy() {
+    use crate::cell::ResettableLazyCell;
+    use std::cell::Cell;
+    let runs = Cell::new(0);
+    let mut lazy = ResettableLazyCell::new(|| {
+        runs.set(runs.get() + 1);
+        if runs.get() == 2 {
+            panic!("second run");
+        }
+        runs.get()
+    });
+    assert_eq!(*lazy, 1);
+    assert_eq!(*lazy, 1);
+    lazy.reset();
+    assert!(catch_unwind(AssertUnwindSafe(|| lazy.forced())).is_err());
+    assert!(lazy.try_get_checked().is_err());
+    let message = catch_unwind(AssertUnwindSafe(|| lazy.forced())).unwrap_err();
+    assert!(message
+        .downcast::<String>()
+        .unwrap()
+        .starts_with("poisoned lock: "));
+    assert_eq!(lazy.take(), None);
+    assert_eq!(*lazy, 3);
+    assert_eq!(lazy.take(), Some(3));
+    assert_eq!(lazy.into_inner(), None);
+}
+
+#[test]
+fn test_memo() {
+    use crate::map::Memo;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let runs = AtomicUsize::new(0);
+    let memo = Memo::new(|locale: &String| {
+        runs.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(10));
+        format!("table for {}", locale)
+    });
+    assert_eq!(memo.try_get(&"en".to_string()), None);
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                assert_eq!(memo.get(&"en".to_string()), "table for en");
+                assert_eq!(memo.get(&"fr".to_string()), "table for fr");
+            });
+        }
+    });
+    assert_eq!(runs.load(Ordering::Relaxed), 2);
+    assert_eq!(memo.len(), 2);
+}
+
+#[test]
+fn test_frozen() {
+    use crate::cell::FrozenVecCell;
+    use crate::sync::{FrozenMapLock, FrozenVecLock};
+    let vec = FrozenVecLock::new();
+    let map = FrozenMapLock::<String, usize>::new();
+    thread::scope(|s| {
+        for i in 0..4 {
+            let vec = &vec;
+            let map = &map;
+            s.spawn(move || {
+                let pushed = vec.push(i);
+                let name = format!("key{}", i % 2);
+                let inserted = map.get_or_insert_with(name.clone(), |key| key.len() + i);
+                for _ in 0..100 {
+                    vec.push(100);
+                }
+                assert_eq!(*pushed, i);
+                assert_eq!(map.get(name.as_str()), Some(inserted));
+            });
+        }
+    });
+    assert_eq!(vec.len(), 404);
+    assert_eq!(map.len(), 2);
+    assert_eq!(
+        *map.insert("key0".to_string(), 0),
+        *map.get("key0").unwrap()
+    );
+
+    let cell = FrozenVecCell::from(vec![1, 2]);
+    let first = cell.get(0).unwrap();
+    cell.push(3);
+    assert_eq!(*first, 1);
+    assert_eq!(cell.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(format!("{:?}", cell), "[1, 2, 3]");
+    assert_eq!(cell.into_vec(), [1, 2, 3]);
+}
+
+#[test]
+fn test_once_vec() {
+    use crate::sync::{OnceSlice, OnceVec};
+    let vec = OnceVec::<usize>::new();
+    assert_eq!(vec.get(0), None);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for i in [0, 31, 32, 95, 96, 5000] {
+                    assert_eq!(*vec.get_or_init(i, || i * 2), i * 2);
+                }
+            });
+        }
+    });
+    assert_eq!(vec.set(31, 0), Err(0));
+    assert_eq!(
+        vec.get_or_try_init(7, || Err::<usize, _>("failed")),
+        Err("failed")
+    );
+    assert_eq!(vec.get(7), None);
+    assert_eq!(
+        vec.iter().collect::<Vec<_>>(),
+        [
+            (0, &0),
+            (31, &62),
+            (32, &64),
+            (95, &190),
+            (96, &192),
+            (5000, &10000)
+        ]
+    );
+    let slice: OnceSlice<usize> = [Some(1), None].into_iter().collect();
+    assert_eq!(*slice.get_or_init(1, || 2), 2);
+    assert_eq!(slice.into_vec(), [Some(1), Some(2)]);
+}
+
+#[test]
+fn test_once_array() {
+    use crate::sync::OnceArray;
+    let array = OnceArray::<usize, 100>::new();
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for i in 0..100 {
+                    assert_eq!(*array.get_or_init(i, || i * 3), i * 3);
+                }
+            });
+        }
+    });
+    let mut array = array;
+    *array.get_mut(5).unwrap() = 0;
+    assert_eq!(array.get(5), Some(&0));
+
+    let array = OnceArray::<String, 40>::new();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        array.get_or_init(33, || array.get_or_init(33, || unreachable!()).clone())
+    }));
+    let message = result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.starts_with("deadlock: OnceArray slot 33"));
+    assert!(catch_unwind(AssertUnwindSafe(|| array.get(33))).is_err());
+    assert_eq!(array.get_or_init(32, || "ok".to_string()), "ok");
+    assert_eq!(array.get(34), None);
+
+    static ARRAY: OnceArray<usize, 100> = OnceArray::new();
+    assert_eq!(ARRAY.get(99), None);
+    assert_eq!(*ARRAY.get_or_init(99, || 1), 1);
+    // Larger than a test thread's stack.
+    let array = OnceArray::<[usize; 4], { 1 << 18 }>::new_boxed();
+    assert_eq!(array.get(1 << 17), None);
+    assert_eq!(*array.get_or_init(1 << 17, || [1; 4]), [1; 4]);
+}
+
+#[test]
+fn test_interner() {
+    use crate::intern::Interner;
+    use std::collections::HashSet;
+    let interner = Interner::<str>::new();
+    assert_eq!(interner.get("a"), None);
+    let symbols: Vec<_> = thread::scope(|s| {
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let interner = &interner;
+                s.spawn(move || interner.intern(["a", "b"][i % 2]))
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert_eq!(symbols[0], symbols[2]);
+    assert_ne!(symbols[0], symbols[1]);
+    assert_eq!(symbols.iter().collect::<HashSet<_>>().len(), 2);
+    assert_eq!(interner.intern_boxed("b".into()), symbols[1]);
+    assert_eq!(interner.get("a"), Some(symbols[0]));
+    assert_eq!(&*symbols[1], "b");
+    assert_eq!(interner.len(), 2);
+
+    let values = Interner::<[u8]>::new();
+    assert_eq!(
+        values.intern(&[1, 2][..]),
+        values.intern_boxed(Box::new([1, 2]))
+    );
+}
+
+#[test]
+fn test_once_type_map() {
+    use crate::map::OnceTypeMap;
+    #[derive(Debug)]
+    struct A(u32);
+    struct B(u32);
+    let map = OnceTypeMap::new();
+    thread::scope(|s| {
+        for i in 0..4 {
+            let map = &map;
+            s.spawn(move || map.get_or_init(|| A(i)).0);
+        }
+    });
+    let a = map.get::<A>().unwrap().0;
+    assert!(a < 4);
+    assert_eq!(
+        map.get_or_try_init(|| Err::<B, _>("failed")).err(),
+        Some("failed")
+    );
+    assert!(map.get::<B>().is_none());
+    assert_eq!(
+        map.get_or_init(|| B(map.get_or_init(|| A(10)).0 + 1)).0,
+        a + 1
+    );
+    assert_eq!(map.len(), 2);
+
+    struct C;
+    struct D;
+    let map = OnceTypeMap::new();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        map.get_or_init(|| {
+            map.get_or_init(|| {
+                map.get_or_init(|| C);
+                D
+            });
+            C
+        });
+    }));
+    let payload = result.unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("deadlock"), "{}", message);
+}
+
+#[test]
+fn test_lazy_thread_local() {
+    use crate::sync::LazyThreadLocal;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let runs = AtomicUsize::new(0);
+    let mut local = LazyThreadLocal::new(|| {
+        runs.fetch_add(1, Ordering::Relaxed);
+        thread::current().id()
+    });
+    assert_eq!(local.try_get(), None);
+    assert_eq!(*local, thread::current().id());
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                assert_eq!(*local.get(), thread::current().id());
+                assert_eq!(*local.get(), thread::current().id());
+            });
+        }
+    });
+    assert_eq!(runs.load(Ordering::Relaxed), 5);
+    assert_eq!(local.iter_mut().count(), 5);
+    assert_eq!(local.into_vec().len(), 5);
+}
+
+#[test]
+fn test_lazy_spawn_init() {
+    static STARTED: Barrier = Barrier::new(2);
+    static LAZY: LazyLock<usize> = LazyLock::new(|| {
+        STARTED.wait();
+        thread::sleep(Duration::from_millis(10));
+        42
+    });
+    LAZY.spawn_init();
+    STARTED.wait();
+    assert_eq!(*LAZY, 42);
+
+    static POOL: LazyLock<usize> = LazyLock::new(|| 7);
+    let (send, recv) = std::sync::mpsc::channel::<Box<dyn FnOnce() + Send>>();
+    POOL.spawn_init_with(|init| send.send(init).unwrap());
+    assert_eq!(POOL.try_get(), None);
+    recv.recv().unwrap()();
+    assert_eq!(POOL.try_get(), Some(&7));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par() {
+    use crate::registry::Registered;
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let cells: Vec<LazyLock<usize, Box<dyn FnOnce() -> usize + Send + Sync>>> = (0..64usize)
+        .map(|i| {
+            LazyLock::new(Box::new(move || {
+                RUNS.fetch_add(1, Ordering::Relaxed);
+                i
+            }) as Box<dyn FnOnce() -> usize + Send + Sync>)
+        })
+        .collect();
+    let refs: Vec<&dyn Registered> = cells.iter().map(|c| c as &dyn Registered).collect();
+    crate::par::force_many_par(&refs);
+    crate::par::force_many_par(&refs);
+    assert_eq!(RUNS.load(Ordering::Relaxed), 64);
+    assert!(cells
+        .iter()
+        .enumerate()
+        .all(|(i, c)| c.try_get() == Some(&i)));
+
+    let fused = FusedLock::new(0usize);
+    let sums: Vec<usize> = (0..64)
+        .into_par_iter()
+        .map(|_| {
+            *fused.read_or_fuse_par(|x| {
+                *x = (0..1000usize).into_par_iter().sum();
+            })
+        })
+        .collect();
+    assert!(sums.iter().all(|&x| x == 499500));
+}
+
+#[test]
+fn test_spin() {
+    use crate::spin::{FusedSpin, LazySpin, OnceSpin};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: LazySpin<usize> = LazySpin::new(|| {
+        RUNS.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(10));
+        5
+    });
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_eq!(*LAZY, 5));
+        }
+    });
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+
+    let once = OnceSpin::<usize>::new();
+    let OnceEntry::Vacant(guard) = once.lock() else {
+        unreachable!()
+    };
+    assert!(once.try_lock().is_none());
+    assert!(once.try_get().is_none());
+    guard.init(1);
+    assert_eq!(once.wait(), &1);
+
+    let fused = FusedSpin::new(0);
+    assert!(catch_unwind(AssertUnwindSafe(|| fused.read_or_fuse(|_| panic!()))).is_err());
+    #[cfg(not(feature = "no-poison"))]
+    assert!(matches!(
+        fused.write_checked(),
+        Err(LockError::Poisoned { .. })
+    ));
+    #[cfg(feature = "no-poison")]
+    assert_eq!(*fused.read_or_fuse(|x| *x = 1), 1);
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn test_critical_section() {
+    use crate::cs::{FusedLock, LazyLock, OnceLock};
+    static LAZY: LazyLock<usize> = LazyLock::new(|| 5);
+    assert_eq!(*LAZY, 5);
+
+    let once = OnceLock::<usize>::new();
+    let OnceEntry::Vacant(guard) = once.lock() else {
+        unreachable!()
+    };
+    assert!(once.try_lock().is_none());
+    assert!(matches!(once.lock_checked(), Err(LockError::Cycle { .. })));
+    assert!(once.try_get().is_none());
+    guard.init(1);
+    assert_eq!(once.wait(), &1);
+
+    let reentrant = OnceLock::<usize>::new();
+    assert!(catch_unwind(AssertUnwindSafe(|| {
+        reentrant.get_or_init(|| *reentrant.get_or_init(|| 1))
+    }))
+    .is_err());
+
+    let fused = FusedLock::new(0);
+    assert!(catch_unwind(AssertUnwindSafe(|| fused.read_or_fuse(|_| panic!()))).is_err());
+    #[cfg(not(feature = "no-poison"))]
+    assert!(matches!(
+        fused.write_checked(),
+        Err(LockError::Poisoned { .. })
+    ));
+    #[cfg(feature = "no-poison")]
+    assert_eq!(*fused.read_or_fuse(|x| *x = 1), 1);
+}
+
+#[test]
+fn test_isr_cell() {
+    use crate::cell::isr::{LazyCell, OnceCell};
+    static ONCE: OnceCell<usize> = OnceCell::new();
+    // Runs as if an interrupt arrived while the initializer was running.
+    fn interrupt() {
+        assert_eq!(ONCE.try_get(), None);
+        assert!(matches!(ONCE.wait_checked(), Err(LockError::Cycle { .. })));
+        assert!(catch_unwind(|| ONCE.get_or_init(|| 2)).is_err());
+    }
+    ONCE.get_or_init(|| {
+        interrupt();
+        1
+    });
+    assert_eq!(ONCE.try_get(), Some(&1));
+
+    static LAZY: LazyCell<usize> = LazyCell::new(|| *LAZY + 1);
+    assert!(catch_unwind(|| *LAZY).is_err());
+}
+
+#[test]
+#[cfg(feature = "futex")]
+fn test_futex_backend() {
+    use crate::sync::futex::{FusedLock, OnceLock};
+    let onces = Arc::new((0..100).map(|_| OnceLock::new()).collect::<Vec<_>>());
+    let barrier = Arc::new(Barrier::new(4));
+    let threads = (0..4)
+        .map(|i| {
+            let onces = onces.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                for once in onces.iter() {
+                    barrier.wait();
+                    once.get_or_init(|| {
+                        thread::sleep(Duration::from_micros(10));
+                        i
+                    });
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert!(onces.iter().all(|x| x.try_get().is_some()));
+    let once = OnceLock::<usize>::new();
+    once.get_or_init(|| {
+        assert!(matches!(
+            once.get_or_init_checked(|| unreachable!()),
+            Err(LockError::Cycle { .. })
+        ));
+        1
+    });
+    let fused = Arc::new(FusedLock::<usize>::new(0));
+    let FusedEntry::Write(guard) = fused.write() else {
+        unreachable!()
+    };
+    let t = thread::spawn({
+        let fused = fused.clone();
+        move || {
+            assert!(fused.try_write_for(Duration::from_millis(10)).is_none());
+            *fused.read_or_fuse(|_| unreachable!())
+        }
+    });
+    thread::sleep(Duration::from_millis(50));
+    guard.fuse();
+    assert_eq!(t.join().unwrap(), 0);
+}
+
+#[test]
+#[cfg(feature = "dyn-backend")]
+fn test_dyn_backend() {
+    use crate::sync::dynamic::{LazyLock, OnceLock};
+    let once = OnceLock::<usize>::new();
+    once.get_or_init(|| {
+        assert!(matches!(
+            once.get_or_init_checked(|| unreachable!()),
+            Err(LockError::Cycle { .. })
+        ));
+        1
+    });
+    let onces = Arc::new((0..100).map(|_| OnceLock::new()).collect::<Vec<_>>());
+    let barrier = Arc::new(Barrier::new(4));
+    let threads = (0..4)
+        .map(|i| {
+            let onces = onces.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                for once in onces.iter() {
+                    barrier.wait();
+                    once.get_or_init(|| i);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert!(onces.iter().all(|x| x.try_get().is_some()));
+    assert!(!crate::sync::DynRawFused::single_threaded());
+    let lazy = LazyLock::new(|| 2);
+    assert_eq!(*lazy, 2);
+}
+
+#[test]
+#[cfg(feature = "deadlock-detection")]
+fn test_cross_thread_deadlock() {
+    let onces = Arc::new((OnceLock::<usize>::new(), OnceLock::<usize>::new()));
+    let barrier = Arc::new(Barrier::new(2));
+    let threads = (0..2)
+        .map(|i| {
+            let onces = onces.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let (mine, theirs) = if i == 0 {
+                    (&onces.0, &onces.1)
+                } else {
+                    (&onces.1, &onces.0)
+                };
+                *mine.get_or_init(|| {
+                    barrier.wait();
+                    *theirs.get_or_init(|| unreachable!()) + 1
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    let messages = threads
+        .into_iter()
+        .map(|t| match t.join() {
+            Ok(_) => String::new(),
+            Err(payload) => payload
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+    let deadlocks = messages
+        .iter()
+        .filter(|m| m.starts_with("deadlock: 2 threads are waiting"))
+        .collect::<Vec<_>>();
+    assert_eq!(deadlocks.len(), 1, "{:?}", messages);
+    assert_eq!(deadlocks[0].matches("waits for the lock at").count(), 2);
+    assert!(onces.0.try_get_checked().is_err());
+    assert!(onces.1.try_get_checked().is_err());
+}
+
+#[test]
+fn test_state_packing() {
+    use crate::sync::state::State;
+    use crate::sync::thread_id::ThreadId;
+    let ids = thread::scope(|s| {
+        (0..8)
+            .map(|_| s.spawn(ThreadId::current))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    let mut sorted = ids.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(sorted.len(), ids.len());
+    for id in ids.into_iter().chain([ThreadId::current()]) {
+        assert_eq!(id.0 % ThreadId::ALIGN, 0);
+        let state = State::new()
+            .with_thread_id(id)
+            .with_init(true)
+            .with_locked(true)
+            .with_parked(true)
+            .with_poison(true);
+        assert_eq!(state.thread_id(), id);
+        assert!(state.init() && state.locked() && state.parked() && state.poison());
+        let state = state.with_locked(false).with_parked(false);
+        assert_eq!(state.thread_id(), id);
+        assert!(state.init() && !state.locked() && !state.parked() && state.poison());
+    }
+    assert_eq!(ThreadId::current(), ThreadId::current());
+}
+
+#[test]
+fn test_thread_id_not_reused() {
+    use crate::sync::thread_id::ThreadId;
+    let fused = FusedLock::new(0);
+    let once = OnceLock::<usize>::new();
+    // A thread that exits while holding the write lock, and threads that start and exit while
+    // another initializes `once`.
+    thread::scope(|s| {
+        s.spawn(|| std::mem::forget(fused.write()));
+    });
+    let ids = thread::scope(|s| {
+        s.spawn(|| {
+            once.get_or_init(|| {
+                thread::sleep(Duration::from_millis(10));
+                1
+            })
+        });
+        (0..64)
+            .map(|_| {
+                thread::spawn(ThreadId::current).join().unwrap();
+                s.spawn(|| {
+                    let result = fused
+                        .try_write_for_checked(Duration::ZERO)
+                        .map(|x| x.is_some());
+                    assert!(matches!(result, Ok(false)));
+                    assert_eq!(*once.get_or_init(|| 1), 1);
+                    ThreadId::current()
+                })
+                .join()
+                .unwrap()
+            })
+            .collect::<Vec<_>>()
+    });
+    let mut sorted = ids.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(sorted.len(), ids.len());
+}
+
+#[test]
+fn test_runtime_backend() {
+    use crate::api::lazy::Lazy;
+    use crate::api::once::Once;
+    use crate::api::raw::{RawFused, RawFusedConst, RawFusedState};
+    use crate::sync::RawFusedLock;
+    use std::time::Instant;
+
+    // A backend that allocates, so it cannot be constructed in const contexts.
+    struct Boxed(Box<RawFusedLock>);
+
+    unsafe impl RawFused for Boxed {
+        type GuardMarker = ();
+        fn unlocked() -> Self {
+            Boxed(Box::new(RawFusedLock::UNLOCKED))
+        }
+        fn read() -> Self {
+            Boxed(Box::new(RawFusedLock::READ))
+        }
+        fn poisoned() -> Self {
+            Boxed(Box::new(RawFusedLock::POISON))
+        }
+        fn write_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+            self.0.write_checked()
+        }
+        fn write_until_checked(
+            &self,
+            deadline: Instant,
+        ) -> Result<Option<RawFusedState>, TryLockError<()>> {
+            self.0.write_until_checked(deadline)
+        }
+        fn try_write_checked(&self) -> Result<Option<RawFusedState>, PoisonError<()>> {
+            self.0.try_write_checked()
+        }
+        fn read_checked(&self) -> Result<RawFusedState, TryLockError<()>> {
+            self.0.read_checked()
+        }
+        fn try_read_checked(&self) -> Result<RawFusedState, PoisonError<()>> {
+            self.0.try_read_checked()
+        }
+        unsafe fn unlock(&self) {
+            unsafe { self.0.unlock() }
+        }
+        unsafe fn unlock_poison(&self) {
+            unsafe { self.0.unlock_poison() }
+        }
+        unsafe fn unlock_fuse(&self) {
+            unsafe { self.0.unlock_fuse() }
+        }
+        fn try_get_mut(&mut self) -> Result<RawFusedState, PoisonError<()>> {
+            self.0.try_get_mut()
+        }
+    }
+
+    let once = Once::<Boxed, usize>::new_runtime();
+    assert_eq!(once.try_get(), None);
+    assert_eq!(*once.get_or_init(|| 1), 1);
+    assert_eq!(once.clone().try_get(), Some(&1));
+    let lazy = Lazy::<Boxed, usize>::new_runtime(|| 2);
+    assert_eq!(*lazy.forced(), 2);
+    let fused = crate::api::fused::Fused::from_raw(Boxed::read(), 3);
+    assert_eq!(fused.try_read(), Some(&3));
+    // Boxed never recovers, so reinitializing panics instead of retrying forever.
+    let once = Once::<Boxed, usize>::new_runtime();
+    assert!(catch_unwind(|| once.get_or_init(|| panic!())).is_err());
+    assert!(catch_unwind(|| once.get_or_reinit(|| 4)).is_err());
+}
+
+// Exercises the pointers tagged with lock state, for `MIRIFLAGS=-Zmiri-strict-provenance`.
+#[test]
+fn test_strict_provenance() {
+    use crate::api::once::Once;
+    use crate::sync::{LazyBox, RawFusedStdThread};
+    let once = Once::<RawFusedStdThread, usize>::new();
+    let lazy = LazyBox::new(|| 2);
+    let barrier = Barrier::new(3);
+    thread::scope(|s| {
+        for _ in 0..3 {
+            s.spawn(|| {
+                barrier.wait();
+                assert_eq!(
+                    *once.get_or_init(|| {
+                        thread::sleep(Duration::from_millis(10));
+                        1
+                    }),
+                    1
+                );
+                assert_eq!(*lazy, 2);
+            });
+        }
+    });
+    assert!(once.try_get().is_some());
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_stats() {
+    let once = OnceLock::<usize>::new();
+    assert_eq!(once.stats().unwrap(), crate::stats::Stats::default());
+    let barrier = Barrier::new(2);
+    thread::scope(|s| {
+        s.spawn(|| {
+            once.get_or_init(|| {
+                barrier.wait();
+                while once.stats().unwrap().parks == 0 {
+                    thread::yield_now();
+                }
+                1
+            })
+        });
+        barrier.wait();
+        assert_eq!(*once.get_or_init(|| unreachable!()), 1);
+    });
+    let stats = once.stats().unwrap();
+    assert!(stats.parks >= 1);
+    assert_eq!(stats.init_attempts, 1);
+    assert!(stats.init_duration > Duration::ZERO);
+    let lazy = LazyLock::<usize>::new(|| panic!());
+    assert!(catch_unwind(|| *lazy).is_err());
+    assert_eq!(lazy.stats().unwrap().init_attempts, 1);
+    assert!(crate::stats::global().init_attempts >= 2);
+}
@@ -1,24 +1,144 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
 #[derive(Copy, Clone, Eq, Ord, PartialEq, PartialOrd, Debug)]
 pub struct ThreadId(pub usize);
 
-#[repr(align(16))]
-struct Aligned128(#[allow(dead_code)] u128);
+// The number of thread ids handed out so far.
+static NEXT: AtomicUsize = AtomicUsize::new(0);
 
 impl ThreadId {
+    /// The alignment of every thread id, whose low bits are therefore clear.
+    pub const ALIGN: usize = 16;
+
+    /// The id of the current thread. Ids are multiples of [ThreadId::ALIGN] assigned in order
+    /// rather than addresses of thread-locals, whose alignment some 32-bit and 16-bit targets do
+    /// not guarantee, and which a new thread may reuse after the thread that owned them exits.
+    /// Ids are never reused, so a lock held by an exited thread is never mistaken for one held by
+    /// the current thread. A process may start up to `usize::MAX / ALIGN` threads that use them.
     pub fn current() -> Self {
-        // guarantee 4 bits of alignment by using u128
-        thread_local!(static KEY: Aligned128 = Aligned128(0));
-        KEY.with(|x| {
-            let x = x as *const _ as usize;
-            ThreadId::from(x)
+        thread_local!(static KEY: Cell<usize> = const { Cell::new(0) });
+        KEY.with(|key| {
+            if key.get() == 0 {
+                key.set(Self::next().0);
+            }
+            ThreadId(key.get())
         })
     }
+
+    /// Like [ThreadId::current], but also records the name of the current thread for
+    /// [OwnerThread]. Unlike ThreadId::current, this may allocate.
+    pub fn current_named() -> Self {
+        thread_local!(static NAMED: Named = Named::new());
+        let _ = NAMED.try_with(|_| {});
+        Self::current()
+    }
+
+    /// A small number identifying the thread in messages.
+    pub fn index(self) -> usize {
+        self.0 / Self::ALIGN
+    }
+
+    /// A new id that identifies no thread, for a write lock that a task holds across an `await`
+    /// and may release from another thread.
+    #[cfg(feature = "async")]
+    pub fn detached() -> Self {
+        Self::next()
+    }
+
+    #[cold]
+    fn next() -> Self {
+        let index = NEXT.fetch_add(1, Relaxed) + 1;
+        if index > usize::MAX / Self::ALIGN {
+            NEXT.store(usize::MAX / Self::ALIGN, Relaxed);
+            panic!("safe-once thread ids exhausted");
+        }
+        ThreadId::from(index * Self::ALIGN)
+    }
 }
 
 impl From<usize> for ThreadId {
     fn from(x: usize) -> Self {
-        assert_eq!(x & 0b1111, 0);
+        assert_eq!(x & (Self::ALIGN - 1), 0);
         assert_ne!(x, 0);
         ThreadId(x)
     }
 }
+
+// The names of the named threads that have locked with ThreadId::current_named.
+static NAMES: Mutex<BTreeMap<ThreadId, Arc<str>>> = Mutex::new(BTreeMap::new());
+
+// Keeps the current thread's name in NAMES until the thread exits.
+struct Named(ThreadId);
+
+impl Named {
+    fn new() -> Self {
+        let tid = ThreadId::current();
+        if let Some(name) = thread::current().name() {
+            NAMES
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(tid, name.into());
+        }
+        Named(tid)
+    }
+}
+
+impl Drop for Named {
+    fn drop(&mut self) {
+        NAMES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.0);
+    }
+}
+
+/// The thread that holds a write lock, as reported by
+/// [RawFused::owner_thread](crate::api::raw::RawFused::owner_thread). Displays as the thread's
+/// name if it has one, and otherwise as a number assigned in the order that threads first lock.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnerThread {
+    index: usize,
+    name: Option<Arc<str>>,
+}
+
+impl OwnerThread {
+    pub(crate) fn new(tid: ThreadId) -> Self {
+        OwnerThread {
+            index: tid.index(),
+            name: NAMES
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&tid)
+                .cloned(),
+        }
+    }
+
+    /// The number of the thread, which is unique within the process.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub(crate) fn is_current(&self) -> bool {
+        self.index == ThreadId::current().index()
+    }
+
+    /// The name of the thread, if it was named when it locked.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+impl Display for OwnerThread {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "thread '{}'", name),
+            None => write!(f, "thread {}", self.index),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! A wait-for graph of the threads parked on a [RawFusedLock], used to detect deadlocks that
+//! involve more than one thread.
+//!
+//! A thread waiting for a lock points to the thread holding it, which the lock's state records.
+//! Before parking, a thread follows these edges, and if they lead back to itself, every thread in
+//! the cycle would wait forever, so it panics with a description of the cycle instead. Unwinding
+//! poisons the locks it holds, which wakes the other threads in the cycle.
+
+use crate::api::raw::RawFused;
+use crate::deadlock::{CycleAction, CycleInfo};
+use crate::sync::thread_id::ThreadId;
+use crate::sync::{OwnerThread, RawFusedLock};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Mutex, PoisonError, TryLockError};
+
+// A lock that a thread is parked on. The thread holds a reference to it while it is in WAITING.
+struct LockPtr(*const RawFusedLock);
+
+unsafe impl Send for LockPtr {}
+
+static WAITING: Mutex<BTreeMap<ThreadId, LockPtr>> = Mutex::new(BTreeMap::new());
+
+/// Records that the current thread is waiting for a lock until dropped.
+pub(crate) struct Waiting(ThreadId);
+
+impl Waiting {
+    /// Record that `tid` is about to park on `lock`. If doing so would deadlock, panic, or act as
+    /// decided by the [cycle handler](crate::deadlock::set_cycle_handler).
+    pub(crate) fn new(lock: &RawFusedLock, tid: ThreadId) -> Result<Waiting, TryLockError<()>> {
+        let mut waiting = WAITING.lock().unwrap_or_else(PoisonError::into_inner);
+        waiting.insert(tid, LockPtr(lock));
+        if let Some((threads, cycle)) = find_cycle(&waiting, tid) {
+            waiting.remove(&tid);
+            drop(waiting);
+            let info = CycleInfo {
+                description: &cycle,
+                threads,
+            };
+            match crate::deadlock::detected(&info) {
+                CycleAction::Error => return Err(TryLockError::WouldBlock),
+                _ => panic!("{}", cycle),
+            }
+        }
+        Ok(Waiting(tid))
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        WAITING
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.0);
+    }
+}
+
+// Follow the edges from `tid`, and describe the cycle if they return to it. Cycles that do not
+// include `tid` were reported by the last of their threads to park.
+fn find_cycle(waiting: &BTreeMap<ThreadId, LockPtr>, tid: ThreadId) -> Option<(usize, String)> {
+    let mut edges = vec![];
+    let mut thread = tid;
+    while edges.len() < waiting.len() {
+        let lock = unsafe { &*waiting.get(&thread)?.0 };
+        let state = lock.state.load(Relaxed);
+        if !state.locked() {
+            return None;
+        }
+        edges.push((thread, lock, state.thread_id()));
+        if state.thread_id() == tid {
+            return Some((edges.len(), describe(&edges)));
+        }
+        thread = state.thread_id();
+    }
+    None
+}
+
+fn describe(edges: &[(ThreadId, &RawFusedLock, ThreadId)]) -> String {
+    let mut message = format!(
+        "deadlock: {} threads are waiting for each other's write locks in a cycle",
+        edges.len()
+    );
+    for &(waiter, lock, holder) in edges {
+        write!(
+            message,
+            "\n  {} waits for the lock at {:p}, held by {}",
+            OwnerThread::new(waiter),
+            lock,
+            OwnerThread::new(holder)
+        )
+        .unwrap();
+        if let Some(location) = lock.owner_location() {
+            write!(message, " since {}", location).unwrap();
+        }
+    }
+    message
+}
@@ -0,0 +1,116 @@
+//! Opportunistic background initialization of [registered](crate::registry) cells.
+//!
+//! The warmup thread forces registered cells for at most `budget` per tick and then sleeps for
+//! the same duration, so that it uses at most about half of one core. It stops as soon as another
+//! thread parks on a [RawFusedLock](crate::sync::RawFusedLock), since that indicates the
+//! foreground is contending with the warmup rather than benefiting from it. Counting parks is the
+//! only cost of the `warmup` feature for programs that never start a warmup.
+
+use crate::registry::{registrations, Registration};
+use crate::sync::{park_count, thread_park_count};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// The outcome of a warmup.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WarmupReport {
+    /// The number of cells forced by the warmup thread.
+    pub forced: usize,
+    /// The number of cells that were still uninitialized when the warmup finished.
+    pub remaining: usize,
+    /// Whether the warmup stopped because of foreground contention.
+    pub contended: bool,
+}
+
+/// A running warmup thread.
+pub struct WarmupHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<WarmupReport>,
+}
+
+impl WarmupHandle {
+    /// Wait for the warmup to finish.
+    pub fn join(self) -> WarmupReport {
+        self.thread.join().unwrap()
+    }
+    /// Stop the warmup after the cell currently being forced, and wait for it.
+    pub fn stop(self) -> WarmupReport {
+        self.stop.store(true, Ordering::Relaxed);
+        self.join()
+    }
+}
+
+/// Force all registered cells in the background.
+pub fn warmup_in_background(budget: Duration) -> WarmupHandle {
+    warmup_in_background_with(budget, |_| true)
+}
+
+/// Force the registered cells selected by `filter` in the background.
+pub fn warmup_in_background_with(
+    budget: Duration,
+    filter: impl Fn(&Registration) -> bool + Send + 'static,
+) -> WarmupHandle {
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = thread::Builder::new()
+        .name("safe-once-warmup".to_string())
+        .spawn({
+            let stop = stop.clone();
+            move || warmup(budget, &filter, &stop)
+        })
+        .unwrap();
+    WarmupHandle { stop, thread }
+}
+
+fn foreground_park_count() -> usize {
+    park_count() - thread_park_count()
+}
+
+fn warmup(
+    budget: Duration,
+    filter: &dyn Fn(&Registration) -> bool,
+    stop: &AtomicBool,
+) -> WarmupReport {
+    let mut pending = registrations()
+        .iter()
+        .filter(|r| filter(r))
+        .collect::<Vec<_>>()
+        .into_iter();
+    let mut report = WarmupReport {
+        forced: 0,
+        remaining: 0,
+        contended: false,
+    };
+    let parks = foreground_park_count();
+    let mut tick = Instant::now();
+    for registration in pending.by_ref() {
+        if stop.load(Ordering::Relaxed) {
+            report.remaining += 1;
+            break;
+        }
+        if foreground_park_count() != parks {
+            report.contended = true;
+            report.remaining += 1;
+            break;
+        }
+        if tick.elapsed() >= budget {
+            thread::sleep(budget);
+            tick = Instant::now();
+        }
+        if registration.is_initialized() {
+            continue;
+        }
+        if catch_unwind(AssertUnwindSafe(|| registration.force())).is_ok()
+            && registration.is_initialized()
+        {
+            report.forced += 1;
+        } else {
+            report.remaining += 1;
+        }
+    }
+    report.remaining += pending.filter(|r| !r.is_initialized()).count();
+    report
+}
@@ -0,0 +1,142 @@
+//! A process-wide hook for threads that wait too long for another thread's initializer.
+//!
+//! After [set_slow_init_handler], a thread that has waited longer than the threshold for the write
+//! lock of a [RawFusedLock](crate::sync::RawFusedLock) calls the handler, and calls it again each
+//! time the threshold elapses while it still waits. The wait itself is unaffected.
+//! ```
+//! use safe_once::watchdog::{set_slow_init_handler, SlowInit};
+//! use std::time::Duration;
+//! fn report(slow: &SlowInit) {
+//!     eprintln!(
+//!         "waited {:?} for {} held by {:?}",
+//!         slow.elapsed,
+//!         slow.type_name.unwrap_or("a cell"),
+//!         slow.owner
+//!     );
+//! }
+//! set_slow_init_handler(Duration::from_secs(30), report);
+//! ```
+
+use crate::api::raw::RawFused;
+use crate::sync::OwnerThread;
+use std::cell::Cell;
+use std::panic::Location;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A wait reported to the handler set by [set_slow_init_handler].
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct SlowInit {
+    /// The [registered](crate::registry) name of the static containing the cell, if any.
+    pub name: Option<&'static str>,
+    /// The type of the cell's value, if the wait began in a method that knows it.
+    pub type_name: Option<&'static str>,
+    /// The thread initializing the cell. See [OwnerThread].
+    pub owner: Option<OwnerThread>,
+    /// Where the initializing thread obtained the write lock.
+    pub owner_location: Option<&'static Location<'static>>,
+    /// How long the current thread has waited.
+    pub elapsed: Duration,
+}
+
+type Handler = (Duration, fn(&SlowInit));
+
+static HAS_HANDLER: AtomicBool = AtomicBool::new(false);
+static HANDLER: Mutex<Option<Handler>> = Mutex::new(None);
+
+thread_local!(static TYPE_NAME: Cell<Option<&'static str>> = const { Cell::new(None) });
+
+/// Call `handler` whenever a thread has waited longer than `threshold` for another thread to
+/// initialize a cell, replacing any previous handler.
+pub fn set_slow_init_handler(threshold: Duration, handler: fn(&SlowInit)) {
+    *HANDLER.lock().unwrap_or_else(PoisonError::into_inner) = Some((threshold, handler));
+    HAS_HANDLER.store(true, Relaxed);
+}
+
+/// Remove the handler set by [set_slow_init_handler].
+pub fn clear_slow_init_handler() {
+    *HANDLER.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    HAS_HANDLER.store(false, Relaxed);
+}
+
+fn handler() -> Option<Handler> {
+    if !HAS_HANDLER.load(Relaxed) {
+        return None;
+    }
+    *HANDLER.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Names the type of the cell that the current thread may wait for until dropped.
+pub(crate) struct Waiting(Option<&'static str>);
+
+impl Waiting {
+    pub(crate) fn enter(type_name: &'static str) -> Self {
+        Waiting(TYPE_NAME.replace(Some(type_name)))
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        TYPE_NAME.set(self.0);
+    }
+}
+
+/// The type of the cell that the current thread is waiting for, if known.
+pub(crate) fn waiting_type_name() -> Option<&'static str> {
+    TYPE_NAME.get()
+}
+
+/// The state of one wait for a lock, which reports to the handler each time its threshold
+/// elapses.
+pub(crate) struct Watchdog {
+    start: Option<Instant>,
+    alarm: Option<Instant>,
+}
+
+impl Watchdog {
+    pub(crate) fn new() -> Self {
+        Watchdog {
+            start: None,
+            alarm: None,
+        }
+    }
+
+    /// The time at which to stop parking, given the caller's deadline.
+    pub(crate) fn deadline(&mut self, deadline: Option<Instant>) -> Option<Instant> {
+        let Some((threshold, _)) = handler() else {
+            return deadline;
+        };
+        let start = *self.start.get_or_insert_with(Instant::now);
+        // A threshold too long to represent never raises the alarm.
+        let Some(alarm) = self.alarm.or_else(|| start.checked_add(threshold)) else {
+            return deadline;
+        };
+        self.alarm = Some(alarm);
+        Some(deadline.map_or(alarm, |deadline| deadline.min(alarm)))
+    }
+
+    /// Call the handler if the alarm has passed and `raw` is still write-locked.
+    pub(crate) fn check<R: RawFused>(&mut self, raw: &R) {
+        let (Some(start), Some(alarm)) = (self.start, self.alarm) else {
+            return;
+        };
+        let now = Instant::now();
+        if now < alarm || raw.is_write_locked() == Some(false) {
+            return;
+        }
+        let Some((threshold, handler)) = handler() else {
+            return;
+        };
+        self.alarm = now.checked_add(threshold);
+        handler(&SlowInit {
+            name: crate::registry::find_containing(raw as *const R as *const u8).map(|r| r.name()),
+            type_name: waiting_type_name(),
+            owner: raw.owner_thread(),
+            owner_location: raw.owner_location(),
+            elapsed: now - start,
+        });
+    }
+}
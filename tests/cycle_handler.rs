@@ -0,0 +1,60 @@
+// Installs a process-wide cycle handler, so this runs in its own test binary.
+#![cfg(feature = "std")]
+
+use safe_once::deadlock::{CycleAction, CycleInfo};
+use safe_once::error::LockError;
+use safe_once::sync::{FusedLock, LazyLock};
+use std::panic::catch_unwind;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+
+static CYCLES: Mutex<Vec<(usize, String)>> = Mutex::new(Vec::new());
+
+fn handle(cycle: &CycleInfo) -> CycleAction {
+    CYCLES
+        .lock()
+        .unwrap()
+        .push((cycle.threads, cycle.description.to_string()));
+    CycleAction::Error
+}
+
+#[test]
+fn test_cycle_handler() {
+    safe_once::set_cycle_handler(handle);
+
+    // A method that cannot return an error still panics.
+    static CYCLE: LazyLock<u32> = LazyLock::new(|| *CYCLE + 1);
+    catch_unwind(|| *CYCLE).unwrap_err();
+    let cycles = std::mem::take(&mut *CYCLES.lock().unwrap());
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].0, 1);
+    assert!(cycles[0].1.starts_with("deadlock: write lock"));
+
+    // With deadlock detection, one of the threads in a cycle receives an error instead.
+    if cfg!(feature = "deadlock-detection") {
+        let locks = (FusedLock::new(0), FusedLock::new(0));
+        let barrier = Barrier::new(2);
+        let results = thread::scope(|s| {
+            let threads = [(&locks.0, &locks.1), (&locks.1, &locks.0)].map(|(mine, theirs)| {
+                let barrier = &barrier;
+                s.spawn(move || {
+                    let _guard = mine.write();
+                    barrier.wait();
+                    theirs.write_checked().map(|_| ()).map_err(|e| match e {
+                        LockError::Cycle { .. } => "cycle",
+                        _ => "poisoned",
+                    })
+                })
+            });
+            threads.map(|t| t.join().unwrap())
+        });
+        assert!(results.contains(&Ok(())), "{:?}", results);
+        assert!(results.contains(&Err("cycle")), "{:?}", results);
+        let cycles = CYCLES.lock().unwrap();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].0, 2);
+        assert!(cycles[0]
+            .1
+            .starts_with("deadlock: 2 threads are waiting for each other's write locks"));
+    }
+}
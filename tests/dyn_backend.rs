@@ -0,0 +1,55 @@
+// Depends on which thread first uses the backend, so this runs in its own test binary.
+#![cfg(feature = "dyn-backend")]
+
+use safe_once::api::fused::FusedEntry;
+use safe_once::sync::dynamic::{FusedLock, LazyLock, OnceLock};
+use safe_once::sync::DynRawFused;
+use std::sync::Barrier;
+use std::thread;
+use std::time::Duration;
+
+static LAZY: LazyLock<usize> = LazyLock::new(|| 42);
+
+#[test]
+fn test_single_threaded_then_shared() {
+    let onces = (0..100).map(|_| OnceLock::new()).collect::<Vec<_>>();
+    for (i, once) in onces.iter().enumerate() {
+        assert_eq!(*once.get_or_init(|| i), i);
+    }
+    assert_eq!(*LAZY, 42);
+    if cfg!(any(target_os = "linux", windows)) {
+        assert!(DynRawFused::single_threaded());
+    }
+
+    // A lock held on the fast path is observed by the thread that revokes it.
+    let fused = FusedLock::<usize>::new(0);
+    let pending = (0..100).map(|_| OnceLock::new()).collect::<Vec<_>>();
+    let barrier = Barrier::new(5);
+    thread::scope(|s| {
+        let FusedEntry::Write(mut guard) = fused.write() else {
+            unreachable!()
+        };
+        let waiter = s.spawn(|| *fused.read_or_fuse(|_| unreachable!()));
+        for i in 0..4 {
+            let pending = &pending;
+            let barrier = &barrier;
+            s.spawn(move || {
+                for once in pending {
+                    barrier.wait();
+                    once.get_or_init(|| i);
+                }
+            });
+        }
+        for once in &pending {
+            barrier.wait();
+            once.get_or_init(|| 4);
+        }
+        thread::sleep(Duration::from_millis(10));
+        *guard = 1;
+        guard.fuse();
+        assert_eq!(waiter.join().unwrap(), 1);
+    });
+    assert!(pending.iter().all(|x| x.try_get().is_some()));
+    assert!(!DynRawFused::single_threaded());
+    assert_eq!(*onces[7].get_or_init(|| unreachable!()), 7);
+}
@@ -0,0 +1,27 @@
+// Depends on which thread first uses the backend, so this runs in its own test binary.
+#![cfg(feature = "dyn-backend")]
+
+use safe_once::api::once::OnceEntry;
+use safe_once::sync::dynamic::OnceLock;
+use safe_once::sync::DynRawFused;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_wait_for_single_threaded_owner() {
+    let once = OnceLock::<usize>::new();
+    let OnceEntry::Vacant(guard) = once.lock() else {
+        unreachable!()
+    };
+    if cfg!(any(target_os = "linux", windows)) {
+        assert!(DynRawFused::single_threaded());
+    }
+    thread::scope(|s| {
+        // The waiter revokes the fast path before parking, so the owner's unlock wakes it.
+        let waiter = s.spawn(|| *once.wait());
+        thread::sleep(Duration::from_millis(10));
+        guard.init(5);
+        assert_eq!(waiter.join().unwrap(), 5);
+    });
+    assert!(!DynRawFused::single_threaded());
+}
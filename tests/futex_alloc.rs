@@ -0,0 +1,59 @@
+// Installs a global allocator, so this runs in its own test binary.
+#![cfg(feature = "futex")]
+
+use safe_once::sync::futex::{LazyLock, OnceLock};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Barrier;
+use std::thread;
+use std::time::Duration;
+
+thread_local!(static ALLOCATIONS: Cell<usize> = const { Cell::new(0) });
+
+// Forced from within the allocator, which would recurse if the lock allocated.
+static TOTAL: LazyLock<AtomicUsize> = LazyLock::new(|| AtomicUsize::new(0));
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|x| x.set(x.get() + 1));
+        TOTAL.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|x| x.get())
+}
+
+#[test]
+fn test_futex_no_alloc() {
+    let once = OnceLock::<usize>::new();
+    let barrier = Barrier::new(2);
+    thread::scope(|s| {
+        let initializer = s.spawn(|| {
+            let before = allocations();
+            once.get_or_init(|| {
+                barrier.wait();
+                thread::sleep(Duration::from_millis(50));
+                1
+            });
+            allocations() - before
+        });
+        barrier.wait();
+        let before = allocations();
+        assert_eq!(*once.get_or_init(|| unreachable!()), 1);
+        assert_eq!(allocations() - before, 0);
+        assert_eq!(initializer.join().unwrap(), 0);
+    });
+    assert!(TOTAL.load(Ordering::Relaxed) > 0);
+}
@@ -0,0 +1,55 @@
+// Installs a global logger, so this runs in its own test binary.
+#![cfg(feature = "log")]
+
+use log::{LevelFilter, Log, Metadata, Record};
+use safe_once::sync::{LazyLock, OnceLock};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Mutex;
+
+// Records each message with its level.
+struct Recorder;
+
+static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+impl Log for Recorder {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+    fn log(&self, record: &Record) {
+        RECORDS
+            .lock()
+            .unwrap()
+            .push(format!("{} {}", record.level(), record.args()));
+    }
+    fn flush(&self) {}
+}
+
+fn take() -> Vec<String> {
+    std::mem::take(&mut *RECORDS.lock().unwrap())
+}
+
+#[test]
+fn test_log() {
+    log::set_logger(&Recorder).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let once = OnceLock::<u32>::new();
+    once.get_or_init(|| 1);
+    let records = take();
+    assert_eq!(records.len(), 1);
+    assert!(records[0].starts_with("DEBUG 0x"));
+    assert!(records[0].contains(": u32 initialized in "));
+
+    let lazy = LazyLock::<u32>::new(|| panic!("failed"));
+    catch_unwind(AssertUnwindSafe(|| *lazy)).unwrap_err();
+    let records = take();
+    assert_eq!(records.len(), 1);
+    assert!(records[0].starts_with("WARN 0x"));
+    assert!(records[0].contains(": u32 initializer panicked after "));
+
+    static CYCLE: LazyLock<u32> = LazyLock::new(|| *CYCLE + 1);
+    catch_unwind(|| *CYCLE).unwrap_err();
+    let records = take();
+    assert!(records[0].starts_with("WARN deadlock: write lock obtained at "));
+    assert!(records[0].contains(": u32 requested again at "));
+}
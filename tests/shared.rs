@@ -0,0 +1,73 @@
+// Forks, so this runs in its own test binary.
+#![cfg(all(feature = "shared", target_os = "linux"))]
+
+use safe_once::error::TryLockError;
+use safe_once::shared::SharedOnceLock;
+use std::ptr::null_mut;
+
+// Map a SharedOnceLock that forked children share with this process.
+fn map<'a>() -> &'a SharedOnceLock<u64> {
+    unsafe {
+        let ptr = libc::mmap(
+            null_mut(),
+            size_of::<SharedOnceLock<u64>>(),
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        assert_ne!(ptr, libc::MAP_FAILED);
+        SharedOnceLock::init_in_place(ptr as *mut SharedOnceLock<u64>).unwrap()
+    }
+}
+
+// Run `child` in a forked process and wait for it to exit.
+fn fork(child: impl FnOnce()) {
+    unsafe {
+        let pid = libc::fork();
+        assert!(pid >= 0);
+        if pid == 0 {
+            child();
+            libc::_exit(0);
+        }
+        let mut status = 0;
+        assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+    }
+}
+
+#[test]
+fn test_shared_once_lock() {
+    let once = map();
+    assert_eq!(once.try_get(), None);
+    fork(|| {
+        once.get_or_init(|| 42);
+    });
+    assert_eq!(once.try_get(), Some(&42));
+    assert_eq!(*once.get_or_init(|| unreachable!()), 42);
+}
+
+#[test]
+fn test_shared_owner_died() {
+    let once = map();
+    fork(|| {
+        once.get_or_init(|| unsafe { libc::_exit(1) });
+    });
+    assert!(matches!(
+        once.get_or_init_checked(|| unreachable!()),
+        Err(TryLockError::Poisoned(_))
+    ));
+    assert!(once.try_get_checked().is_err());
+}
+
+#[test]
+fn test_shared_reentrant() {
+    let once = map();
+    let result = once.get_or_init_checked(|| {
+        assert!(matches!(
+            once.get_or_init_checked(|| 2),
+            Err(TryLockError::WouldBlock)
+        ));
+        1
+    });
+    assert_eq!(result.ok(), Some(&1));
+}
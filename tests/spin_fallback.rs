@@ -0,0 +1,39 @@
+// Disabling parking is process-wide, so this runs in its own test binary.
+#![cfg(feature = "std")]
+
+use safe_once::observer::{set_observer, Event};
+use safe_once::sync::{disable_parking, parking_disabled, OnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+static EVENTS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn test_spin_fallback() {
+    set_observer(|_: &Event| {
+        EVENTS.fetch_add(1, Ordering::Relaxed);
+    });
+    disable_parking();
+    assert!(parking_disabled());
+    let once = Arc::new(OnceLock::<usize>::new());
+    let barrier = Arc::new(Barrier::new(4));
+    let threads = (0..4)
+        .map(|i| {
+            let once = once.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                *once.get_or_init(|| {
+                    thread::sleep(Duration::from_millis(50));
+                    i
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        assert_eq!(Some(&t.join().unwrap()), once.try_get());
+    }
+    assert_eq!(EVENTS.load(Ordering::Relaxed), 0);
+}
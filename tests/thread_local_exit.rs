@@ -0,0 +1,50 @@
+// Thread-local destructors are shared by the process, so this runs in its own test binary.
+#![cfg(feature = "std")]
+
+use safe_once::sync::LazyThreadLocal;
+use std::cell::Cell;
+use std::thread;
+
+static COUNTERS: LazyThreadLocal<Cell<u64>> = LazyThreadLocal::new(|| Cell::new(0));
+
+// Reads the counter of its thread from a thread-local destructor.
+struct Exit(&'static Cell<u64>);
+
+impl Drop for Exit {
+    fn drop(&mut self) {
+        assert!(std::ptr::eq(self.0, COUNTERS.get()));
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+thread_local! {
+    static EXIT: Exit = Exit(COUNTERS.get());
+}
+
+#[test]
+fn test_thread_local_exit() {
+    let local = LazyThreadLocal::new(|| thread::current().id());
+    let main = *local;
+    let mut ids = vec![main];
+    for _ in 0..10 {
+        // Each thread exits before the next starts, and keeps a value of its own.
+        ids.push(thread::scope(|s| s.spawn(|| *local.get()).join().unwrap()));
+    }
+    assert_eq!(local.into_vec(), ids);
+
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                let counter = COUNTERS.get();
+                counter.set(1);
+                EXIT.with(|exit| assert!(std::ptr::eq(exit.0, counter)));
+            });
+        }
+    });
+    for _ in 0..4 {
+        // A later thread never sees the counter of an exited one.
+        thread::spawn(|| assert_eq!(COUNTERS.get().get(), 0))
+            .join()
+            .unwrap();
+    }
+}
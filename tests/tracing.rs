@@ -0,0 +1,94 @@
+// Installs a global tracing subscriber, so this runs in its own test binary.
+#![cfg(feature = "tracing")]
+
+use safe_once::sync::{FusedLock, LazyLock, OnceLock};
+use std::fmt::Debug;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+// Records each span and event as its message or name followed by its fields.
+struct Recorder {
+    next_id: AtomicU64,
+}
+
+static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else if field.name() != "elapsed" && field.name() != "address" {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+    fn new_span(&self, span: &Attributes) -> Id {
+        let mut fields = Fields(span.metadata().name().to_string());
+        span.record(&mut fields);
+        RECORDS.lock().unwrap().push(fields.0);
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+    fn record(&self, _: &Id, _: &Record) {}
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, event: &Event) {
+        let mut fields = Fields(String::new());
+        event.record(&mut fields);
+        RECORDS.lock().unwrap().push(fields.0);
+    }
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
+
+fn take() -> Vec<String> {
+    std::mem::take(&mut *RECORDS.lock().unwrap())
+}
+
+#[test]
+fn test_tracing() {
+    tracing::subscriber::set_global_default(Recorder {
+        next_id: AtomicU64::new(1),
+    })
+    .unwrap();
+    take();
+
+    let once = OnceLock::<u32>::new();
+    assert_eq!(*once.get_or_init(|| 1), 1);
+    assert_eq!(
+        take(),
+        vec!["safe_once::init type_name=\"u32\"", "initialized"]
+    );
+    once.get_or_init(|| unreachable!());
+    assert_eq!(take(), Vec::<String>::new());
+
+    let lazy = LazyLock::<u32>::new(|| panic!("failed"));
+    catch_unwind(AssertUnwindSafe(|| *lazy)).unwrap_err();
+    assert_eq!(
+        take(),
+        vec![
+            "safe_once::init type_name=\"u32\"",
+            "initializer panicked, poisoning the cell"
+        ]
+    );
+
+    let fused = FusedLock::new(1u8);
+    catch_unwind(AssertUnwindSafe(|| {
+        let _entry = fused.write();
+        panic!("failed");
+    }))
+    .unwrap_err();
+    assert_eq!(
+        take(),
+        vec!["write guard dropped while panicking, poisoning the cell type_name=\"u8\""]
+    );
+}
@@ -0,0 +1,28 @@
+// Counts parks across the process, so this runs in its own test binary.
+#![cfg(feature = "warmup")]
+
+use safe_once::register;
+use safe_once::sync::{LazyLock, OnceLock};
+use safe_once::warmup::{warmup_in_background, WarmupReport};
+use std::time::Duration;
+
+static WARMUP_A: LazyLock<usize> = LazyLock::new(|| 1);
+static WARMUP_B: LazyLock<usize> = LazyLock::new(|| panic!());
+static WARMUP_C: OnceLock<usize> = OnceLock::new();
+register!(WARMUP_A);
+register!(WARMUP_B);
+register!(WARMUP_C);
+
+#[test]
+fn test_warmup() {
+    let report = warmup_in_background(Duration::from_millis(10)).join();
+    assert_eq!(
+        report,
+        WarmupReport {
+            forced: 1,
+            remaining: 2,
+            contended: false,
+        }
+    );
+    assert_eq!(WARMUP_A.try_get(), Some(&1));
+}
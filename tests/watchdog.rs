@@ -0,0 +1,66 @@
+// Installs a process-wide handler, so this runs in its own test binary.
+#![cfg(feature = "std")]
+
+use safe_once::sync::LazyLock;
+use safe_once::watchdog::SlowInit;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+use std::time::Duration;
+
+struct Config(u32);
+
+static CONFIG: LazyLock<Config> = LazyLock::new(|| {
+    STARTED.wait();
+    thread::sleep(Duration::from_millis(100));
+    Config(1)
+});
+
+static SLOW: LazyLock<Config> = LazyLock::new(|| {
+    STARTED.wait();
+    thread::sleep(Duration::from_millis(50));
+    Config(2)
+});
+
+static STARTED: Barrier = Barrier::new(2);
+
+static REPORTS: Mutex<Vec<SlowInit>> = Mutex::new(Vec::new());
+
+fn report(slow: &SlowInit) {
+    REPORTS.lock().unwrap().push(slow.clone());
+}
+
+#[test]
+fn test_slow_init_handler() {
+    safe_once::set_slow_init_handler(Duration::from_millis(20), report);
+    thread::scope(|s| {
+        thread::Builder::new()
+            .name("config-loader".to_string())
+            .spawn_scoped(s, || CONFIG.0)
+            .unwrap();
+        STARTED.wait();
+        assert_eq!(CONFIG.0, 1);
+    });
+    let reports = REPORTS.lock().unwrap();
+    assert!(!reports.is_empty());
+    for (i, slow) in reports.iter().enumerate() {
+        assert!(slow.type_name.unwrap().ends_with("Config"), "{:?}", slow);
+        assert_eq!(slow.owner.as_ref().unwrap().name(), Some("config-loader"));
+        assert_eq!(
+            slow.owner_location.is_some(),
+            cfg!(feature = "owner-location")
+        );
+        assert!(slow.elapsed >= Duration::from_millis(20) * (i as u32 + 1));
+    }
+    let count = reports.len();
+    drop(reports);
+
+    // A threshold too long to represent never fires.
+    safe_once::set_slow_init_handler(Duration::MAX, report);
+    thread::scope(|s| {
+        s.spawn(|| SLOW.0);
+        STARTED.wait();
+        assert_eq!(SLOW.0, 2);
+    });
+    assert_eq!(REPORTS.lock().unwrap().len(), count);
+    safe_once::watchdog::clear_slow_init_handler();
+}